@@ -1,6 +1,6 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_prost_build::configure()
-        .build_server(false) // only build client code
+        .build_server(true) // the server is only used by test stubs
         .compile_protos(
             &["src/workflow/actions/agent/proto/agent.proto"],
             &["src/workflow/actions/agent/proto"],
@@ -1,12 +1,26 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use tokio::runtime::{Builder, Runtime};
 
-use crate::{Engine, Result};
+use crate::{
+    ChannelEvent, ChannelOptions, Clock, Config, Engine, GraphEvent, Result,
+    engine::{ProcessRetention, WorkflowLimits},
+    runtime::DEFAULT_EVENT_HISTORY,
+    workflow::template::{DEFAULT_TEMPLATE_DEPTH, NumberFormat},
+};
 
 pub struct EngineBuilder {
     async_worker_thread_number: u16,
     rt: Option<Arc<Runtime>>,
+    stdout_logger: bool,
+    limits: WorkflowLimits,
+    retention: ProcessRetention,
+    event_history: usize,
+    clock: Option<Arc<dyn Clock>>,
+    deterministic: bool,
+    template_depth: usize,
+    number_format: NumberFormat,
+    evict_consumed_outputs: bool,
 }
 
 impl Default for EngineBuilder {
@@ -14,6 +28,15 @@ impl Default for EngineBuilder {
         Self {
             async_worker_thread_number: 16,
             rt: None,
+            stdout_logger: false,
+            limits: WorkflowLimits::default(),
+            retention: ProcessRetention::default(),
+            event_history: DEFAULT_EVENT_HISTORY,
+            clock: None,
+            deterministic: false,
+            template_depth: DEFAULT_TEMPLATE_DEPTH,
+            number_format: NumberFormat::default(),
+            evict_consumed_outputs: false,
         }
     }
 }
@@ -23,6 +46,19 @@ impl EngineBuilder {
         Self::default()
     }
 
+    /// Creates a builder with the settings of a loaded [`Config`].
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            async_worker_thread_number: config.async_worker_thread_number,
+            limits: WorkflowLimits {
+                max_nodes: config.limits.max_nodes,
+                max_edges: config.limits.max_edges,
+            },
+            event_history: config.event_history,
+            ..Self::default()
+        }
+    }
+
     pub fn async_worker_thread_number(
         mut self,
         n: u16,
@@ -39,14 +75,207 @@ impl EngineBuilder {
         self
     }
 
+    /// Prints every workflow/node transition and log line to stdout.
+    ///
+    /// Disabled by default; useful when getting started or debugging.
+    pub fn with_stdout_logger(mut self) -> Self {
+        self.stdout_logger = true;
+        self
+    }
+
+    /// Rejects workflows with more than `n` nodes when building processes.
+    pub fn max_nodes(
+        mut self,
+        n: usize,
+    ) -> Self {
+        self.limits.max_nodes = Some(n);
+        self
+    }
+
+    /// Rejects workflows with more than `n` edges when building processes.
+    pub fn max_edges(
+        mut self,
+        n: usize,
+    ) -> Self {
+        self.limits.max_edges = Some(n);
+        self
+    }
+
+    /// Keeps completed processes fetchable through `Engine::get_process` for
+    /// `ttl` after they complete, measured on the engine's clock.
+    ///
+    /// A process completes when it succeeds, fails or is aborted. By default it
+    /// leaves the engine's cache as soon as it completes.
+    pub fn retain_completed_for(
+        mut self,
+        ttl: Duration,
+    ) -> Self {
+        self.retention.ttl = Some(ttl);
+        self
+    }
+
+    /// Keeps at most the `n` most recently completed processes fetchable
+    /// through `Engine::get_process`, evicting the oldest first.
+    ///
+    /// Combined with [`retain_completed_for`](Self::retain_completed_for), a
+    /// process is evicted by whichever limit it reaches first.
+    pub fn max_completed_processes(
+        mut self,
+        n: usize,
+    ) -> Self {
+        self.retention.max_completed = Some(n);
+        self
+    }
+
+    /// Keeps the last `n` events of each process, readable through
+    /// `Process::recent_events`. Defaults to 100; 0 disables recording.
+    pub fn event_history(
+        mut self,
+        n: usize,
+    ) -> Self {
+        self.event_history = n;
+        self
+    }
+
+    /// Uses `clock` for timestamps, node timeouts and retry delays instead
+    /// of the system clock, e.g. a [`MockClock`](crate::MockClock) in tests.
+    pub fn clock(
+        mut self,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Makes every run of a workflow produce the same event sequence.
+    ///
+    /// Nodes run one at a time in topological order, processes are numbered
+    /// `process-1`, `process-2`, ... and each process reads time from its own
+    /// [`LogicalClock`](crate::LogicalClock), replacing any configured clock.
+    /// Intended for tests.
+    pub fn deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    /// Re-resolves template values that contain templates themselves, e.g. a
+    /// configuration fetched from an API, making at most `depth` passes in total.
+    ///
+    /// Defaults to 1: resolved values are used as-is.
+    pub fn template_depth(
+        mut self,
+        depth: usize,
+    ) -> Self {
+        self.template_depth = depth;
+        self
+    }
+
+    /// Sets how numbers are rendered when substituted into template strings,
+    /// e.g. [`NumberFormat::Integer`] to render a float `1.0` as `1` in URLs.
+    ///
+    /// Defaults to [`NumberFormat::Json`]. Values that templates resolve to as
+    /// a whole, such as JSON bodies, keep their numbers as-is.
+    pub fn number_format(
+        mut self,
+        number_format: NumberFormat,
+    ) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Frees memory during long runs by dropping a node's outputs once every
+    /// node reading them has completed: its successors and the nodes whose
+    /// templates reference it.
+    ///
+    /// Evicted outputs are missing from `Process::get_outputs`; outputs of
+    /// nodes nobody reads from, such as the last node, are kept.
+    pub fn evict_consumed_outputs(mut self) -> Self {
+        self.evict_consumed_outputs = true;
+        self
+    }
+
     pub fn build(&self) -> Result<Engine> {
-        let runtime = if self.rt.is_some() {
-            self.rt.as_ref().unwrap().clone()
+        let runtime = if let Some(rt) = &self.rt {
+            rt.clone()
         } else {
             Arc::new(Builder::new_multi_thread().worker_threads(self.async_worker_thread_number.into()).enable_all().build().unwrap())
         };
-        let engine = Engine::new(runtime);
+        let mut engine = Engine::new(runtime)
+            .with_limits(self.limits)
+            .with_retention(self.retention)
+            .with_event_history(self.event_history)
+            .with_template_depth(self.template_depth)
+            .with_number_format(self.number_format);
+        if let Some(clock) = &self.clock {
+            engine = engine.with_clock(clock.clone());
+        }
+        if self.deterministic {
+            engine = engine.with_deterministic();
+        }
+        if self.evict_consumed_outputs {
+            engine = engine.with_evict_consumed_outputs();
+        }
+
+        if self.stdout_logger {
+            let subscriber = ChannelEvent::channel(engine.channel(), ChannelOptions::default());
+            subscriber.on_event(|e| match &e.event {
+                GraphEvent::Workflow(event) => println!("[{}] workflow {}", e.pid, event.str()),
+                GraphEvent::Node(event) => println!("[{}] node {} {}", e.pid, e.nid, event.str()),
+            });
+            subscriber.on_log(|log| println!("[{}] node {} log: {}", log.pid, log.nid, log.content));
+        }
 
         Ok(engine)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use serde_json::json;
+
+    use crate::{ChannelEvent, ChannelOptions, EdgeModel, EngineBuilder, NodeModel, WorkflowModel};
+
+    #[test]
+    fn test_stdout_logger_keeps_user_subscribers() {
+        let engine = EngineBuilder::new().with_stdout_logger().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "logger".to_string(),
+            nodes: vec![
+                NodeModel {
+                    id: "n1".to_string(),
+                    uses: "start".to_string(),
+                    action: json!({}),
+                    ..Default::default()
+                },
+                NodeModel {
+                    id: "n2".to_string(),
+                    uses: "end".to_string(),
+                    action: json!({}),
+                    ..Default::default()
+                },
+            ],
+            edges: vec![EdgeModel {
+                id: "e1".to_string(),
+                source: "n1".to_string(),
+                target: "n2".to_string(),
+                source_handle: "source".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_complete(move |pid| {
+            let _ = tx.send(pid);
+        });
+        process.start();
+
+        let pid = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(pid, process.id());
+    }
+}
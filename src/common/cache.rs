@@ -47,6 +47,21 @@ where
         self.variables.get(key)
     }
 
+    /// Get the value for `key`, or insert the one produced by `init`.
+    ///
+    /// Concurrent callers for the same key wait for a single `init` to finish
+    /// instead of each inserting their own value.
+    pub fn try_get_with<E>(
+        &self,
+        key: K,
+        init: impl FnOnce() -> Result<V, E>,
+    ) -> Result<V, E>
+    where
+        E: Clone + Send + Sync + 'static,
+    {
+        self.variables.try_get_with(key, init).map_err(|e| (*e).clone())
+    }
+
     /// Remove environment variables through key `&K`.
     pub fn remove(
         &self,
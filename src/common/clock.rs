@@ -0,0 +1,176 @@
+//! Time sources for workflow execution.
+//!
+//! Timestamps, node timeouts and retry delays read time through a [`Clock`],
+//! so tests can drive them with a [`MockClock`] instead of sleeping.
+
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::Duration,
+};
+
+use futures::{FutureExt, future::BoxFuture};
+use tokio::sync::oneshot;
+
+use crate::utils;
+
+/// Source of the current time and of timers.
+pub trait Clock: Send + Sync {
+    /// Returns the current time in milliseconds since the Unix epoch.
+    fn now_millis(&self) -> i64;
+
+    /// Returns a future that completes once `duration` has elapsed on this clock.
+    fn sleep(
+        &self,
+        duration: Duration,
+    ) -> BoxFuture<'static, ()>;
+}
+
+/// Wall-clock time backed by the system clock and tokio timers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        utils::time::time_millis()
+    }
+
+    fn sleep(
+        &self,
+        duration: Duration,
+    ) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// Clock whose readings are a counter advancing by one on every reading.
+///
+/// Timestamps then depend only on the order in which they are taken, which
+/// makes them reproducible for serialized runs. Sleeps use real tokio timers.
+#[derive(Debug, Default)]
+pub struct LogicalClock {
+    ticks: AtomicI64,
+}
+
+impl Clock for LogicalClock {
+    fn now_millis(&self) -> i64 {
+        self.ticks.fetch_add(1, Ordering::SeqCst)
+    }
+
+    fn sleep(
+        &self,
+        duration: Duration,
+    ) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// Manually driven clock for tests.
+///
+/// Time only moves when [`advance`](MockClock::advance) is called, which also
+/// completes every pending sleep whose deadline has been reached.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let clock = Arc::new(MockClock::new(0));
+/// let engine = EngineBuilder::new().clock(clock.clone()).build()?;
+/// // ... start a process whose node has a 1s timeout ...
+/// clock.advance(Duration::from_secs(1)); // the node times out immediately
+/// ```
+#[derive(Default)]
+pub struct MockClock {
+    state: Mutex<MockClockState>,
+}
+
+#[derive(Default)]
+struct MockClockState {
+    now: i64,
+    sleepers: Vec<(i64, oneshot::Sender<()>)>,
+}
+
+impl MockClock {
+    /// Creates a clock reading `now_millis`.
+    pub fn new(now_millis: i64) -> Self {
+        Self {
+            state: Mutex::new(MockClockState {
+                now: now_millis,
+                sleepers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Moves the clock forward, waking the sleeps that are now due.
+    pub fn advance(
+        &self,
+        duration: Duration,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.now += duration.as_millis() as i64;
+
+        let now = state.now;
+        let (due, pending) = std::mem::take(&mut state.sleepers).into_iter().partition(|(deadline, _)| *deadline <= now);
+        state.sleepers = pending;
+        for (_, tx) in due {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Returns the number of sleeps not yet due.
+    pub fn pending_sleeps(&self) -> usize {
+        self.state.lock().unwrap().sleepers.len()
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> i64 {
+        self.state.lock().unwrap().now
+    }
+
+    fn sleep(
+        &self,
+        duration: Duration,
+    ) -> BoxFuture<'static, ()> {
+        if duration.is_zero() {
+            return futures::future::ready(()).boxed();
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let mut state = self.state.lock().unwrap();
+        let deadline = state.now + duration.as_millis() as i64;
+        state.sleepers.push((deadline, tx));
+
+        async move {
+            let _ = rx.await;
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_wakes_due_sleeps() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let clock = Arc::new(MockClock::new(1_000));
+
+        let short = runtime.spawn(clock.sleep(Duration::from_millis(500)));
+        let long = runtime.spawn(clock.sleep(Duration::from_secs(2)));
+        assert_eq!(clock.pending_sleeps(), 2);
+
+        clock.advance(Duration::from_millis(600));
+        assert_eq!(clock.now_millis(), 1_600);
+        runtime.block_on(short).unwrap();
+        assert!(!long.is_finished());
+        assert_eq!(clock.pending_sleeps(), 1);
+
+        clock.advance(Duration::from_secs(2));
+        runtime.block_on(long).unwrap();
+    }
+}
@@ -1,9 +1,11 @@
 mod cache;
+mod clock;
 mod queue;
 mod shutdown;
 mod vars;
 
 pub use cache::MemCache;
+pub use clock::{Clock, LogicalClock, MockClock, SystemClock};
 pub use queue::{BroadcastQueue, Queue};
 pub use shutdown::Shutdown;
 pub use vars::Vars;
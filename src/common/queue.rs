@@ -2,7 +2,10 @@
 //!
 //! Provides both point-to-point (Queue) and broadcast (BroadcastQueue) messaging.
 
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
 
 use tokio::sync::broadcast;
 
@@ -64,6 +67,8 @@ impl<T> Queue<T> {
 #[derive(Clone)]
 pub struct BroadcastQueue<T> {
     sender: Arc<broadcast::Sender<T>>,
+    /// Number of messages delivered to at least one subscriber.
+    sent: Arc<AtomicU64>,
 }
 
 impl<T: Clone> BroadcastQueue<T> {
@@ -73,6 +78,7 @@ impl<T: Clone> BroadcastQueue<T> {
 
         Arc::new(Self {
             sender: Arc::new(tx),
+            sent: Arc::new(AtomicU64::new(0)),
         })
     }
 
@@ -82,9 +88,15 @@ impl<T: Clone> BroadcastQueue<T> {
         msg: T,
     ) -> Result<()> {
         self.sender.send(msg).map_err(|e| ActflowError::Queue(e.to_string()))?;
+        self.sent.fetch_add(1, Ordering::SeqCst);
         Ok(())
     }
 
+    /// number of messages sent to at least one subscriber
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::SeqCst)
+    }
+
     /// subscribe to the queue
     pub fn subscribe(&self) -> broadcast::Receiver<T> {
         self.sender.subscribe()
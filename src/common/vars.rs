@@ -18,7 +18,7 @@ use serde_json::{Map, Value, json};
 /// # Example
 ///
 /// ```rust
-/// use actflow::common::Vars;
+/// use actflow::Vars;
 ///
 /// let mut vars = Vars::new();
 /// vars.set("name", "John");
@@ -31,7 +31,7 @@ pub struct Vars {
     inner: Map<String, Value>,
 }
 
-#[allow(dead_code)]
+/// Iterator over the entries of a [`Vars`], in key order.
 pub struct Iter<'a> {
     iter: serde_json::map::Iter<'a>,
 }
@@ -202,15 +202,46 @@ impl Vars {
     where
         T: for<'de> Deserialize<'de> + Clone,
     {
-        if let Some(value) = self.inner.get(name) {
-            if let Ok(value) = serde_json::from_value::<T>(value.clone()) {
-                return Some(value);
-            }
+        if let Some(value) = self.inner.get(name)
+            && let Ok(value) = serde_json::from_value::<T>(value.clone())
+        {
+            return Some(value);
         }
 
         None
     }
 
+    /// Returns the keys, in sorted order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use actflow::Vars;
+    ///
+    /// let vars = Vars::new().with("b", 2).with("a", 1);
+    /// assert_eq!(vars.keys().collect::<Vec<_>>(), ["a", "b"]);
+    /// ```
+    pub fn keys(&self) -> serde_json::map::Keys<'_> {
+        self.inner.keys()
+    }
+
+    /// Returns the entries, in the same order as [`Vars::keys`].
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            iter: self.inner.iter(),
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     pub fn get_value(
         &self,
         name: &str,
@@ -239,6 +270,57 @@ impl Vars {
     ) {
         self.inner.append(&mut vars.inner);
     }
+
+    /// Applies a JSON Merge Patch (RFC 7386).
+    ///
+    /// Keys set to `null` in `patch` are removed, nested objects are merged
+    /// recursively and any other value replaces the existing one. Since vars
+    /// are always an object, a patch that is not an object is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use actflow::Vars;
+    /// use serde_json::json;
+    ///
+    /// let mut vars = Vars::from(json!({ "a": 1, "b": { "c": 2 } }));
+    /// vars.merge_patch(&json!({ "a": null, "b": { "d": 3 } }));
+    ///
+    /// assert_eq!(serde_json::Value::from(vars), json!({ "b": { "c": 2, "d": 3 } }));
+    /// ```
+    pub fn merge_patch(
+        &mut self,
+        patch: &Value,
+    ) {
+        if let Value::Object(patch) = patch {
+            merge_patch_object(&mut self.inner, patch);
+        }
+    }
+}
+
+fn merge_patch_object(
+    target: &mut Map<String, Value>,
+    patch: &Map<String, Value>,
+) {
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(key);
+            }
+            Value::Object(patch) => {
+                let entry = target.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+                if !entry.is_object() {
+                    *entry = Value::Object(Map::new());
+                }
+                if let Value::Object(entry) = entry {
+                    merge_patch_object(entry, patch);
+                }
+            }
+            value => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
 }
 
 #[allow(unused)]
@@ -303,8 +385,72 @@ fn from_json_number(n: &serde_json::Number) -> Value {
     if n.is_i64() {
         Value::Number(serde_json::Number::from(n.as_i64().unwrap()))
     } else if n.is_u64() {
-        return Value::Number(serde_json::Number::from(n.as_u64().unwrap()));
+        Value::Number(serde_json::Number::from(n.as_u64().unwrap()))
     } else {
-        return Value::Number(serde_json::Number::from_f64(n.as_f64().unwrap()).unwrap());
+        Value::Number(serde_json::Number::from_f64(n.as_f64().unwrap()).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enumerate_entries() {
+        let mut vars = Vars::new();
+        assert!(vars.is_empty());
+
+        vars.set("zeta", 3);
+        vars.set("alpha", "first");
+        vars.set("mid", json!({ "nested": true }));
+        vars.set("alpha", "replaced");
+
+        assert_eq!(vars.len(), 3);
+        assert!(!vars.is_empty());
+        // Keys come back sorted, whatever the insertion order
+        assert_eq!(vars.keys().map(String::as_str).collect::<Vec<_>>(), ["alpha", "mid", "zeta"]);
+        assert_eq!(
+            vars.iter().map(|(k, v)| (k.as_str(), v.clone())).collect::<Vec<_>>(),
+            [("alpha", json!("replaced")), ("mid", json!({ "nested": true })), ("zeta", json!(3))]
+        );
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        let mut vars = Vars::from(json!({
+            "title": "Goodbye!",
+            "author": { "givenName": "John", "familyName": "Doe" },
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        }));
+
+        vars.merge_patch(&json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": { "familyName": null, "address": { "city": "Berlin" } },
+            "tags": ["example"]
+        }));
+
+        assert_eq!(
+            Value::from(vars),
+            json!({
+                "title": "Hello!",
+                "author": { "givenName": "John", "address": { "city": "Berlin" } },
+                "tags": ["example"],
+                "content": "This will be unchanged",
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_patch_replaces_non_objects() {
+        let mut vars = Vars::from(json!({ "a": "text", "b": { "c": 1 } }));
+
+        vars.merge_patch(&json!({ "a": { "nested": true }, "b": 2, "missing": null }));
+        assert_eq!(Value::from(vars.clone()), json!({ "a": { "nested": true }, "b": 2 }));
+
+        vars.merge_patch(&json!(["not", "an", "object"]));
+        assert_eq!(Value::from(vars), json!({ "a": { "nested": true }, "b": 2 }));
     }
 }
@@ -6,23 +6,49 @@ use std::{fs, path::Path};
 
 use serde::Deserialize;
 
+use crate::{ActflowError, Result, runtime::DEFAULT_EVENT_HISTORY};
+
 /// Main configuration for the Actflow engine.
 ///
+/// Every field is optional; unknown keys are rejected so typos surface early.
+///
 /// # Example TOML
 ///
 /// ```toml
 /// async_worker_thread_number = 16
+/// event_history = 100
+///
+/// [limits]
+/// max_nodes = 500
+/// max_edges = 1000
 /// ```
 #[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     /// Number of async worker threads (range: 1-32768, default: 16).
     pub async_worker_thread_number: u16,
+    /// Number of recent events each process keeps (default: 100).
+    pub event_history: usize,
+    /// Size limits checked before a process is built.
+    pub limits: LimitsConfig,
+}
+
+/// Workflow size limits.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Maximum number of nodes in a workflow.
+    pub max_nodes: Option<usize>,
+    /// Maximum number of edges in a workflow.
+    pub max_edges: Option<usize>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             async_worker_thread_number: 16,
+            event_history: DEFAULT_EVENT_HISTORY,
+            limits: LimitsConfig::default(),
         }
     }
 }
@@ -32,20 +58,90 @@ impl Config {
     ///
     /// # Panics
     ///
-    /// Panics if the file cannot be read or parsed.
+    /// Panics if the file cannot be read or is invalid.
+    #[deprecated(since = "0.1.7", note = "use `Config::load_from_file`, which returns an error instead of panicking")]
     pub fn create<T: AsRef<Path>>(path: T) -> Self {
-        let data = fs::read_to_string(path.as_ref()).expect(&format!("failed to load config file {:?}", path.as_ref()));
+        Self::load_from_file(path).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Loads configuration from a TOML file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ActflowError::Config` if the file cannot be read or is invalid.
+    pub fn load_from_file<T: AsRef<Path>>(path: T) -> Result<Self> {
+        let data = fs::read_to_string(path.as_ref()).map_err(|e| ActflowError::Config(format!("failed to read config file {:?}: {}", path.as_ref(), e)))?;
 
         Self::load_from_str(data.as_str())
     }
 
     /// Parses configuration from a TOML string.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the string cannot be parsed as valid TOML.
-    pub fn load_from_str(toml_str: &str) -> Self {
-        let config = toml::from_str::<Config>(toml_str).expect("failed to parse the toml str");
-        config
+    /// Returns `ActflowError::Config` naming the offending key if the string
+    /// is not valid TOML, has an unknown key or a value out of range.
+    pub fn load_from_str(toml_str: &str) -> Result<Self> {
+        let config = toml::from_str::<Config>(toml_str).map_err(|e| ActflowError::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.async_worker_thread_number == 0 {
+            return Err(ActflowError::Config(
+                "async_worker_thread_number must be at least 1".to_string(),
+            ));
+        }
+        if self.limits.max_nodes == Some(0) {
+            return Err(ActflowError::Config("limits.max_nodes must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_from_str() {
+        let config = Config::load_from_str(
+            r#"
+            async_worker_thread_number = 4
+
+            [limits]
+            max_nodes = 10
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.async_worker_thread_number, 4);
+        assert_eq!(config.event_history, DEFAULT_EVENT_HISTORY);
+        assert_eq!(config.limits.max_nodes, Some(10));
+        assert_eq!(config.limits.max_edges, None);
+    }
+
+    #[test]
+    fn test_load_from_str_rejects_invalid() {
+        let err = Config::load_from_str("async_worker_thread_number = 0").unwrap_err();
+        assert!(matches!(&err, ActflowError::Config(msg) if msg.contains("async_worker_thread_number")));
+
+        let err = Config::load_from_str("[limits]\nmax_node = 10").unwrap_err();
+        assert!(matches!(&err, ActflowError::Config(msg) if msg.contains("max_node")));
+
+        let err = Config::load_from_str("event_history = \"many\"").unwrap_err();
+        assert!(matches!(&err, ActflowError::Config(msg) if msg.contains("event_history")));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_create_loads_file() {
+        let path = std::env::temp_dir().join(format!("actflow-config-{}.toml", std::process::id()));
+        fs::write(&path, "event_history = 5").unwrap();
+
+        let config = Config::create(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(config.event_history, 5);
     }
 }
@@ -0,0 +1,53 @@
+//! Named locks serializing nodes across processes.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Async locks keyed by a node's `concurrency_key`.
+///
+/// Shared by every process of an engine, so nodes with the same key never
+/// run at the same time, whichever process they belong to.
+#[derive(Default)]
+pub struct ConcurrencyLocks {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl ConcurrencyLocks {
+    /// Creates an empty set of locks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits for the lock named `key`; it is held until the guard is dropped.
+    pub async fn acquire(
+        &self,
+        key: &str,
+    ) -> OwnedMutexGuard<()> {
+        let lock = self.locks.lock().unwrap().entry(key.to_string()).or_default().clone();
+        lock.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_same_key_is_exclusive() {
+        let locks = ConcurrencyLocks::new();
+
+        let guard = locks.acquire("db").await;
+        // Other keys are independent
+        let _other = locks.acquire("cache").await;
+
+        let lock = locks.locks.lock().unwrap().get("db").unwrap().clone();
+        assert!(lock.try_lock().is_err());
+
+        drop(guard);
+        assert!(lock.try_lock().is_ok());
+    }
+}
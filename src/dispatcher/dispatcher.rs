@@ -6,21 +6,27 @@
 //! - Handling node results and determining next steps
 //! - Managing retries and timeouts
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
+use serde_json::Value;
 use tokio::{runtime::Runtime, sync::mpsc};
 
 use crate::{
+    ActflowError, FailurePolicy, Result,
     common::{Queue, Shutdown, Vars},
     events::{ErrorReason, Event, GraphEvent, Message, NodeEvent, WorkflowAbortedEvent, WorkflowEvent, WorkflowFailedEvent, WorkflowStartEvent},
-    runtime::{Context, WorkflowCommand},
-    utils,
+    runtime::{Context, DeadLetter, DeadLetters, NodeTiming, WorkflowCommand},
     workflow::{
         Workflow,
         actions::{ActionOutput, ActionType},
-        consts::{IF_ELSE_FALSE, IF_ELSE_SELECTED, IF_ELSE_TRUE},
-        edge::{EdgeSelectOptions, FixedHandle, SourceHandle},
-        node::{NodeExecutionStatus, NodeId, NodeResult},
+        consts::{FAIL_BRANCH_ERROR, FAN_OUT_INDEX, FAN_OUT_ITEM, FAN_OUT_RESULTS, IF_ELSE_FALSE, IF_ELSE_SELECTED, IF_ELSE_TRUE, WEIGHTED_SELECTED},
+        edge::{EdgeId, EdgeSelectOptions, FixedHandle, SourceHandle},
+        node::{ErrorStrategy, Node, NodeExecutionStatus, NodeId, NodeResult},
+        template,
     },
 };
 
@@ -42,6 +48,12 @@ pub struct Dispatcher {
     runtime: Arc<Runtime>,
     /// Shutdown coordinator.
     shutdown: Arc<Shutdown>,
+    /// How node failures affect running sibling branches.
+    failure_policy: FailurePolicy,
+    /// Workflow ID reported in dead letters.
+    wid: String,
+    /// Sinks receiving a dead letter if the workflow fails.
+    dead_letters: Arc<DeadLetters>,
 }
 
 impl Dispatcher {
@@ -58,9 +70,32 @@ impl Dispatcher {
             command_queue,
             runtime,
             shutdown: Arc::new(Shutdown::new()),
+            failure_policy: FailurePolicy::default(),
+            wid: String::new(),
+            dead_letters: Arc::new(DeadLetters::new()),
         }
     }
 
+    /// Sets the policy applied when a node fails.
+    pub fn with_failure_policy(
+        mut self,
+        failure_policy: FailurePolicy,
+    ) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Sets the sinks receiving a dead letter for workflow `wid` if it fails.
+    pub fn with_dead_letters(
+        mut self,
+        wid: String,
+        dead_letters: Arc<DeadLetters>,
+    ) -> Self {
+        self.wid = wid;
+        self.dead_letters = dead_letters;
+        self
+    }
+
     /// Starts the dispatcher's main event loop.
     ///
     /// The loop processes:
@@ -75,39 +110,91 @@ impl Dispatcher {
         let command_queue = self.command_queue.clone();
         let runtime = self.runtime.clone();
         let shutdown = self.shutdown.clone();
+        let failure_policy = self.failure_policy;
+        let wid = self.wid.clone();
+        let dead_letters = self.dead_letters.clone();
 
         self.runtime.spawn(async move {
+            // Nodes that paused themselves and are waiting for a resume command
+            let mut paused: HashSet<NodeId> = HashSet::new();
+            // Nodes that failed while running nodes are allowed to finish (wait_all),
+            // along with the first error, reported once nothing is left running
+            let mut failed: HashSet<NodeId> = HashSet::new();
+            let mut first_error: Option<(NodeId, String)> = None;
+
             loop {
                 tokio::select! {
                     _ = shutdown.wait() => break,
 
                     // Handle node execution results
                     Some((nid, event)) = rx.recv() => {
+                        if let Some(gate) = ctx.serial_gate() {
+                            gate.finish(&nid);
+                        }
+
                         // Publish node event to external channel
                         let _ = ctx.channel().event_queue().send(Event::new(&Message {
                             pid: ctx.pid(),
+                            correlation_id: ctx.correlation_id(),
                             nid: nid.clone(),
                             event: GraphEvent::Node(event.clone()),
-                        })).unwrap();
+                        }));
 
                         match event {
+                            NodeEvent::Succeeded(_) if first_error.is_some() => {
+                                // Keep the outputs, but start nothing new once the workflow is failing
+                                workflow.mark_node_executed(&nid);
+                            }
+                            NodeEvent::Skipped if first_error.is_some() => {
+                                workflow.mark_node_skipped(&nid);
+                            }
+                            NodeEvent::Succeeded(_) if workflow.node_action_type(&nid) == Some(ActionType::End) => {
+                                Self::complete_workflow(&ctx, &workflow, &shutdown, nid);
+                            }
                             NodeEvent::Succeeded(_) => {
                                 Self::handle_node_success(&ctx, &workflow, &runtime, &tx, nid).await;
                             }
-                            NodeEvent::Error(err) => {
-                                // Send workflow failed event
-                                let _ = ctx.channel().event_queue().send(Event::new(&Message {
-                                    pid: ctx.pid(),
-                                    nid: nid.clone(),
-                                    event: GraphEvent::Workflow(WorkflowEvent::Failed(WorkflowFailedEvent {
-                                        error: err.to_string(),
-                                    })),
-                                }));
-                                // Stop the workflow
-                                shutdown.shutdown();
+                            NodeEvent::Skipped if workflow.node_skips_subtree(&nid) => {
+                                // Skipped by `skip_if`, along with the nodes reached only through it
+                                workflow.mark_node_skipped(&nid);
+                                Self::skip_subtree(&ctx, &workflow, &runtime, &tx, &nid);
+                            }
+                            NodeEvent::Skipped => {
+                                // Disabled node: pass through to its successors without outputs
+                                workflow.mark_node_skipped(&nid);
+                                Self::schedule_next(&ctx, &workflow, &runtime, &tx, &nid, EdgeSelectOptions::default());
+                            }
+                            NodeEvent::Paused(_) => {
+                                // Keep the node taken so the workflow stays alive until resumed
+                                paused.insert(nid);
                             }
+                            NodeEvent::Error(err) if first_error.is_none() && Self::has_error_strategy(&workflow, &nid) => {
+                                Self::handle_node_error(&ctx, &workflow, &runtime, &tx, nid, err.to_string()).await;
+                            }
+                            NodeEvent::Error(_) if first_error.is_none() && workflow.node_continue_on_error(&nid) => {
+                                // Best-effort node: pass through to its successors without outputs
+                                workflow.mark_node_skipped(&nid);
+                                Self::schedule_next(&ctx, &workflow, &runtime, &tx, &nid, EdgeSelectOptions::default());
+                            }
+                            NodeEvent::Error(err) => match failure_policy {
+                                FailurePolicy::FailFast => {
+                                    Self::fail_workflow(&ctx, &shutdown, &wid, &dead_letters, nid, err.to_string());
+                                }
+                                FailurePolicy::WaitAll => {
+                                    failed.insert(nid.clone());
+                                    first_error.get_or_insert((nid, err.to_string()));
+                                }
+                            },
                             _ => {}
                         }
+
+                        // Under wait_all, fail once every node still running has finished
+                        if first_error.is_some()
+                            && workflow.get_running_nodes().iter().all(|running| failed.contains(running) || paused.contains(running))
+                            && let Some((nid, error)) = first_error.take()
+                        {
+                            Self::fail_workflow(&ctx, &shutdown, &wid, &dead_letters, nid, error);
+                        }
                     }
 
                     // Handle workflow commands
@@ -115,36 +202,74 @@ impl Dispatcher {
                         if let Some(cmd) = cmd_opt {
                             match cmd {
                                 WorkflowCommand::Start => {
-                                    if let Some(root_node) = workflow.get_root_node() {
+                                    let root_node = match workflow.get_root_node() {
+                                        Ok(root_node) => root_node,
+                                        Err(err) => {
+                                            Self::fail_workflow(&ctx, &shutdown, &wid, &dead_letters, "".to_string(), err.to_string());
+                                            continue;
+                                        }
+                                    };
+                                    if let Some(root_node) = root_node {
                                         // Get all node IDs for batch initialization
                                         let node_ids = workflow.get_all_node_ids();
 
                                         let _ = ctx.channel().event_queue().send(Event::new(&Message {
                                             pid: ctx.pid(),
+                                            correlation_id: ctx.correlation_id(),
                                             nid: "".to_string(),
                                             event: GraphEvent::Workflow(WorkflowEvent::Start(WorkflowStartEvent {
                                                 node_ids,
+                                                labels: ctx.labels().clone(),
                                             })),
                                         }));
 
-                                        Self::spawn_node(&ctx, &workflow, &runtime, &tx, root_node.id);
+                                        if workflow.has_completed_nodes() {
+                                            // Retried run: continue from the nodes the failed run did not finish
+                                            for nid in workflow.get_ready_nodes() {
+                                                Self::spawn_node(&ctx, &workflow, &runtime, &tx, nid);
+                                            }
+                                        } else {
+                                            Self::spawn_node(&ctx, &workflow, &runtime, &tx, root_node.id);
+                                        }
                                     }
                                 }
-                                WorkflowCommand::Abort => {
+                                WorkflowCommand::Abort(reason) => {
                                      let _ = ctx.channel().event_queue().send(Event::new(&Message {
                                          pid: ctx.pid(),
+                                         correlation_id: ctx.correlation_id(),
                                          nid: "".to_string(),
                                          event: GraphEvent::Workflow(WorkflowEvent::Aborted(WorkflowAbortedEvent {
-                                             reason: "Aborted by command".to_string(),
+                                             reason,
                                              outputs: std::collections::HashMap::new(),
                                          })),
                                      }));
                                      shutdown.shutdown();
                                 }
+                                WorkflowCommand::Resume(nid, outputs) => {
+                                    if paused.remove(&nid) {
+                                        let event = Self::resume_node(&ctx, &workflow, &nid, outputs);
+                                        let _ = tx.send((nid, event)).await;
+                                    }
+                                }
+                                WorkflowCommand::CancelNode(nid) => {
+                                    if workflow.get_running_nodes().contains(&nid) {
+                                        ctx.cancel_node(&nid);
+                                    }
+                                }
                             }
                         }
                     }
                 }
+
+                // Start the next waiting node once the previous one has finished
+                if let Some(gate) = ctx.serial_gate() {
+                    gate.advance();
+                }
+            }
+
+            // Let nodes still waiting for their turn give up
+            if let Some(gate) = ctx.serial_gate() {
+                gate.close();
             }
         });
     }
@@ -154,6 +279,16 @@ impl Dispatcher {
         self.shutdown.shutdown();
     }
 
+    /// Returns the execution timings of the nodes that started so far.
+    pub fn timings(&self) -> HashMap<NodeId, NodeTiming> {
+        self.ctx.timings().iter().map(|(nid, timing)| (nid.as_ref().clone(), timing)).collect()
+    }
+
+    /// Returns the approximate memory held by node outputs, in bytes.
+    pub fn outputs_byte_size(&self) -> usize {
+        self.ctx.outputs_byte_size()
+    }
+
     /// Returns all node outputs collected during execution.
     pub fn outputs(&self) -> Vars {
         let mut result = Vars::new();
@@ -163,11 +298,63 @@ impl Dispatcher {
         result
     }
 
+    /// Returns the workflow graph with the execution state of its nodes.
+    pub fn workflow(&self) -> &Arc<Workflow> {
+        &self.workflow
+    }
+
     /// Checks if the dispatcher has completed execution.
     pub fn is_complete(&self) -> bool {
         self.shutdown.is_terminated()
     }
 
+    /// Publishes the workflow failed event, hands a dead letter naming the
+    /// failing node to the sinks and stops the workflow.
+    fn fail_workflow(
+        ctx: &Arc<Context>,
+        shutdown: &Arc<Shutdown>,
+        wid: &str,
+        dead_letters: &DeadLetters,
+        nid: NodeId,
+        error: String,
+    ) {
+        let _ = ctx.channel().event_queue().send(Event::new(&Message {
+            pid: ctx.pid(),
+            correlation_id: ctx.correlation_id(),
+            nid: nid.clone(),
+            event: GraphEvent::Workflow(WorkflowEvent::Failed(WorkflowFailedEvent {
+                error: error.clone(),
+            })),
+        }));
+        dead_letters.send(&DeadLetter {
+            pid: ctx.pid(),
+            wid: wid.to_string(),
+            nid,
+            error,
+            inputs: ctx.process_inputs().clone(),
+        });
+        shutdown.shutdown();
+    }
+
+    /// Completes the workflow once an end node has run, stopping any other
+    /// nodes still running.
+    fn complete_workflow(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        shutdown: &Arc<Shutdown>,
+        nid: NodeId,
+    ) {
+        workflow.mark_node_executed(&nid);
+        let _ = ctx.channel().event_queue().send(Event::new(&Message {
+            pid: ctx.pid(),
+            correlation_id: ctx.correlation_id(),
+            nid,
+            event: GraphEvent::Workflow(WorkflowEvent::Succeeded),
+        }));
+        ctx.done();
+        shutdown.shutdown();
+    }
+
     /// Spawns a node for execution in a separate task.
     fn spawn_node(
         ctx: &Arc<Context>,
@@ -181,9 +368,18 @@ impl Dispatcher {
         let tx = tx.clone();
 
         workflow.mark_node_taken(&nid);
+        let ready_time = ctx.clock().now_millis();
+
+        // In serial mode, wait until the dispatcher gives this node its turn
+        let turn = ctx.serial_gate().map(|gate| gate.enqueue(&nid));
 
         runtime.spawn(async move {
-            let result = Self::execute_node(ctx, workflow, nid.clone()).await;
+            if let Some(turn) = turn
+                && turn.await.is_err()
+            {
+                return;
+            }
+            let result = Self::execute_node(ctx, workflow, nid.clone(), ready_time).await;
             let _ = tx.send((nid, result)).await;
         });
     }
@@ -199,41 +395,156 @@ impl Dispatcher {
 
         let mut edge_select_options = EdgeSelectOptions::default();
 
-        // Handle if-else node: determine selected branch and skip others
-        let node = workflow.get_node(&nid).unwrap(); // should be safe
-        if node.action.action_type() == ActionType::IfElse {
-            if let Some(outputs) = ctx.outputs().get(&nid) {
-                if let Some(source_handle) = outputs.get::<String>(IF_ELSE_SELECTED) {
-                    // Determine the selected source handle
-                    let selected_handle = if source_handle == IF_ELSE_TRUE {
-                        SourceHandle::Fixed(FixedHandle::True)
-                    } else if source_handle == IF_ELSE_FALSE {
-                        SourceHandle::Fixed(FixedHandle::False)
-                    } else {
-                        SourceHandle::Node(source_handle)
-                    };
+        // Handle branching nodes: determine selected branch and skip others
+        let selected_key = match workflow.node_action_type(&nid) {
+            Some(ActionType::IfElse) => Some(IF_ELSE_SELECTED),
+            Some(ActionType::Weighted) => Some(WEIGHTED_SELECTED),
+            _ => None,
+        };
+        if let Some(selected_key) = selected_key
+            && let Some(outputs) = ctx.outputs().get(&nid)
+            && let Some(source_handle) = outputs.get::<String>(selected_key)
+        {
+            // Determine the selected source handle
+            let selected_handle = if source_handle == IF_ELSE_TRUE {
+                SourceHandle::Fixed(FixedHandle::True)
+            } else if source_handle == IF_ELSE_FALSE {
+                // No case matched: prefer an explicit catch-all branch over `false`
+                let default = SourceHandle::Fixed(FixedHandle::Default);
+                if workflow.get_outgoing_edges(&nid).iter().any(|edge| edge.source_handle == default) {
+                    default
+                } else {
+                    SourceHandle::Fixed(FixedHandle::False)
+                }
+            } else {
+                SourceHandle::Node(source_handle)
+            };
 
-                    edge_select_options.source_handle = selected_handle.clone();
+            Self::skip_unselected_branches(ctx, workflow, runtime, tx, &nid, &selected_handle);
+            edge_select_options.source_handle = selected_handle;
+        }
 
-                    // Skip unselected branches and send events
-                    let skipped = workflow.skip_unselected_branches(&nid, &selected_handle);
-                    for (skipped_nid, _) in skipped {
-                        let _ = ctx.channel().event_queue().send(Event::new(&Message {
-                            pid: ctx.pid(),
-                            nid: skipped_nid,
-                            event: GraphEvent::Node(NodeEvent::Skipped),
-                        }));
-                    }
-                }
+        Self::schedule_next(ctx, workflow, runtime, tx, &nid, edge_select_options);
+    }
+
+    /// Returns whether a node handles its own errors instead of failing the workflow.
+    fn has_error_strategy(
+        workflow: &Workflow,
+        nid: &NodeId,
+    ) -> bool {
+        workflow.node_error_strategy(nid).is_some_and(|strategy| strategy != ErrorStrategy::None)
+    }
+
+    /// Continues past a failed node according to its error strategy.
+    ///
+    /// `default_value` completes the node with its default outputs; `fail_branch`
+    /// records the error as `{ "error": ... }` and follows only `fail_branch` edges.
+    async fn handle_node_error(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        runtime: &Arc<Runtime>,
+        tx: &mpsc::Sender<(NodeId, NodeEvent)>,
+        nid: NodeId,
+        error: String,
+    ) {
+        let node = workflow.get_node(&nid).unwrap(); // should be safe
+        match node.error_strategy {
+            ErrorStrategy::None => {}
+            ErrorStrategy::DefaultValue => {
+                ctx.add_output(nid.clone(), node.default_value.unwrap_or_default());
+                Self::handle_node_success(ctx, workflow, runtime, tx, nid).await;
             }
+            ErrorStrategy::FailBranch => {
+                workflow.mark_node_executed(&nid);
+                ctx.add_output(nid.clone(), Vars::new().with(FAIL_BRANCH_ERROR, error));
+
+                let fail_branch = SourceHandle::Fixed(FixedHandle::FailBranch);
+                Self::skip_unselected_branches(ctx, workflow, runtime, tx, &nid, &fail_branch);
+                Self::schedule_next(
+                    ctx,
+                    workflow,
+                    runtime,
+                    tx,
+                    &nid,
+                    EdgeSelectOptions {
+                        source_handle: fail_branch,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Skips the branches of `nid` not leaving through `selected_handle`, reporting
+    /// each skipped node, and spawns nodes the skipping made ready.
+    fn skip_unselected_branches(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        runtime: &Arc<Runtime>,
+        tx: &mpsc::Sender<(NodeId, NodeEvent)>,
+        nid: &NodeId,
+        selected_handle: &SourceHandle,
+    ) {
+        let skipped = workflow.skip_unselected_branches(nid, selected_handle);
+        Self::report_skipped(ctx, workflow, runtime, tx, skipped);
+    }
+
+    /// Reports each node skipped along with a branch, and spawns the join nodes
+    /// the skipping unblocked because their other predecessors already completed.
+    fn report_skipped(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        runtime: &Arc<Runtime>,
+        tx: &mpsc::Sender<(NodeId, NodeEvent)>,
+        skipped: Vec<(NodeId, EdgeId)>,
+    ) {
+        for (skipped_nid, _) in skipped {
+            let _ = ctx.channel().event_queue().send(Event::new(&Message {
+                pid: ctx.pid(),
+                correlation_id: ctx.correlation_id(),
+                nid: skipped_nid,
+                event: GraphEvent::Node(NodeEvent::Skipped),
+            }));
         }
 
-        let next_nodes = workflow.get_next_ready_node(&nid, edge_select_options);
+        for ready_nid in workflow.get_ready_nodes() {
+            Self::spawn_node(ctx, workflow, runtime, tx, ready_nid);
+        }
+    }
+
+    /// Skips every branch leaving `nid`, reporting each skipped node. Nodes that
+    /// are also reached through other branches stay pending and run once those
+    /// complete.
+    fn skip_subtree(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        runtime: &Arc<Runtime>,
+        tx: &mpsc::Sender<(NodeId, NodeEvent)>,
+        nid: &NodeId,
+    ) {
+        let skipped = workflow.get_outgoing_edges(nid).iter().flat_map(|edge| workflow.skip_branch(&edge.id)).collect();
+        Self::report_skipped(ctx, workflow, runtime, tx, skipped);
+        // Finishes the workflow if nothing is left to run
+        Self::schedule_next(ctx, workflow, runtime, tx, nid, EdgeSelectOptions::default());
+    }
+
+    /// Spawns the successors of a completed node that are ready, or finishes the
+    /// workflow once every node has executed or been skipped.
+    fn schedule_next(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        runtime: &Arc<Runtime>,
+        tx: &mpsc::Sender<(NodeId, NodeEvent)>,
+        nid: &NodeId,
+        edge_select_options: EdgeSelectOptions,
+    ) {
+        let next_nodes = workflow.get_next_ready_node(nid, edge_select_options);
         let all_executed = workflow.is_all_node_executed();
+        Self::evict_consumed_outputs(ctx, workflow);
 
         if next_nodes.is_empty() && all_executed {
             let _ = ctx.channel().event_queue().send(Event::new(&Message {
                 pid: ctx.pid(),
+                correlation_id: ctx.correlation_id(),
                 nid: nid.clone(),
                 event: GraphEvent::Workflow(WorkflowEvent::Succeeded),
             }));
@@ -245,12 +556,33 @@ impl Dispatcher {
         }
     }
 
+    /// Drops the outputs of nodes whose consumers have all completed, if the
+    /// process evicts consumed outputs.
+    fn evict_consumed_outputs(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+    ) {
+        let Some(consumers) = ctx.output_consumers() else {
+            return;
+        };
+        let outputs = ctx.outputs();
+        for (producer, readers) in consumers.iter() {
+            if outputs.get(producer).is_some() && readers.iter().all(|reader| workflow.is_node_completed(reader)) {
+                ctx.remove_output(producer);
+            }
+        }
+    }
+
     /// Executes a single node logic, including retries and timeout handling.
     /// This function is intended to be spawned as a separate task by the dispatcher.
+    ///
+    /// `ready_time` is when the node became ready; the wait until it starts is
+    /// recorded as its queue wait.
     async fn execute_node(
         ctx: Arc<Context>,
         workflow: Arc<Workflow>,
         nid: NodeId,
+        ready_time: i64,
     ) -> NodeEvent {
         let event_queue = ctx.channel().event_queue();
 
@@ -261,81 +593,1195 @@ impl Dispatcher {
             }
         };
 
-        let mut retry_times = node.retry.as_ref().map(|r| r.times).unwrap_or(0);
-        let retry_interval = node.retry.as_ref().map(|r| r.interval).unwrap_or(0);
+        if node.disabled {
+            return NodeEvent::Skipped;
+        }
+
+        // Let the node read its predecessors' outputs merged as `{{#__incoming__.key#}}`
+        let ctx = Arc::new(ctx.with_incoming(workflow.get_incoming_sources(&nid)));
+
+        if let Some(skip_if) = &node.skip_if {
+            match template::resolve_template_in(&ctx, skip_if, &nid, "skip_if") {
+                Ok(resolved) if template::is_truthy(&resolved) => return NodeEvent::Skipped,
+                Ok(_) => {}
+                Err(e) => return NodeEvent::Error(ErrorReason::Exception(e.to_string())),
+            }
+        }
+
+        // Resolve the node's input mapping before running its action
+        let mut inputs = Vars::new();
+        if !node.inputs.is_empty() {
+            match Self::resolve_inputs(&ctx, &node) {
+                Ok(resolved) => {
+                    ctx.add_inputs(nid.clone(), resolved.clone());
+                    inputs = resolved;
+                }
+                Err(e) => return NodeEvent::Error(ErrorReason::Exception(e.to_string())),
+            }
+        }
+
+        // Resolve the array to fan out over, if the node is reached by a fan-out edge
+        let fan_out_items = match workflow.get_fan_out_selector(&nid) {
+            Some(selector) => match Self::resolve_fan_out_items(&ctx, &selector) {
+                Ok(items) => Some(items),
+                Err(e) => return NodeEvent::Error(ErrorReason::Exception(e.to_string())),
+            },
+            None => None,
+        };
+
+        // Let `Process::cancel_node` stop this node's execution
+        let cancellation = ctx.track_cancellation(&nid);
+
+        // Wait for nodes sharing the concurrency key, in any process, to finish
+        let concurrency_locks = ctx.concurrency_locks();
+        let _concurrency_guard = match &node.concurrency_key {
+            Some(key) => tokio::select! {
+                guard = concurrency_locks.acquire(key) => Some(guard),
+                _ = ctx.wait_shutdown() => return NodeEvent::Stopped(ctx.clock().now_millis()),
+                _ = cancellation.wait() => return NodeEvent::Error(ErrorReason::Failed("Cancelled".to_string())),
+            },
+            None => None,
+        };
 
         // Track start time before action execution (as timestamp)
-        let start_time = utils::time::time_millis();
+        let start_time = ctx.clock().now_millis();
+        let mut timing = NodeTiming {
+            ready_time,
+            start_time,
+            end_time: None,
+            queue_wait_ms: start_time - ready_time,
+        };
+        ctx.add_timing(nid.clone(), timing);
 
         // Emit Running event
         let _ = event_queue.send(Event::new(&Message {
             pid: ctx.pid(),
+            correlation_id: ctx.correlation_id(),
             nid: nid.clone(),
             event: GraphEvent::Node(NodeEvent::Running(start_time)),
         }));
 
-        loop {
-            let action_ctx = ctx.clone();
-            let action_node = node.clone();
-            let action_nid = nid.clone();
-
-            let run_future = async move {
-                if let Some(timeout) = action_node.timeout {
-                    tokio::time::timeout(timeout, async move {
-                        action_node.action.run(action_ctx, action_node.id.clone()).await
-                    })
-                    .await
-                } else {
-                    Ok(action_node.action.run(action_ctx, action_nid).await)
-                }
-            };
+        let node_result = match fan_out_items {
+            Some(items) => Self::run_fan_out(&ctx, &node, &nid, &cancellation, inputs, items).await,
+            None => Self::run_action(&ctx, &node, &nid, &cancellation).await,
+        };
 
-            let ret = tokio::select! {
-                _ = ctx.wait_shutdown() => return NodeEvent::Stopped(utils::time::time_millis()),
-                res = run_future => res,
-            };
+        // Track end time after action execution (as timestamp)
+        let end_time = ctx.clock().now_millis();
+        timing.end_time = Some(end_time);
+        ctx.add_timing(nid.clone(), timing);
 
-            // Track end time after action execution (as timestamp)
-            let end_time = utils::time::time_millis();
+        match node_result.status {
+            NodeExecutionStatus::Pending => unreachable!(),
+            NodeExecutionStatus::Succeeded => match Self::store_outputs(&ctx, &node, &nid, node_result.outputs) {
+                Ok(()) => NodeEvent::Succeeded(end_time),
+                Err(e) => NodeEvent::Error(ErrorReason::Exception(e.to_string())),
+            },
+            NodeExecutionStatus::Failed => NodeEvent::Error(ErrorReason::Failed(node_result.error.unwrap_or_default())),
+            NodeExecutionStatus::Exception => NodeEvent::Error(ErrorReason::Exception(node_result.exception.unwrap_or_default())),
+            NodeExecutionStatus::Stopped => NodeEvent::Stopped(end_time),
+            NodeExecutionStatus::Paused => NodeEvent::Paused(end_time),
+        }
+    }
 
-            // Convert ActionOutput to NodeResult
-            let node_result = match &ret {
-                Ok(output) => NodeResult::from_result_output(output.clone()),
-                Err(_) => NodeResult::from_output(ActionOutput::failed("Timeout".to_string())),
-            };
+    /// Runs a node's action, retrying failures as configured and applying its timeout.
+    ///
+    /// Signalling `cancellation` fails the node with "Cancelled" without further retries.
+    async fn run_action(
+        ctx: &Arc<Context>,
+        node: &Arc<Node>,
+        nid: &NodeId,
+        cancellation: &Shutdown,
+    ) -> NodeResult {
+        let event_queue = ctx.channel().event_queue();
+
+        // Templated retry settings are resolved against the outputs available now
+        let (mut retry_times, retry_interval) = node.retry.as_ref().map(|r| r.resolve(ctx)).unwrap_or((0, 0));
 
-            let should_retry = match node_result.status {
-                NodeExecutionStatus::Failed => true,
-                _ => false,
+        loop {
+            // Every `before_run` is paired with an `after_run` getting the final
+            // output, including timeouts, cancellation and shutdown
+            let interceptors = ctx.interceptors();
+            let mut interrupted = false;
+            let output = match interceptors.before_run(ctx, node) {
+                Some(output) => output,
+                None => {
+                    let run = node.action.run(ctx.clone(), nid.clone());
+                    let timeout = node.timeout.map(|timeout| ctx.clock().sleep(timeout));
+                    let timeout = async move {
+                        match timeout {
+                            Some(timeout) => timeout.await,
+                            None => std::future::pending().await,
+                        }
+                    };
+                    tokio::select! {
+                        result = run => result.unwrap_or_else(|e| ActionOutput::exception(e.to_string())),
+                        _ = timeout => ActionOutput::failed("Timeout".to_string()),
+                        _ = ctx.wait_shutdown() => {
+                            interrupted = true;
+                            ActionOutput::stopped()
+                        }
+                        _ = cancellation.wait() => {
+                            interrupted = true;
+                            ActionOutput::failed("Cancelled".to_string())
+                        }
+                    }
+                }
             };
+            interceptors.after_run(ctx, node, &output);
+
+            let node_result = NodeResult::from_output(output);
+            if interrupted {
+                return node_result;
+            }
+
+            let should_retry = matches!(node_result.status, NodeExecutionStatus::Failed);
 
             if should_retry && retry_times > 0 {
                 retry_times -= 1;
-                if retry_interval > 0 {
+                // A delay suggested by the action (e.g. HTTP Retry-After) wins over the configured interval
+                let retry_delay = node_result.retry_after.unwrap_or(Duration::from_millis(retry_interval));
+                if !retry_delay.is_zero() {
                     tokio::select! {
-                        _ = ctx.wait_shutdown() => return NodeEvent::Stopped(utils::time::time_millis()),
-                        _ = tokio::time::sleep(Duration::from_millis(retry_interval)) => {}
+                        _ = ctx.wait_shutdown() => return NodeResult::from_output(ActionOutput::stopped()),
+                        _ = cancellation.wait() => return NodeResult::from_output(ActionOutput::failed("Cancelled".to_string())),
+                        _ = ctx.clock().sleep(retry_delay) => {}
                     }
                 }
                 let _ = event_queue.send(Event::new(&Message {
                     pid: ctx.pid(),
+                    correlation_id: ctx.correlation_id(),
                     nid: nid.clone(),
                     event: GraphEvent::Node(NodeEvent::Retry),
                 }));
                 continue;
             }
 
-            return match node_result.status {
-                NodeExecutionStatus::Pending => unreachable!(),
-                NodeExecutionStatus::Succeeded => {
-                    ctx.add_output(nid.clone(), node_result.outputs);
-                    NodeEvent::Succeeded(end_time)
+            return node_result;
+        }
+    }
+
+    /// Runs a node's action once per fan-out item, concurrently, and joins the results.
+    ///
+    /// Each execution sees the node's inputs plus `item` and `index`. On success the
+    /// node's outputs are `{ "results": [...] }` in item order; otherwise the first
+    /// unsuccessful result is returned.
+    async fn run_fan_out(
+        ctx: &Arc<Context>,
+        node: &Arc<Node>,
+        nid: &NodeId,
+        cancellation: &Shutdown,
+        inputs: Vars,
+        items: Vec<Value>,
+    ) -> NodeResult {
+        let runs = items.into_iter().enumerate().map(|(index, item)| {
+            let item_ctx = Arc::new(ctx.fork());
+            item_ctx.add_inputs(nid.clone(), inputs.clone().with(FAN_OUT_ITEM, item).with(FAN_OUT_INDEX, index));
+            async move { Self::run_action(&item_ctx, node, nid, cancellation).await }
+        });
+        let results = futures::future::join_all(runs).await;
+
+        if let Some(result) = results.iter().find(|r| r.status != NodeExecutionStatus::Succeeded) {
+            return result.clone();
+        }
+
+        let outputs: Vec<Value> = results.into_iter().map(|r| r.outputs.into()).collect();
+        NodeResult::from_output(ActionOutput::success(Vars::new().with(FAN_OUT_RESULTS, outputs)))
+    }
+
+    /// Completes a paused node with the outputs it was resumed with, stored as
+    /// if its action had returned them.
+    fn resume_node(
+        ctx: &Arc<Context>,
+        workflow: &Arc<Workflow>,
+        nid: &NodeId,
+        outputs: Vars,
+    ) -> NodeEvent {
+        let Some(node) = workflow.get_node(nid) else {
+            return NodeEvent::Error(ErrorReason::Exception(format!("Node {} not found", nid)));
+        };
+        let ctx = ctx.with_incoming(workflow.get_incoming_sources(nid));
+        match Self::store_outputs(&ctx, &node, nid, outputs) {
+            Ok(()) => NodeEvent::Succeeded(ctx.clock().now_millis()),
+            Err(e) => NodeEvent::Error(ErrorReason::Exception(e.to_string())),
+        }
+    }
+
+    /// Stores a node's outputs, replacing them with the result of its output transform if it has one.
+    fn store_outputs(
+        ctx: &Context,
+        node: &Node,
+        nid: &NodeId,
+        outputs: Vars,
+    ) -> Result<()> {
+        ctx.add_output(nid.clone(), outputs);
+        if let Some(transform) = &node.output_transform {
+            let outputs = Self::transform_outputs(ctx, nid, transform)?;
+            ctx.add_output(nid.clone(), outputs);
+        }
+        Ok(())
+    }
+
+    /// Applies a node's output transform to the raw outputs already stored under its ID.
+    fn transform_outputs(
+        ctx: &Context,
+        nid: &NodeId,
+        transform: &Value,
+    ) -> Result<Vars> {
+        match template::resolve_json_template(ctx, transform).map_err(|e| template::field_error(nid, "output_transform", e))? {
+            value @ Value::Object(_) => Ok(value.into()),
+            _ => Err(template::field_error(
+                nid,
+                "output_transform",
+                ActflowError::Runtime("must produce an object".to_string()),
+            )),
+        }
+    }
+
+    /// Resolves a fan-out selector to the array of items to fan out over.
+    fn resolve_fan_out_items(
+        ctx: &Context,
+        selector: &str,
+    ) -> Result<Vec<Value>> {
+        match template::resolve_template_to_values(ctx, selector)?.into_iter().next() {
+            Some(Value::Array(items)) => Ok(items),
+            _ => Err(ActflowError::Runtime(format!(
+                "fan-out selector '{}' did not resolve to an array",
+                selector
+            ))),
+        }
+    }
+
+    /// Resolves a node's input variable mapping against the context.
+    pub(crate) fn resolve_inputs(
+        ctx: &Context,
+        node: &Node,
+    ) -> Result<Vars> {
+        let mut inputs = Vars::new();
+        for input in &node.inputs {
+            let value = template::resolve_template_to_values(ctx, &input.value_selector)
+                .map_err(|e| template::field_error(&node.id, &format!("inputs.{}", input.variable), e))?
+                .into_iter()
+                .next()
+                .ok_or_else(|| ActflowError::Runtime(format!("input '{}' not found", input.variable)))?;
+
+            inputs.set(&input.variable, value);
+        }
+        Ok(inputs)
+    }
+}
+
+#[cfg(all(test, feature = "js"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicUsize, Ordering},
+            mpsc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use serde_json::json;
+
+    use crate::test_support::{branch_edge, edge, start_and_wait, start_node};
+    use crate::{
+        ActionInterceptor, ActionOutput, ChannelEvent, ChannelOptions, Context, EdgeModel, EngineBuilder, FailurePolicy, GraphEvent, MockClock, Node,
+        NodeEvent, NodeModel, ProcessOptions, WorkflowEvent, WorkflowModel, common::Vars,
+    };
+
+    fn code_node(
+        id: &str,
+        variables: serde_json::Value,
+        code: &str,
+    ) -> NodeModel {
+        NodeModel {
+            id: id.to_string(),
+            uses: "code".to_string(),
+            action: json!({
+                "variables": variables,
+                "code_language": "javascript",
+                "code": code
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// An `http_request` node calling the local server on `port`.
+    fn http_node(
+        id: &str,
+        port: u16,
+        timeout: u64,
+    ) -> NodeModel {
+        NodeModel {
+            id: id.to_string(),
+            uses: "http_request".to_string(),
+            action: json!({
+                "url": format!("http://127.0.0.1:{}/", port),
+                "method": "GET",
+                "auth": { "auth_type": "no_auth" },
+                "headers": {},
+                "params": {},
+                "body": { "content_type": "none" },
+                "timeout": timeout
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fan_out_edge() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "fan_out".to_string(),
+            nodes: vec![
+                start_node("start"),
+                code_node("items", json!([]), "function main() { return { items: [1, 2, 3] } }"),
+                code_node(
+                    "double",
+                    json!([
+                        { "variable": "item", "value_selector": "{{#double.item#}}" },
+                        { "variable": "index", "value_selector": "{{#double.index#}}" }
+                    ]),
+                    "function main({ item, index }) { return { doubled: item * 2, index } }",
+                ),
+            ],
+            edges: vec![
+                edge("e1", "start", "items"),
+                EdgeModel {
+                    fan_out_selector: Some("{{#items.items#}}".to_string()),
+                    ..edge("e2", "items", "double")
+                },
+            ],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        let results: serde_json::Value = process.get_outputs().get("double").unwrap();
+        assert_eq!(
+            results,
+            json!({ "results": [
+                { "doubled": 2, "index": 0 },
+                { "doubled": 4, "index": 1 },
+                { "doubled": 6, "index": 2 }
+            ] })
+        );
+
+        engine.shutdown();
+    }
+
+    /// Records every call and short-circuits the node named `mocked`.
+    struct RecordingInterceptor {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ActionInterceptor for RecordingInterceptor {
+        fn before_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            self.calls.lock().unwrap().push(format!("before:{}", node.id));
+            (node.id == "mocked").then(|| ActionOutput::success(Vars::new().with("source", "interceptor")))
+        }
+
+        fn after_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+            output: &ActionOutput,
+        ) {
+            self.calls.lock().unwrap().push(format!("after:{}:{}", node.id, output.status.as_ref()));
+        }
+    }
+
+    #[test]
+    fn test_interceptor_wraps_actions() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        engine.register_interceptor(RecordingInterceptor {
+            calls: calls.clone(),
+        });
+
+        let workflow = WorkflowModel {
+            id: "intercepted".to_string(),
+            nodes: vec![
+                start_node("start"),
+                code_node("real", json!([]), "function main() { return { source: 'action' } }"),
+                code_node("mocked", json!([]), "function main() { throw new Error('must not run') }"),
+            ],
+            edges: vec![edge("e1", "start", "real"), edge("e2", "real", "mocked")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["before:start", "after:start:succeeded", "before:real", "after:real:succeeded", "before:mocked", "after:mocked:succeeded"]
+        );
+        let outputs = process.get_outputs();
+        assert_eq!(outputs.get::<serde_json::Value>("real"), Some(json!({ "source": "action" })));
+        assert_eq!(
+            outputs.get::<serde_json::Value>("mocked"),
+            Some(json!({ "source": "interceptor" }))
+        );
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_interceptor_sees_timed_out_action() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        engine.register_interceptor(RecordingInterceptor {
+            calls: calls.clone(),
+        });
+
+        let workflow = WorkflowModel {
+            id: "timed_out".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    timeout: Some(100),
+                    ..http_node("fetch", port, 10_000)
+                },
+            ],
+            edges: vec![edge("e1", "start", "fetch")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        assert!(start_and_wait(&engine, &process).is_error());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["before:start", "after:start:succeeded", "before:fetch", "after:fetch:failed"]
+        );
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_unresolved_input_names_node_and_field() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "unresolved_input".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    inputs: Some(json!([{ "variable": "user_id", "value_selector": "{{#start.missing#}}" }])),
+                    ..code_node("n2", json!([]), "function main() { return {} }")
+                },
+            ],
+            edges: vec![edge("e1", "start", "n2")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        match start_and_wait(&engine, &process) {
+            GraphEvent::Workflow(WorkflowEvent::Failed(failed)) => {
+                assert!(failed.error.contains("node 'n2' field 'inputs.user_id'"), "{}", failed.error);
+                assert!(
+                    failed.error.contains("variable '{{#start.missing#}}' not found"),
+                    "{}",
+                    failed.error
+                );
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        engine.shutdown();
+    }
+
+    /// Fails every attempt of `flaky`, counting them.
+    #[derive(Default)]
+    struct FailingInterceptor {
+        attempts: AtomicUsize,
+    }
+
+    impl ActionInterceptor for Arc<FailingInterceptor> {
+        fn before_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            (node.id == "flaky").then(|| {
+                self.attempts.fetch_add(1, Ordering::SeqCst);
+                ActionOutput::failed("rate limited".to_string())
+            })
+        }
+    }
+
+    /// Runs `limits -> flaky` with the given retry config on `flaky`, returning its number of attempts.
+    fn count_retry_attempts(retry: serde_json::Value) -> usize {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        let interceptor = Arc::new(FailingInterceptor::default());
+        engine.register_interceptor(interceptor.clone());
+
+        let workflow = WorkflowModel {
+            id: "templated_retry".to_string(),
+            nodes: vec![
+                start_node("start"),
+                code_node("limits", json!([]), "function main() { return { retries: 2 } }"),
+                NodeModel {
+                    retry: Some(retry),
+                    ..code_node("flaky", json!([]), "function main() { return {} }")
+                },
+            ],
+            edges: vec![edge("e1", "start", "limits"), edge("e2", "limits", "flaky")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        engine.shutdown();
+        interceptor.attempts.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_retry_times_from_upstream_output() {
+        assert_eq!(
+            count_retry_attempts(json!({ "times": "{{#limits.retries#}}", "interval": 0 })),
+            3
+        );
+        assert_eq!(
+            count_retry_attempts(json!({ "times": 1, "interval": "{{#limits.retries#}}" })),
+            2
+        );
+    }
+
+    #[test]
+    fn test_unresolved_retry_times_use_default() {
+        assert_eq!(
+            count_retry_attempts(json!({ "times": "{{#limits.missing#}}", "interval": 0, "default_times": 1 })),
+            2
+        );
+        assert_eq!(
+            count_retry_attempts(json!({ "times": "{{#limits.missing#}}", "interval": 0 })),
+            1
+        );
+    }
+
+    /// Tracks the peak number of `guarded` nodes running at once.
+    #[derive(Default)]
+    struct OverlapInterceptor {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl ActionInterceptor for Arc<OverlapInterceptor> {
+        fn before_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            if node.id == "guarded" {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+            }
+            None
+        }
+
+        fn after_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+            _output: &ActionOutput,
+        ) {
+            if node.id == "guarded" {
+                self.running.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_concurrency_key_is_exclusive_across_processes() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let overlap = Arc::new(OverlapInterceptor::default());
+        engine.register_interceptor(overlap.clone());
+
+        let workflow = WorkflowModel {
+            id: "keyed".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    concurrency_key: Some("shared-resource".to_string()),
+                    ..code_node(
+                        "guarded",
+                        json!([]),
+                        "function main() { const end = Date.now() + 200; while (Date.now() < end) {} return {} }",
+                    )
+                },
+            ],
+            edges: vec![edge("e1", "start", "guarded")],
+            ..Default::default()
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let processes: Vec<_> = (0..2)
+            .map(|_| {
+                let process = engine.build_workflow_process(&workflow).unwrap();
+                let tx = tx.clone();
+                ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_complete(move |pid| {
+                    let _ = tx.send(pid);
+                });
+                process
+            })
+            .collect();
+
+        let started = Instant::now();
+        processes.iter().for_each(|process| process.start());
+        for _ in &processes {
+            rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+
+        assert_eq!(overlap.peak.load(Ordering::SeqCst), 1);
+        assert!(started.elapsed() >= Duration::from_millis(400));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_disabled_node_is_skipped() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "disabled".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    disabled: true,
+                    ..code_node("broken", json!([]), "function main() { throw new Error('should not run') }")
+                },
+                code_node("after", json!([]), "function main() { return { ran: true } }"),
+            ],
+            edges: vec![edge("e1", "start", "broken"), edge("e2", "broken", "after")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if e.nid == "broken" || e.event.is_complete() {
+                let _ = tx.send((e.nid.clone(), e.event.clone()));
+            }
+        });
+        process.start();
+
+        let (nid, event) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(nid, "broken");
+        assert!(matches!(event, GraphEvent::Node(NodeEvent::Skipped)));
+        let (_, event) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, GraphEvent::Workflow(WorkflowEvent::Succeeded)));
+
+        let outputs = process.get_outputs();
+        assert!(outputs.get::<serde_json::Value>("broken").is_none());
+        assert_eq!(outputs.get::<serde_json::Value>("after"), Some(json!({ "ran": true })));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_skip_if_skips_subtree() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "skip_if".to_string(),
+            vars: HashMap::from([("FLAGS".to_string(), json!({ "beta_off": true }))]),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    skip_if: Some("{{~FLAGS.beta_off~}}".to_string()),
+                    skip_subtree: true,
+                    ..code_node("beta", json!([]), "function main() { throw new Error('should not run') }")
+                },
+                code_node(
+                    "beta_report",
+                    json!([]),
+                    "function main() { throw new Error('should not run') }",
+                ),
+                code_node("stable", json!([]), "function main() { return { ran: true } }"),
+            ],
+            edges: vec![edge("e1", "start", "beta"), edge("e2", "beta", "beta_report"), edge("e3", "start", "stable")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if matches!(e.event, GraphEvent::Node(NodeEvent::Skipped)) || e.event.is_complete() || e.event.is_error() {
+                let _ = tx.send((e.nid.clone(), e.event.clone()));
+            }
+        });
+        process.start();
+
+        let mut skipped = Vec::new();
+        loop {
+            let (nid, event) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            match event {
+                GraphEvent::Node(NodeEvent::Skipped) => skipped.push(nid),
+                event => {
+                    assert!(matches!(event, GraphEvent::Workflow(WorkflowEvent::Succeeded)), "{:?}", event);
+                    break;
                 }
-                NodeExecutionStatus::Failed => NodeEvent::Error(ErrorReason::Failed(node_result.error.unwrap_or_default())),
-                NodeExecutionStatus::Exception => NodeEvent::Error(ErrorReason::Exception(node_result.exception.unwrap_or_default())),
-                NodeExecutionStatus::Stopped => NodeEvent::Stopped(end_time),
-                NodeExecutionStatus::Paused => NodeEvent::Paused(end_time),
+            }
+        }
+        skipped.sort();
+        assert_eq!(skipped, vec!["beta", "beta_report"]);
+
+        let outputs = process.get_outputs();
+        assert!(outputs.get::<serde_json::Value>("beta").is_none());
+        assert!(outputs.get::<serde_json::Value>("beta_report").is_none());
+        assert_eq!(outputs.get::<serde_json::Value>("stable"), Some(json!({ "ran": true })));
+
+        engine.shutdown();
+    }
+
+    /// Runs `start` fanning out to a branch that fails immediately and a slow
+    /// branch that succeeds, returning the failure error and the outputs at that point.
+    fn run_failing_branches(failure_policy: FailurePolicy) -> (String, Vars) {
+        // Answers a single request after a delay
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]).unwrap();
+            thread::sleep(Duration::from_millis(300));
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+        });
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "failure_policy".to_string(),
+            nodes: vec![start_node("start"), code_node("fail", json!([]), "function main() { throw new Error('boom') }"), http_node("slow", port, 5000)],
+            edges: vec![edge("e1", "start", "fail"), edge("e2", "start", "slow")],
+            failure_policy,
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let outputs_process = process.clone();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if let GraphEvent::Workflow(WorkflowEvent::Failed(failed)) = &e.event {
+                let _ = tx.send((failed.error.clone(), outputs_process.get_outputs()));
+            }
+        });
+        process.start();
+        let result = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        engine.shutdown();
+        result
+    }
+
+    #[test]
+    fn test_weighted_node_takes_selected_branch() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "weighted".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    id: "split".to_string(),
+                    uses: "weighted".to_string(),
+                    action: json!({ "branches": [{ "id": "control", "weight": 0 }, { "id": "canary", "weight": 100 }] }),
+                    ..Default::default()
+                },
+                code_node("control", json!([]), "function main() { return { variant: 'control' } }"),
+                code_node("canary", json!([]), "function main() { return { variant: 'canary' } }"),
+            ],
+            edges: vec![edge("e1", "start", "split"), branch_edge("e2", "split", "control", "control"), branch_edge("e3", "split", "canary", "canary")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        let outputs = process.get_outputs();
+        assert_eq!(
+            outputs.get::<serde_json::Value>("split"),
+            Some(json!({ "selected_branch": "canary" }))
+        );
+        assert_eq!(outputs.get::<serde_json::Value>("canary"), Some(json!({ "variant": "canary" })));
+        assert!(outputs.get::<serde_json::Value>("control").is_none());
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_if_else_takes_default_branch() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "default_branch".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    id: "route".to_string(),
+                    uses: "if_else".to_string(),
+                    action: json!({
+                        "cases": [{
+                            "case_id": "gold",
+                            "logical_operator": "and",
+                            "conditions": [{
+                                "variable_selector": "{{@input.tier@}}",
+                                "comparison_operator": "is",
+                                "value": "gold"
+                            }]
+                        }]
+                    }),
+                    ..Default::default()
+                },
+                code_node("gold", json!([]), "function main() { return { lane: 'gold' } }"),
+                code_node("fallback", json!([]), "function main() { return { lane: 'default' } }"),
+            ],
+            edges: vec![edge("e1", "start", "route"), branch_edge("e2", "route", "gold", "gold"), branch_edge("e3", "route", "fallback", "default")],
+            ..Default::default()
+        };
+        let process = engine
+            .build_workflow_process_with(
+                &workflow,
+                ProcessOptions {
+                    inputs: Vars::new().with("tier", "silver"),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        start_and_wait(&engine, &process);
+
+        let outputs = process.get_outputs();
+        assert_eq!(outputs.get::<serde_json::Value>("fallback"), Some(json!({ "lane": "default" })));
+        assert!(outputs.get::<serde_json::Value>("gold").is_none());
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_join_reads_merged_incoming_outputs() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "incoming".to_string(),
+            nodes: vec![
+                start_node("start"),
+                code_node("left", json!([]), "function main() { return { a: 1, shared: 'left' } }"),
+                code_node("right", json!([]), "function main() { return { b: 2, shared: 'right' } }"),
+                code_node(
+                    "join",
+                    json!([
+                        { "variable": "a", "value_selector": "{{#__incoming__.a#}}" },
+                        { "variable": "b", "value_selector": "{{#__incoming__.b#}}" },
+                        { "variable": "shared", "value_selector": "{{#__incoming__.shared#}}" }
+                    ]),
+                    "function main({ a, b, shared }) { return { sum: a + b, shared } }",
+                ),
+            ],
+            edges: vec![edge("e1", "start", "left"), edge("e2", "start", "right"), edge("e3", "left", "join"), edge("e4", "right", "join")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        // On a conflict the predecessor of the later edge wins
+        assert_eq!(
+            process.get_outputs().get::<serde_json::Value>("join"),
+            Some(json!({ "sum": 3, "shared": "right" }))
+        );
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_continue_on_error_skips_failed_node() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "continue_on_error".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    continue_on_error: true,
+                    ..code_node("log", json!([]), "function main() { throw new Error('log sink down') }")
+                },
+                code_node("done", json!([]), "function main() { return { ok: true } }"),
+            ],
+            edges: vec![edge("e1", "start", "log"), edge("e2", "log", "done")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let event = start_and_wait(&engine, &process);
+        assert!(matches!(event, GraphEvent::Workflow(WorkflowEvent::Succeeded)), "{:?}", event);
+
+        let outputs = process.get_outputs();
+        assert!(outputs.get::<serde_json::Value>("log").is_none());
+        assert_eq!(outputs.get::<serde_json::Value>("done"), Some(json!({ "ok": true })));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_fail_fast_stops_siblings() {
+        let (error, outputs) = run_failing_branches(FailurePolicy::FailFast);
+        assert!(error.contains("boom"));
+        assert!(outputs.get::<serde_json::Value>("slow").is_none());
+    }
+
+    #[test]
+    fn test_wait_all_lets_siblings_finish() {
+        let (error, outputs) = run_failing_branches(FailurePolicy::WaitAll);
+        assert!(error.contains("boom"));
+        let slow = outputs.get::<serde_json::Value>("slow").unwrap();
+        assert_eq!(slow["status_code"], json!(200));
+    }
+
+    #[test]
+    fn test_timeout_with_mock_clock() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let clock = Arc::new(MockClock::new(1_000));
+        let engine = EngineBuilder::new().clock(clock.clone()).build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "timeout".to_string(),
+            nodes: vec![NodeModel {
+                timeout: Some(60_000),
+                ..http_node("fetch", port, 120_000)
+            }],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if matches!(
+                e.event,
+                GraphEvent::Node(NodeEvent::Running(_)) | GraphEvent::Workflow(WorkflowEvent::Failed(_))
+            ) {
+                let _ = tx.send(e.event.clone());
+            }
+        });
+        process.start();
+
+        let running = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(running, GraphEvent::Node(NodeEvent::Running(1_000))));
+
+        // Wait for the node's timeout to be armed, then let it expire
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while clock.pending_sleeps() == 0 {
+            assert!(Instant::now() < deadline, "timeout was not armed");
+            thread::sleep(Duration::from_millis(5));
+        }
+        clock.advance(Duration::from_secs(60));
+
+        match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            GraphEvent::Workflow(WorkflowEvent::Failed(failed)) => assert!(failed.error.contains("Timeout")),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_end_node_completes_workflow() {
+        // Accepts the connection but never answers, keeping the other branch running
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "explicit_end".to_string(),
+            nodes: vec![
+                start_node("start"),
+                code_node("double", json!([]), "function main() { return { doubled: 42 } }"),
+                NodeModel {
+                    id: "end".to_string(),
+                    uses: "end".to_string(),
+                    action: json!({ "outputs": { "answer": "{{#double.doubled#}}", "label": "done" } }),
+                    ..Default::default()
+                },
+                http_node("slow", port, 60_000),
+            ],
+            edges: vec![edge("e1", "start", "double"), edge("e2", "double", "end"), edge("e3", "start", "slow")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if let GraphEvent::Workflow(event @ (WorkflowEvent::Succeeded | WorkflowEvent::Failed(_))) = &e.event {
+                let _ = tx.send((e.nid.clone(), event.str().to_string()));
+            }
+        });
+        process.start();
+
+        let (nid, event) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!((nid.as_str(), event.as_str()), ("end", "Succeeded"));
+        assert_eq!(
+            process.get_outputs().get::<serde_json::Value>("end"),
+            Some(json!({ "answer": 42, "label": "done" }))
+        );
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+        engine.shutdown();
+    }
+
+    /// Runs a diamond workflow on a fresh deterministic engine and returns its events.
+    fn run_deterministic_diamond() -> Vec<(String, String, String)> {
+        let engine = EngineBuilder::new().deterministic().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "diamond".to_string(),
+            nodes: vec![
+                start_node("start"),
+                code_node("left", json!([]), "function main() { return { side: 'left' } }"),
+                code_node("right", json!([]), "function main() { return { side: 'right' } }"),
+                code_node("join", json!([]), "function main() { return {} }"),
+            ],
+            edges: vec![edge("e1", "start", "left"), edge("e2", "start", "right"), edge("e3", "left", "join"), edge("e4", "right", "join")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            let detail = match &e.event {
+                GraphEvent::Node(event @ (NodeEvent::Running(ts) | NodeEvent::Succeeded(ts))) => format!("{}@{}", event.str(), ts),
+                GraphEvent::Node(event) => event.str().to_string(),
+                GraphEvent::Workflow(event) => event.str().to_string(),
             };
+            let _ = tx.send((e.pid.clone(), e.nid.clone(), detail, e.event.is_complete()));
+        });
+        process.start();
+
+        let mut events = Vec::new();
+        loop {
+            let (pid, nid, detail, complete) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            events.push((pid, nid, detail));
+            if complete {
+                break;
+            }
+        }
+
+        engine.shutdown();
+        events
+    }
+
+    #[test]
+    fn test_deterministic_runs_repeat() {
+        let first = run_deterministic_diamond();
+        let second = run_deterministic_diamond();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|(pid, _, _)| pid == "process-1"));
+
+        // Each node finishes before the next one starts
+        let node_events: Vec<&str> = first.iter().map(|(_, _, detail)| detail.as_str()).filter(|detail| detail.contains('@')).collect();
+        assert_eq!(node_events.len(), 8);
+        for pair in node_events.chunks(2) {
+            assert!(
+                pair[0].starts_with("Running") && pair[1].starts_with("Succeeded"),
+                "{:?}",
+                node_events
+            );
         }
     }
+
+    #[test]
+    fn test_cancel_node_takes_fail_branch() {
+        // Accepts the connection but never answers, keeping the node running
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let workflow = WorkflowModel {
+            id: "cancel_node".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    error_strategy: Some("fail_branch".to_string()),
+                    ..http_node("fetch", port, 60_000)
+                },
+                code_node("done", json!([]), "function main() { return { ok: true } }"),
+                code_node(
+                    "fallback",
+                    json!([{ "variable": "error", "value_selector": "{{#fetch.error#}}" }]),
+                    "function main({ error }) { return { recovered_from: error } }",
+                ),
+            ],
+            edges: vec![edge("e1", "start", "fetch"), edge("e2", "fetch", "done"), branch_edge("e3", "fetch", "fallback", "fail_branch")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (running_tx, running_rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if e.nid == "fetch" && matches!(e.event, GraphEvent::Node(NodeEvent::Running(_))) {
+                let _ = running_tx.send(());
+            }
+        });
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if e.event.is_complete() {
+                let _ = tx.send(e.event.clone());
+            }
+        });
+
+        process.start();
+        running_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        process.cancel_node("fetch").unwrap();
+
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(event, GraphEvent::Workflow(WorkflowEvent::Succeeded)));
+
+        let outputs = process.get_outputs();
+        assert_eq!(
+            outputs.get::<serde_json::Value>("fallback"),
+            Some(json!({ "recovered_from": "Failed: Cancelled" }))
+        );
+        assert!(outputs.get::<serde_json::Value>("done").is_none());
+
+        engine.shutdown();
+    }
 }
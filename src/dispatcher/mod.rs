@@ -1,3 +1,8 @@
+mod concurrency;
+#[allow(clippy::module_inception)]
 mod dispatcher;
+mod serial;
 
+pub use concurrency::ConcurrencyLocks;
 pub use dispatcher::Dispatcher;
+pub use serial::SerialGate;
@@ -0,0 +1,105 @@
+//! Serial node execution for deterministic runs.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use tokio::sync::oneshot;
+
+use crate::workflow::node::NodeId;
+
+/// Lets one node run at a time, picking waiting nodes in topological order.
+///
+/// Nodes are enqueued when they become ready and only start once the
+/// dispatcher [`advance`](SerialGate::advance)s the gate, so the order nodes
+/// run in no longer depends on task scheduling.
+pub struct SerialGate {
+    /// Position of each node in the workflow's topological order.
+    order: HashMap<NodeId, usize>,
+    state: Mutex<GateState>,
+}
+
+#[derive(Default)]
+struct GateState {
+    /// Node currently holding the turn.
+    running: Option<NodeId>,
+    /// Nodes waiting for their turn, keyed by topological position.
+    waiting: BTreeMap<(usize, NodeId), oneshot::Sender<()>>,
+}
+
+impl SerialGate {
+    /// Creates a gate ordering nodes as listed in `order`.
+    pub fn new(order: Vec<NodeId>) -> Self {
+        Self {
+            order: order.into_iter().enumerate().map(|(i, nid)| (nid, i)).collect(),
+            state: Mutex::new(GateState::default()),
+        }
+    }
+
+    /// Queues `nid`; the returned receiver completes when it is the node's turn.
+    pub fn enqueue(
+        &self,
+        nid: &NodeId,
+    ) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let position = self.order.get(nid).copied().unwrap_or(usize::MAX);
+        self.state.lock().unwrap().waiting.insert((position, nid.clone()), tx);
+        rx
+    }
+
+    /// Releases the turn held by `nid`, if it holds it.
+    pub fn finish(
+        &self,
+        nid: &NodeId,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        if state.running.as_ref() == Some(nid) {
+            state.running = None;
+        }
+    }
+
+    /// Starts the first waiting node if no node holds the turn.
+    pub fn advance(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.running.is_some() {
+            return;
+        }
+        while let Some(((_, nid), tx)) = state.waiting.pop_first() {
+            if tx.send(()).is_ok() {
+                state.running = Some(nid);
+                return;
+            }
+        }
+    }
+
+    /// Drops every waiting node; their receivers complete with an error.
+    pub fn close(&self) {
+        self.state.lock().unwrap().waiting.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_releases_in_topological_order() {
+        let gate = SerialGate::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+
+        let mut c = gate.enqueue(&"c".to_string());
+        let mut b = gate.enqueue(&"b".to_string());
+
+        gate.advance();
+        assert!(b.try_recv().is_ok());
+        assert!(c.try_recv().is_err());
+
+        // c waits until b finishes
+        gate.advance();
+        assert!(c.try_recv().is_err());
+
+        gate.finish(&"b".to_string());
+        gate.advance();
+        assert!(c.try_recv().is_ok());
+    }
+}
@@ -4,18 +4,32 @@
 //! - Creating and running process instances
 //! - Graceful shutdown coordination
 
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering},
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
 };
 
+use futures::stream::BoxStream;
 use tokio::runtime::Runtime;
 
 use crate::{
-    ActflowError, ChannelEvent, ChannelOptions, Result,
-    common::{MemCache, Queue, Shutdown},
+    ActflowError, ActionOutput, ChannelEvent, ChannelOptions, NodeModel, Result,
+    common::{Clock, LogicalClock, MemCache, Queue, Shutdown, SystemClock, Vars},
+    dispatcher::{ConcurrencyLocks, Dispatcher},
+    events::{Event, Log, Message},
     model::WorkflowModel,
-    runtime::{Channel, Process, ProcessId},
+    runtime::{Channel, Context, DEFAULT_EVENT_HISTORY, DeadLetter, DeadLetters, Process, ProcessId, ProcessOptions, ProcessState},
+    utils,
+    workflow::{
+        functions::TemplateFunctions,
+        interceptor::{ActionInterceptor, ActionInterceptors},
+        node::Node,
+        template::{DEFAULT_TEMPLATE_DEPTH, NumberFormat},
+    },
 };
 
 /// Maximum number of processes to cache in memory.
@@ -23,6 +37,33 @@ const PROCESS_CACHE_SIZE: usize = 2048;
 /// Size of the queue for completed process notifications.
 const PROCESS_COMPLETE_QUEUE_SIZE: usize = 100;
 
+/// Limits on the size of workflow models an engine accepts.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct WorkflowLimits {
+    /// Maximum number of nodes, unlimited when `None`.
+    pub max_nodes: Option<usize>,
+    /// Maximum number of edges, unlimited when `None`.
+    pub max_edges: Option<usize>,
+}
+
+/// How long completed processes stay in the engine's cache.
+///
+/// With neither limit set, processes are removed as soon as they complete.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ProcessRetention {
+    /// Time a completed process is kept for.
+    pub ttl: Option<Duration>,
+    /// Maximum number of completed processes kept, the oldest evicted first.
+    pub max_completed: Option<usize>,
+}
+
+impl ProcessRetention {
+    /// Returns whether completed processes are kept at all.
+    fn is_enabled(&self) -> bool {
+        self.ttl.is_some() || self.max_completed.is_some()
+    }
+}
+
 /// The main workflow engine.
 ///
 /// Engine is the central coordinator for Actflow, responsible for:
@@ -33,8 +74,36 @@ pub struct Engine {
     channel: Arc<Channel>,
     /// Queue for receiving process completion notifications.
     procs_complete_queue: Arc<Queue<ProcessId>>,
-    /// In-memory cache of active processes.
+    /// In-memory cache of active processes, and of completed ones within the retention.
     procs: Arc<MemCache<ProcessId, Arc<Process>>>,
+    /// Client-supplied external ids mapped to the process they created.
+    external_ids: Arc<MemCache<String, ProcessId>>,
+    /// Functions callable from templates, shared by all processes.
+    functions: Arc<TemplateFunctions>,
+    /// Interceptors called around every action, shared by all processes.
+    interceptors: Arc<ActionInterceptors>,
+    /// Locks for node concurrency keys, shared by all processes.
+    concurrency_locks: Arc<ConcurrencyLocks>,
+    /// Sinks receiving the records of failed processes.
+    dead_letters: Arc<DeadLetters>,
+    /// Maximum number of passes made when resolving a template.
+    template_depth: usize,
+    /// How numbers are rendered in template strings.
+    number_format: NumberFormat,
+    /// Whether processes drop outputs once every node reading them has completed.
+    evict_consumed_outputs: bool,
+    /// Size limits checked before a process is built.
+    limits: WorkflowLimits,
+    /// How long completed processes stay fetchable.
+    retention: ProcessRetention,
+    /// Number of recent events each process keeps.
+    event_history: usize,
+    /// Time source handed to every process.
+    clock: Arc<dyn Clock>,
+    /// Whether processes run reproducibly, see [`EngineBuilder::deterministic`](crate::EngineBuilder::deterministic).
+    deterministic: bool,
+    /// Number of processes built, numbering processes in deterministic mode.
+    process_seq: AtomicU64,
 
     /// Flag indicating if the engine is running.
     running: Arc<AtomicBool>,
@@ -55,17 +124,98 @@ impl Engine {
             channel,
             procs_complete_queue,
             procs: Arc::new(MemCache::new(PROCESS_CACHE_SIZE)),
+            external_ids: Arc::new(MemCache::new(PROCESS_CACHE_SIZE)),
+            functions: Arc::new(TemplateFunctions::new()),
+            interceptors: Arc::new(ActionInterceptors::new()),
+            concurrency_locks: Arc::new(ConcurrencyLocks::new()),
+            dead_letters: Arc::new(DeadLetters::new()),
+            template_depth: DEFAULT_TEMPLATE_DEPTH,
+            number_format: NumberFormat::default(),
+            evict_consumed_outputs: false,
+            limits: WorkflowLimits::default(),
+            retention: ProcessRetention::default(),
+            event_history: DEFAULT_EVENT_HISTORY,
+            clock: Arc::new(SystemClock),
+            deterministic: false,
+            process_seq: AtomicU64::new(0),
             running: Arc::new(AtomicBool::new(false)),
             runtime,
             shutdown: Arc::new(Shutdown::new()),
         }
     }
 
+    /// Sets the size limits checked before a process is built.
+    pub(crate) fn with_limits(
+        mut self,
+        limits: WorkflowLimits,
+    ) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets how long completed processes stay fetchable.
+    pub(crate) fn with_retention(
+        mut self,
+        retention: ProcessRetention,
+    ) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Sets the number of recent events each process keeps.
+    pub(crate) fn with_event_history(
+        mut self,
+        event_history: usize,
+    ) -> Self {
+        self.event_history = event_history;
+        self
+    }
+
+    /// Sets the maximum number of passes made when resolving a template.
+    pub(crate) fn with_template_depth(
+        mut self,
+        depth: usize,
+    ) -> Self {
+        self.template_depth = depth;
+        self
+    }
+
+    /// Sets how numbers are rendered in template strings.
+    pub(crate) fn with_number_format(
+        mut self,
+        number_format: NumberFormat,
+    ) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Makes processes drop outputs once every node reading them has completed.
+    pub(crate) fn with_evict_consumed_outputs(mut self) -> Self {
+        self.evict_consumed_outputs = true;
+        self
+    }
+
+    /// Sets the time source used by processes.
+    pub(crate) fn with_clock(
+        mut self,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Makes processes run reproducibly.
+    pub(crate) fn with_deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
     /// Starts the engine and begins processing events.
     ///
     /// This method:
     /// - Begins listening on the event channel
-    /// - Spawns a background task to clean up completed processes
+    /// - Spawns a background task to clean up completed processes, once
+    ///   they are past the configured retention
     pub fn launch(&self) {
         if self.running.swap(true, Ordering::Relaxed) {
             return;
@@ -75,19 +225,52 @@ impl Engine {
 
         // Process complete queue
         let procs_complete_queue = self.procs_complete_queue.clone();
-        ChannelEvent::channel(self.channel.clone(), ChannelOptions::default()).on_complete(move |pid| {
+        let subscriber = ChannelEvent::channel(self.channel.clone(), ChannelOptions::default());
+        subscriber.on_complete(move |pid| {
             let _ = procs_complete_queue.send(pid);
         });
+        let procs_complete_queue = self.procs_complete_queue.clone();
+        subscriber.on_abort(move |pid, _| {
+            let _ = procs_complete_queue.send(pid);
+        });
+        let procs_complete_queue = self.procs_complete_queue.clone();
+        subscriber.on_error(move |e| {
+            let _ = procs_complete_queue.send(e.pid.clone());
+        });
 
         let procs_complete_queue = self.procs_complete_queue.clone();
         let shutdown = self.shutdown.clone();
         let procs = self.procs.clone();
+        let retention = self.retention;
+        let clock = self.clock.clone();
         self.runtime.spawn(async move {
+            // Completed processes kept for `max_completed`, oldest first
+            let mut retained = VecDeque::new();
             loop {
                 tokio::select! {
                     _ = shutdown.wait() => break,
                     Some(pid) = procs_complete_queue.next_async() => {
-                        procs.remove(&pid);
+                        if !retention.is_enabled() {
+                            procs.remove(&pid);
+                            continue;
+                        }
+                        if let Some(ttl) = retention.ttl {
+                            let procs = procs.clone();
+                            let expired = clock.sleep(ttl);
+                            let pid = pid.clone();
+                            tokio::spawn(async move {
+                                expired.await;
+                                procs.remove(&pid);
+                            });
+                        }
+                        if let Some(max_completed) = retention.max_completed {
+                            retained.push_back(pid);
+                            while retained.len() > max_completed {
+                                if let Some(evicted) = retained.pop_front() {
+                                    procs.remove(&evicted);
+                                }
+                            }
+                        }
                     }
                 }
             }
@@ -101,7 +284,7 @@ impl Engine {
     /// - Aborts all running processes
     /// - Shuts down the event channel
     pub fn shutdown(&self) {
-        if self.running.swap(false, Ordering::Relaxed) {
+        if !self.running.swap(false, Ordering::Relaxed) {
             return;
         }
 
@@ -112,16 +295,111 @@ impl Engine {
         self.channel.shutdown();
     }
 
+    /// Checks whether the engine is able to accept and run processes.
+    ///
+    /// Returns an error if the engine has not been launched, has been shut down,
+    /// or its event channel is no longer dispatching events.
+    pub fn health(&self) -> Result<()> {
+        if !self.running.load(Ordering::Relaxed) {
+            return Err(ActflowError::Engine("Engine is not running".to_string()));
+        }
+        if self.channel.is_closed() {
+            return Err(ActflowError::Engine("Event channel is closed".to_string()));
+        }
+        Ok(())
+    }
+
     /// Creates a new process instance from a workflow model.
+    ///
+    /// Returns [`ActflowError::Workflow`] if the model exceeds the node or edge
+    /// limits configured on the [`EngineBuilder`](crate::EngineBuilder).
     pub fn build_workflow_process(
         &self,
         workflow: &WorkflowModel,
+    ) -> Result<Arc<Process>> {
+        self.build_workflow_process_with(workflow, ProcessOptions::default())
+    }
+
+    /// Creates a new process instance with per-run options.
+    ///
+    /// Only the per-run fields of `options` are used, the rest come from the engine:
+    /// - `inputs` are referenced in templates as `{{@input.key@}}`
+    /// - each entry of `node_inputs` is placed in the process outputs under its
+    ///   node id before the process starts, so templates like `{{#n1.key#}}`
+    ///   resolve even for nodes that have not run yet
+    /// - `id` replaces the generated process id
+    /// - `labels` (e.g., `tenant_id`, `trigger_source`) are echoed in the workflow
+    ///   start event and can be used to look the process up with
+    ///   [`processes_with_label`](Self::processes_with_label)
+    /// - `correlation_id`, e.g. the id of the request that started the process,
+    ///   is carried by its logs and events instead of the process id
+    /// - `external_id` is a client-supplied key: if a process created with the
+    ///   same external id is still active, that process is returned instead of
+    ///   starting a duplicate run. Once it has completed, a new process is created
+    ///
+    /// ```rust,ignore
+    /// let process = engine.build_workflow_process_with(
+    ///     &workflow,
+    ///     ProcessOptions {
+    ///         inputs: Vars::new().with("user_id", 7),
+    ///         correlation_id: Some("req-42".to_string()),
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    /// ```
+    ///
+    /// Returns [`ActflowError::Workflow`] if the model exceeds the configured
+    /// limits or a key of `node_inputs` is not a node of the workflow.
+    pub fn build_workflow_process_with(
+        &self,
+        workflow: &WorkflowModel,
+        mut options: ProcessOptions,
+    ) -> Result<Arc<Process>> {
+        let Some(key) = options.external_id.take() else {
+            return self.build_process(workflow, options);
+        };
+        if let Some(pid) = self.external_ids.get(&key) {
+            if let Some(process) = self.procs.get(&pid)
+                && !process.is_complete()
+            {
+                return Ok(process);
+            }
+            self.external_ids.remove(&key);
+        }
+
+        let pid = self.external_ids.try_get_with(key, || self.build_process(workflow, options).map(|p| p.id().to_string()))?;
+        self.procs.get(&pid).ok_or(ActflowError::Process(format!("Process {} not found", pid)))
+    }
+
+    fn build_process(
+        &self,
+        workflow: &WorkflowModel,
+        options: ProcessOptions,
     ) -> Result<Arc<Process>> {
         if !self.running.load(Ordering::Relaxed) {
             return Err(ActflowError::Engine("Engine is not running".to_string()));
         }
+        self.check_limits(workflow)?;
+        let ProcessOptions {
+            inputs,
+            node_inputs,
+            id,
+            labels,
+            correlation_id,
+            ..
+        } = options;
+        let mut options = ProcessOptions {
+            inputs,
+            node_inputs,
+            labels,
+            correlation_id,
+            ..self.process_options()
+        };
+        if id.is_some() {
+            options.id = id;
+        }
         // Create a new process
-        let process = Process::new(workflow, self.channel.clone(), self.runtime.clone())?;
+        let process = Process::new(workflow, self.channel.clone(), self.runtime.clone(), options)?;
         let process_id = process.id().to_string();
 
         // Check if process already exists in cache
@@ -134,6 +412,127 @@ impl Engine {
         Ok(process)
     }
 
+    /// Settings for a new process.
+    ///
+    /// In deterministic mode each process gets a sequential id, a fresh
+    /// [`LogicalClock`] and runs its nodes one at a time.
+    fn process_options(&self) -> ProcessOptions {
+        let mut options = ProcessOptions {
+            functions: self.functions.clone(),
+            interceptors: self.interceptors.clone(),
+            concurrency_locks: self.concurrency_locks.clone(),
+            dead_letters: self.dead_letters.clone(),
+            template_depth: self.template_depth,
+            number_format: self.number_format,
+            evict_consumed_outputs: self.evict_consumed_outputs,
+            event_history: self.event_history,
+            clock: self.clock.clone(),
+            ..ProcessOptions::default()
+        };
+        if self.deterministic {
+            let seq = self.process_seq.fetch_add(1, Ordering::Relaxed) + 1;
+            options.id = Some(format!("process-{}", seq));
+            options.clock = Arc::new(LogicalClock::default());
+            options.serial = true;
+        }
+        options
+    }
+
+    /// Rejects workflow models larger than the configured limits.
+    fn check_limits(
+        &self,
+        workflow: &WorkflowModel,
+    ) -> Result<()> {
+        if let Some(max) = self.limits.max_nodes
+            && workflow.nodes.len() > max
+        {
+            return Err(ActflowError::Workflow(format!(
+                "workflow {} has {} nodes, exceeding the limit of {}",
+                workflow.id,
+                workflow.nodes.len(),
+                max
+            )));
+        }
+        if let Some(max) = self.limits.max_edges
+            && workflow.edges.len() > max
+        {
+            return Err(ActflowError::Workflow(format!(
+                "workflow {} has {} edges, exceeding the limit of {}",
+                workflow.id,
+                workflow.edges.len(),
+                max
+            )));
+        }
+        Ok(())
+    }
+
+    /// Runs a single node in isolation, without building a workflow.
+    ///
+    /// Each top-level key of `inputs` is a node id whose value is seeded as that
+    /// node's outputs, so templates like `{{#n1.key#}}` resolve as they would in
+    /// a full run. Blocks until the action finishes, so it must not be called
+    /// from within an async context.
+    pub fn run_node(
+        &self,
+        node_model: &NodeModel,
+        inputs: Vars,
+    ) -> Result<ActionOutput> {
+        let node = Node::new(Vars::from(serde_json::to_value(node_model)?))?;
+
+        let ctx = Arc::new(
+            Context::new(utils::longid(), self.channel.clone())
+                .with_functions(self.functions.clone())
+                .with_template_depth(self.template_depth)
+                .with_number_format(self.number_format),
+        );
+        for (nid, outputs) in inputs.iter() {
+            ctx.add_output(nid.clone(), Vars::from(outputs.clone()));
+        }
+        if !node.inputs.is_empty() {
+            ctx.add_inputs(node.id.clone(), Dispatcher::resolve_inputs(&ctx, &node)?);
+        }
+
+        self.runtime.block_on(node.action.run(ctx, node.id.clone()))
+    }
+
+    /// Re-runs a failed process from the nodes that did not finish.
+    ///
+    /// Builds a new process from the same workflow, inputs, labels and correlation id in which
+    /// the nodes the failed run executed are already complete, with their
+    /// outputs seeded, and nodes it skipped stay skipped. Starting it runs the
+    /// failed node(s) and everything after them, sparing the successful steps.
+    ///
+    /// The failed process is looked up like [`get_process`](Self::get_process),
+    /// so it must still be retained: enable
+    /// [`retain_completed_for`](crate::EngineBuilder::retain_completed_for) or
+    /// [`max_completed_processes`](crate::EngineBuilder::max_completed_processes)
+    /// and retry within that window. Without retention, finished processes are
+    /// evicted right away and cannot be retried.
+    ///
+    /// Returns [`ActflowError::Process`] if the process is unknown or has not failed.
+    pub fn retry_process(
+        &self,
+        process_id: &str,
+    ) -> Result<Arc<Process>> {
+        let process = self.procs.get(&process_id.to_string()).ok_or_else(|| ActflowError::Process(format!("Process {} not found", process_id)))?;
+        if process.state() != ProcessState::Failed {
+            return Err(ActflowError::Process(format!("Process {} has not failed", process.id())));
+        }
+        let checkpoint = process.checkpoint();
+        let retry = self.build_workflow_process_with(
+            process.model(),
+            ProcessOptions {
+                inputs: process.inputs().clone(),
+                node_inputs: checkpoint.outputs.clone(),
+                labels: process.labels().clone(),
+                correlation_id: Some(process.correlation_id().to_string()),
+                ..Default::default()
+            },
+        )?;
+        retry.restore(&checkpoint);
+        Ok(retry)
+    }
+
     /// Stops a running process by its id.
     pub fn stop(
         &self,
@@ -148,7 +547,26 @@ impl Engine {
         }
     }
 
+    /// Resumes a paused node of a running process with the given outputs.
+    pub fn resume_node(
+        &self,
+        process_id: &str,
+        nid: &str,
+        outputs: Vars,
+    ) -> Result<()> {
+        let process_id_string = process_id.to_string();
+        if let Some(process) = self.procs.get(&process_id_string) {
+            process.resume_node(nid, outputs)
+        } else {
+            Err(ActflowError::Process(format!("Process {} not found", process_id)))
+        }
+    }
+
     /// Gets a process by its id from the cache.
+    ///
+    /// Completed processes are only found within the retention configured with
+    /// [`EngineBuilder::retain_completed_for`](crate::EngineBuilder::retain_completed_for)
+    /// or [`EngineBuilder::max_completed_processes`](crate::EngineBuilder::max_completed_processes).
     pub fn get_process(
         &self,
         process_id: &String,
@@ -156,8 +574,676 @@ impl Engine {
         self.procs.get(process_id)
     }
 
+    /// Returns the cached processes whose label `key` equals `value`, including
+    /// completed ones still within the retention.
+    pub fn processes_with_label(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> Vec<Arc<Process>> {
+        self.procs.iter().map(|(_, process)| process).filter(|process| process.labels().get(key).is_some_and(|v| v == value)).collect()
+    }
+
+    /// Registers a function callable from templates as `{{ name(args) }}`.
+    ///
+    /// Registering under the name of a built-in (`now`, `uuid`, `upper`,
+    /// `base64encode`, `base64decode`) replaces it.
+    pub fn register_template_function(
+        &self,
+        name: &str,
+        f: impl Fn(&[String]) -> Result<String> + Send + Sync + 'static,
+    ) {
+        self.functions.register(name, f);
+    }
+
+    /// Registers an interceptor called around every action of every process.
+    ///
+    /// Interceptors run in registration order; see [`ActionInterceptor`].
+    pub fn register_interceptor(
+        &self,
+        interceptor: impl ActionInterceptor + 'static,
+    ) {
+        self.interceptors.register(Arc::new(interceptor));
+    }
+
+    /// Registers a sink receiving a [`DeadLetter`] for every process that fails.
+    ///
+    /// A process fails once the failing node's error strategy and retries are
+    /// exhausted; the record names that node and carries the process inputs
+    /// so the run can be inspected and reprocessed. Sinks run on the event
+    /// thread, before `on_error` subscribers, and should hand heavy work off.
+    pub fn on_dead_letter(
+        &self,
+        sink: impl Fn(&DeadLetter) + Send + Sync + 'static,
+    ) {
+        self.dead_letters.register(sink);
+    }
+
+    /// Subscribes to events matching `options` as an async stream.
+    ///
+    /// ```rust,ignore
+    /// let mut events = engine.subscribe(ChannelOptions::with_pid(pid));
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event.event);
+    /// }
+    /// ```
+    pub fn subscribe(
+        &self,
+        options: ChannelOptions,
+    ) -> BoxStream<'static, Event<Message>> {
+        ChannelEvent::channel(self.channel.clone(), options).stream()
+    }
+
+    /// Subscribes to logs matching `options` as an async stream, e.g. to tail
+    /// a process's logs in a console.
+    ///
+    /// ```rust,ignore
+    /// let mut logs = engine.subscribe_logs(ChannelOptions::with_pid(pid));
+    /// while let Some(log) = logs.next().await {
+    ///     println!("[{}] {}", log.nid, log.content);
+    /// }
+    /// ```
+    pub fn subscribe_logs(
+        &self,
+        options: ChannelOptions,
+    ) -> BoxStream<'static, Event<Log>> {
+        ChannelEvent::channel(self.channel.clone(), options).log_stream()
+    }
+
     /// Returns a reference to the event channel.
     pub fn channel(&self) -> Arc<Channel> {
         self.channel.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, Ordering},
+            mpsc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use crate::test_support::{edge, node, start_and_wait, start_node};
+    use crate::{
+        ActflowError, ActionInterceptor, ChannelEvent, ChannelOptions, Context, Engine, EngineBuilder, FailurePolicy, GraphEvent, Node, NodeEvent,
+        NodeExecutionStatus, NodeModel, ProcessOptions, ProcessState, Vars, WorkflowEvent, WorkflowModel, runtime::Process, workflow::actions::ActionOutput,
+    };
+
+    fn create_workflow() -> WorkflowModel {
+        WorkflowModel {
+            id: "test".to_string(),
+            nodes: vec![start_node("n1")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_processes_with_label() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let tenant = |id: &str| HashMap::from([("tenant_id".to_string(), id.to_string())]);
+        let acme = engine
+            .build_workflow_process_with(
+                &create_workflow(),
+                ProcessOptions {
+                    labels: tenant("acme"),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let globex = engine
+            .build_workflow_process_with(
+                &create_workflow(),
+                ProcessOptions {
+                    labels: tenant("globex"),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        engine.build_workflow_process(&create_workflow()).unwrap();
+
+        let found = engine.processes_with_label("tenant_id", "acme");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id(), acme.id());
+        assert!(engine.processes_with_label("tenant_id", "initech").is_empty());
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(globex.id().to_string())).on_event(move |e| {
+            if let GraphEvent::Workflow(WorkflowEvent::Start(start)) = &e.event {
+                let _ = tx.send(start.labels.clone());
+            }
+        });
+        globex.start();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), tenant("globex"));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_build_workflow_process_with_process_id() {
+        let engine = EngineBuilder::new().deterministic().build().unwrap();
+        engine.launch();
+
+        let options = ProcessOptions {
+            id: Some("order-7".to_string()),
+            ..Default::default()
+        };
+        let handlers = engine.channel().event_handler_count();
+        // The caller's id wins over the sequential one of deterministic mode
+        let process = engine.build_workflow_process_with(&create_workflow(), options.clone()).unwrap();
+        assert_eq!(process.id(), "order-7");
+        assert!(matches!(
+            engine.build_workflow_process_with(&create_workflow(), options),
+            Err(ActflowError::Process(_))
+        ));
+        // Neither the unstarted process nor the rejected duplicate subscribed to its events
+        assert_eq!(engine.channel().event_handler_count(), handlers);
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_build_workflow_process_node_limit() {
+        let engine = EngineBuilder::new().max_nodes(1).build().unwrap();
+        engine.launch();
+
+        assert!(engine.build_workflow_process(&create_workflow()).is_ok());
+
+        let mut workflow = create_workflow();
+        workflow.nodes.push(node("n2", "end", json!({})));
+        let result = engine.build_workflow_process(&workflow);
+        assert!(matches!(result, Err(ActflowError::Workflow(_))));
+
+        engine.shutdown();
+    }
+
+    #[cfg(feature = "js")]
+    #[test]
+    fn test_run_node() {
+        use crate::NodeExecutionStatus;
+
+        let engine = EngineBuilder::new().build().unwrap();
+        let node = NodeModel {
+            id: "n2".to_string(),
+            uses: "code".to_string(),
+            action: json!({
+                "variables": [{ "variable": "value", "value_selector": "{{#n1.value#}}" }],
+                "code_language": "javascript",
+                "code": "function main({ value }) { return { doubled: value * 2 } }"
+            }),
+            ..Default::default()
+        };
+
+        let output = engine.run_node(&node, Vars::new().with("n1", json!({ "value": 21 }))).unwrap();
+        assert_eq!(output.status, NodeExecutionStatus::Succeeded);
+        assert_eq!(output.outputs.get::<i64>("doubled"), Some(42));
+    }
+
+    #[test]
+    fn test_subscribe_stream() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let engine = EngineBuilder::new().runtime(runtime.clone()).build().unwrap();
+        engine.launch();
+
+        let process = engine.build_workflow_process(&create_workflow()).unwrap();
+        let mut events = engine.subscribe(ChannelOptions::with_pid(process.id().to_string()));
+        process.start();
+
+        let collected = runtime.block_on(async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                let mut collected = Vec::new();
+                while let Some(event) = events.next().await {
+                    let complete = event.event.is_complete();
+                    collected.push(event);
+                    if complete {
+                        break;
+                    }
+                }
+                collected
+            })
+            .await
+            .unwrap()
+        });
+
+        assert!(collected.iter().all(|e| e.pid == process.id()));
+        assert!(matches!(
+            collected.first().unwrap().event,
+            GraphEvent::Workflow(WorkflowEvent::Start(_))
+        ));
+        assert!(collected.iter().any(|e| e.nid == "n1" && matches!(e.event, GraphEvent::Node(NodeEvent::Succeeded(_)))));
+
+        engine.shutdown();
+    }
+
+    struct LoggingInterceptor;
+
+    impl ActionInterceptor for LoggingInterceptor {
+        fn before_run(
+            &self,
+            ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            ctx.emit_log(node.id.clone(), "running".to_string());
+            None
+        }
+    }
+
+    #[test]
+    fn test_correlation_id_on_logs_and_events() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let engine = EngineBuilder::new().runtime(runtime.clone()).build().unwrap();
+        engine.register_interceptor(LoggingInterceptor);
+        engine.launch();
+
+        let process = engine
+            .build_workflow_process_with(
+                &create_workflow(),
+                ProcessOptions {
+                    correlation_id: Some("req-42".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(process.correlation_id(), "req-42");
+        let mut events = engine.subscribe(ChannelOptions::with_pid(process.id().to_string()));
+        let mut logs = engine.subscribe_logs(ChannelOptions::with_pid(process.id().to_string()));
+        process.start();
+
+        let (events, log) = runtime.block_on(async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                let mut collected = Vec::new();
+                while let Some(event) = events.next().await {
+                    let complete = event.event.is_complete();
+                    collected.push(event);
+                    if complete {
+                        break;
+                    }
+                }
+                (collected, logs.next().await.unwrap())
+            })
+            .await
+            .unwrap()
+        });
+        assert!(events.len() > 2);
+        assert!(events.iter().all(|e| e.correlation_id == "req-42"));
+        assert_eq!(log.content, "running");
+        assert_eq!(log.correlation_id, "req-42");
+
+        // Without one, the process id is used
+        let process = engine.build_workflow_process(&create_workflow()).unwrap();
+        assert_eq!(process.correlation_id(), process.id());
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_subscribe_logs_stream() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let engine = EngineBuilder::new().runtime(runtime.clone()).build().unwrap();
+        engine.launch();
+
+        let mut logs = engine.subscribe_logs(ChannelOptions::new("p1".to_string(), "agent*".to_string()));
+
+        let ctx = Context::new("p1".to_string(), engine.channel());
+        let other = Context::new("p2".to_string(), engine.channel());
+        ctx.emit_sequenced_log("agent1".to_string(), "connecting".to_string(), 0, None);
+        other.emit_log("agent1".to_string(), "other process".to_string());
+        ctx.emit_log("start".to_string(), "other node".to_string());
+        ctx.emit_sequenced_log("agent1".to_string(), "streaming".to_string(), 1, None);
+        ctx.emit_sequenced_log("agent1".to_string(), "done".to_string(), 2, None);
+
+        let collected = runtime.block_on(async { tokio::time::timeout(Duration::from_secs(5), logs.by_ref().take(3).collect::<Vec<_>>()).await.unwrap() });
+
+        assert!(collected.iter().all(|log| log.pid == "p1" && log.nid == "agent1"));
+        assert_eq!(
+            collected.iter().map(|log| (log.seq, log.content.as_str())).collect::<Vec<_>>(),
+            vec![(Some(0), "connecting"), (Some(1), "streaming"), (Some(2), "done")]
+        );
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_health_after_launch() {
+        let engine = EngineBuilder::new().build().unwrap();
+        assert!(engine.health().is_err());
+
+        engine.launch();
+        assert!(engine.health().is_ok());
+    }
+
+    #[test]
+    fn test_health_after_shutdown() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        engine.shutdown();
+
+        assert!(engine.health().is_err());
+    }
+
+    #[test]
+    fn test_shutdown_running_engine() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        engine.shutdown();
+        assert!(engine.channel().is_closed());
+        assert!(engine.build_workflow_process(&create_workflow()).is_err());
+
+        // Shutting down again is a no-op
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_resume_node_unknown_process() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        assert!(engine.resume_node("unknown", "n1", Vars::new()).is_err());
+    }
+
+    /// Pauses `approve` instead of running it.
+    struct ApprovalInterceptor;
+
+    impl ActionInterceptor for ApprovalInterceptor {
+        fn before_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            (node.id == "approve").then(ActionOutput::paused)
+        }
+    }
+
+    #[test]
+    fn test_pause_and_resume_node() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let engine = EngineBuilder::new().runtime(runtime.clone()).build().unwrap();
+        engine.launch();
+        engine.register_interceptor(ApprovalInterceptor);
+
+        let workflow = WorkflowModel {
+            id: "approval".to_string(),
+            nodes: vec![
+                start_node("n1"),
+                NodeModel {
+                    output_transform: Some(json!({ "approved_by": "{{#approve.user#}}" })),
+                    ..node(
+                        "approve",
+                        "code",
+                        json!({ "variables": [], "code_language": "javascript", "code": "function main() { return {} }" }),
+                    )
+                },
+                node("n3", "end", json!({ "outputs": { "by": "{{#approve.approved_by#}}" } })),
+            ],
+            edges: vec![edge("e1", "n1", "approve"), edge("e2", "approve", "n3")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        let paused = process.wait_for_node("approve", NodeExecutionStatus::Paused);
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if e.event.is_finished() {
+                let _ = tx.send(e.event.clone());
+            }
+        });
+        process.start();
+
+        runtime.block_on(async { tokio::time::timeout(Duration::from_secs(5), paused).await.unwrap().unwrap() });
+        assert_eq!(process.state(), ProcessState::Paused);
+        engine.resume_node(process.id(), "approve", Vars::new().with("user", "alice")).unwrap();
+
+        // The resumed outputs go through the node's output transform like an action's
+        assert!(rx.recv_timeout(Duration::from_secs(5)).unwrap().is_complete());
+        let outputs = process.get_outputs();
+        assert_eq!(
+            outputs.get::<serde_json::Value>("approve"),
+            Some(json!({ "approved_by": "alice" }))
+        );
+        assert_eq!(outputs.get::<serde_json::Value>("n3"), Some(json!({ "by": "alice" })));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_build_workflow_process_with_external_id() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        let workflow = create_workflow();
+
+        let keyed = |external_id: &str| ProcessOptions {
+            external_id: Some(external_id.to_string()),
+            ..Default::default()
+        };
+
+        let first = engine.build_workflow_process_with(&workflow, keyed("request-1")).unwrap();
+        let second = engine.build_workflow_process_with(&workflow, keyed("request-1")).unwrap();
+        let other = engine.build_workflow_process_with(&workflow, keyed("request-2")).unwrap();
+
+        assert_eq!(first.id(), second.id());
+        assert_ne!(first.id(), other.id());
+    }
+
+    /// `n1 -> n2`, where `n2` throws.
+    fn create_failing_workflow() -> WorkflowModel {
+        WorkflowModel {
+            id: "failing".to_string(),
+            nodes: vec![
+                start_node("n1"),
+                node(
+                    "n2",
+                    "code",
+                    json!({ "variables": [], "code_language": "javascript", "code": "function main() { throw new Error('boom') }" }),
+                ),
+            ],
+            edges: vec![edge("e1", "n1", "n2")],
+            ..Default::default()
+        }
+    }
+
+    /// Starts a process and waits until it completes.
+    fn run_to_completion(
+        engine: &Engine,
+        workflow: &WorkflowModel,
+    ) -> Arc<Process> {
+        let process = engine.build_workflow_process(workflow).unwrap();
+        assert!(start_and_wait(engine, &process).is_complete());
+        process
+    }
+
+    /// Waits until the engine no longer holds `pid`, returning whether it did in time.
+    fn wait_evicted(
+        engine: &Engine,
+        pid: &str,
+    ) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if engine.get_process(&pid.to_string()).is_none() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[test]
+    fn test_completed_process_is_removed_by_default() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let process = run_to_completion(&engine, &create_workflow());
+        assert!(wait_evicted(&engine, process.id()));
+
+        let failed = engine.build_workflow_process(&create_failing_workflow()).unwrap();
+        assert!(start_and_wait(&engine, &failed).is_error());
+        assert!(wait_evicted(&engine, failed.id()));
+        // Without retention there is nothing left to retry
+        assert!(matches!(engine.retry_process(failed.id()), Err(ActflowError::Process(_))));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_retain_completed_for() {
+        let engine = EngineBuilder::new().retain_completed_for(Duration::from_millis(300)).build().unwrap();
+        engine.launch();
+
+        let process = run_to_completion(&engine, &create_workflow());
+        let retained = engine.get_process(&process.id().to_string()).unwrap();
+        assert_eq!(retained.state(), ProcessState::Succeeded);
+
+        assert!(wait_evicted(&engine, process.id()));
+
+        // Failed runs are retained and evicted the same way
+        let failed = engine.build_workflow_process(&create_failing_workflow()).unwrap();
+        assert!(start_and_wait(&engine, &failed).is_error());
+        let retained = engine.get_process(&failed.id().to_string()).unwrap();
+        assert_eq!(retained.state(), ProcessState::Failed);
+
+        assert!(wait_evicted(&engine, failed.id()));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_max_completed_processes() {
+        let engine = EngineBuilder::new().max_completed_processes(1).build().unwrap();
+        engine.launch();
+
+        let first = run_to_completion(&engine, &create_workflow());
+        thread::sleep(Duration::from_millis(100));
+        assert!(engine.get_process(&first.id().to_string()).is_some());
+
+        let second = run_to_completion(&engine, &create_workflow());
+        assert!(wait_evicted(&engine, first.id()));
+        assert!(engine.get_process(&second.id().to_string()).is_some());
+
+        engine.shutdown();
+    }
+
+    /// Fails `n2` until fixed, recording the nodes that run.
+    #[derive(Default)]
+    struct FlakyInterceptor {
+        fixed: AtomicBool,
+        ran: Mutex<Vec<String>>,
+    }
+
+    impl ActionInterceptor for Arc<FlakyInterceptor> {
+        fn before_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            self.ran.lock().unwrap().push(node.id.clone());
+            match node.id.as_str() {
+                "n2" if self.fixed.load(Ordering::SeqCst) => Some(ActionOutput::success(Vars::new().with("value", 42))),
+                "n2" => Some(ActionOutput::failed("upstream unavailable".to_string())),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_process_from_failed_node() {
+        // The failed run must still be retained to be retried
+        let engine = EngineBuilder::new().retain_completed_for(Duration::from_secs(60)).build().unwrap();
+        engine.launch();
+        let interceptor = Arc::new(FlakyInterceptor::default());
+        engine.register_interceptor(interceptor.clone());
+
+        let workflow = WorkflowModel {
+            id: "retry".to_string(),
+            nodes: vec![
+                start_node("n1"),
+                node(
+                    "n2",
+                    "http_request",
+                    json!({
+                        "url": "http://127.0.0.1:9/",
+                        "method": "GET",
+                        "auth": { "auth_type": "no_auth" },
+                        "headers": {},
+                        "params": {},
+                        "body": { "content_type": "none" },
+                        "timeout": 1000
+                    }),
+                ),
+                node("n3", "end", json!({ "outputs": { "value": "{{#n2.value#}}" } })),
+            ],
+            edges: vec![edge("e1", "n1", "n2"), edge("e2", "n2", "n3")],
+            ..Default::default()
+        };
+
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        assert!(matches!(engine.retry_process("missing"), Err(ActflowError::Process(_))));
+        assert!(matches!(engine.retry_process(process.id()), Err(ActflowError::Process(_))));
+        start_and_wait(&engine, &process);
+        assert_eq!(process.state(), ProcessState::Failed);
+        assert_eq!(*interceptor.ran.lock().unwrap(), vec!["n1", "n2"]);
+
+        interceptor.fixed.store(true, Ordering::SeqCst);
+        interceptor.ran.lock().unwrap().clear();
+        let retry = engine.retry_process(process.id()).unwrap();
+        start_and_wait(&engine, &retry);
+
+        assert_eq!(retry.state(), ProcessState::Succeeded);
+        assert_eq!(*interceptor.ran.lock().unwrap(), vec!["n2", "n3"]);
+        assert_eq!(retry.get_outputs().get::<serde_json::Value>("n3"), Some(json!({ "value": 42 })));
+    }
+
+    #[test]
+    fn test_dead_letter_for_failed_process() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        engine.register_interceptor(Arc::new(FlakyInterceptor::default()));
+
+        let (tx, rx) = mpsc::channel();
+        engine.on_dead_letter(move |letter| {
+            let _ = tx.send(letter.clone());
+        });
+
+        let mut workflow = WorkflowModel {
+            id: "dead_letter".to_string(),
+            nodes: vec![start_node("n1"), node("n2", "end", json!({}))],
+            edges: vec![edge("e1", "n1", "n2")],
+            ..Default::default()
+        };
+
+        // Successful runs leave no record
+        let succeeded = engine.build_workflow_process(&create_workflow()).unwrap();
+        succeeded.start();
+
+        // The dispatcher names the failing node under either policy
+        for failure_policy in [FailurePolicy::FailFast, FailurePolicy::WaitAll] {
+            workflow.failure_policy = failure_policy;
+            let process = engine
+                .build_workflow_process_with(
+                    &workflow,
+                    ProcessOptions {
+                        inputs: Vars::new().with("order_id", 7),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            process.start();
+
+            let letter = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(letter.pid, process.id());
+            assert_eq!(letter.wid, "dead_letter");
+            assert_eq!(letter.nid, "n2");
+            assert!(letter.error.contains("upstream unavailable"), "{}", letter.error);
+            assert_eq!(letter.inputs.get::<i64>("order_id"), Some(7));
+        }
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
@@ -72,6 +72,14 @@ pub enum ActflowError {
     /// Message queue errors.
     #[error("{0}")]
     Queue(String),
+
+    /// Invalid value in a definition, located by a JSON pointer.
+    #[error("invalid value at '{path}': {message}")]
+    Validation {
+        /// JSON pointer to the offending field, e.g. `/cases/0/logical_operator`.
+        path: String,
+        message: String,
+    },
 }
 
 impl From<ActflowError> for String {
@@ -107,6 +115,9 @@ impl From<serde_json::Error> for ActflowError {
 
 impl From<jsonschema::ValidationError<'_>> for ActflowError {
     fn from(error: jsonschema::ValidationError<'_>) -> Self {
-        ActflowError::Runtime(error.to_string())
+        ActflowError::Validation {
+            path: error.instance_path().to_string(),
+            message: error.to_string(),
+        }
     }
 }
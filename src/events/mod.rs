@@ -31,6 +31,8 @@ pub enum GraphEvent {
 pub struct Message {
     /// Process ID that generated this event.
     pub pid: ProcessId,
+    /// Correlation ID of the process, the process ID unless one was supplied.
+    pub correlation_id: String,
     /// Node ID that generated this event (empty for workflow events).
     pub nid: NodeId,
     /// The actual event data.
@@ -42,12 +44,18 @@ pub struct Message {
 pub struct Log {
     /// Process ID that generated this log.
     pub pid: ProcessId,
+    /// Correlation ID of the process, the process ID unless one was supplied.
+    pub correlation_id: String,
     /// Node ID that generated this log.
     pub nid: NodeId,
     /// Log message content.
     pub content: String,
     /// Timestamp in milliseconds of the log entry.
     pub timestamp: i64,
+    /// Position of the log within the node's log stream, when the source numbers its logs.
+    pub seq: Option<u64>,
+    /// Phase of the node's work the log belongs to, when the source reports one.
+    pub phase: Option<String>,
 }
 
 impl<T> std::ops::Deref for Event<T>
@@ -83,4 +91,11 @@ impl GraphEvent {
     pub fn is_error(&self) -> bool {
         matches!(self, GraphEvent::Workflow(WorkflowEvent::Failed(_)))
     }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            GraphEvent::Workflow(WorkflowEvent::Succeeded | WorkflowEvent::Failed(_) | WorkflowEvent::Aborted(_))
+        )
+    }
 }
@@ -2,6 +2,8 @@
 
 use std::fmt;
 
+use crate::common::Vars;
+
 /// Events emitted during node execution.
 ///
 /// Each variant includes a timestamp (i64 milliseconds) where applicable.
@@ -21,6 +23,13 @@ pub enum NodeEvent {
     Error(ErrorReason),
     /// Node is retrying after a failure.
     Retry,
+    /// Domain event published by an action.
+    Custom {
+        /// Event name chosen by the action (e.g., "payment_authorized").
+        name: String,
+        /// Event payload.
+        payload: Vars,
+    },
 }
 
 impl NodeEvent {
@@ -34,6 +43,9 @@ impl NodeEvent {
             NodeEvent::Succeeded(_) => "Succeeded",
             NodeEvent::Error(_) => "Error",
             NodeEvent::Retry => "Retry",
+            NodeEvent::Custom {
+                ..
+            } => "Custom",
         }
     }
 }
@@ -37,6 +37,8 @@ impl WorkflowEvent {
 pub struct WorkflowStartEvent {
     /// All node IDs in the workflow for batch initialization.
     pub node_ids: Vec<NodeId>,
+    /// Labels the process was built with (e.g., `tenant_id`).
+    pub labels: HashMap<String, String>,
 }
 
 /// Event emitted when a workflow fails.
@@ -27,23 +27,34 @@
 
 mod builder;
 mod common;
+mod config;
 mod dispatcher;
 mod engine;
 mod error;
 mod events;
 mod model;
 mod runtime;
+#[cfg(test)]
+pub(crate) mod test_support;
 mod utils;
 mod workflow;
 
 use std::sync::{Arc, RwLock};
 
 pub use builder::EngineBuilder;
+pub use common::{Clock, LogicalClock, MockClock, SystemClock, Vars};
+pub use config::{Config, LimitsConfig};
 pub use engine::Engine;
 pub use error::ActflowError;
 pub use events::*;
 pub use model::*;
-pub use runtime::{ChannelEvent, ChannelOptions};
+pub use runtime::{ChannelEvent, ChannelOptions, Context, DeadLetter, EffectiveConfig, NodeTiming, ProcessOptions, ProcessState, RunStatus, RunSummary};
+pub use workflow::{
+    actions::ActionOutput,
+    interceptor::ActionInterceptor,
+    node::{Node, NodeExecutionStatus},
+    template::NumberFormat,
+};
 
 /// Result type alias for Actflow operations.
 pub type Result<T> = std::result::Result<T, ActflowError>;
@@ -0,0 +1,148 @@
+//! Differences between two versions of a workflow.
+
+use std::collections::HashMap;
+
+use crate::model::WorkflowModel;
+
+/// Nodes and edges that changed between two workflow versions, by id.
+///
+/// Ids in each list are sorted. A node or edge is modified when any of its
+/// fields differ, including its action configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkflowDiff {
+    /// Nodes only present in the new version.
+    pub added_nodes: Vec<String>,
+    /// Nodes only present in the old version.
+    pub removed_nodes: Vec<String>,
+    /// Nodes present in both versions with different definitions.
+    pub modified_nodes: Vec<String>,
+    /// Edges only present in the new version.
+    pub added_edges: Vec<String>,
+    /// Edges only present in the old version.
+    pub removed_edges: Vec<String>,
+    /// Edges present in both versions with different definitions.
+    pub modified_edges: Vec<String>,
+}
+
+impl WorkflowDiff {
+    /// Returns `true` if the nodes and edges of both versions are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.modified_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.modified_edges.is_empty()
+    }
+}
+
+impl WorkflowModel {
+    /// Compares the nodes and edges of this workflow with `other`, the newer version.
+    ///
+    /// Workflow-level settings such as `env` or `vars` are not compared.
+    pub fn diff(
+        &self,
+        other: &WorkflowModel,
+    ) -> WorkflowDiff {
+        let (added_nodes, removed_nodes, modified_nodes) = diff_by_id(&self.nodes, &other.nodes, |node| &node.id);
+        let (added_edges, removed_edges, modified_edges) = diff_by_id(&self.edges, &other.edges, |edge| &edge.id);
+        WorkflowDiff {
+            added_nodes,
+            removed_nodes,
+            modified_nodes,
+            added_edges,
+            removed_edges,
+            modified_edges,
+        }
+    }
+}
+
+/// Returns the sorted ids of added, removed and modified items.
+fn diff_by_id<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    id: impl Fn(&T) -> &String,
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let old: HashMap<&String, &T> = old.iter().map(|item| (id(item), item)).collect();
+    let new: HashMap<&String, &T> = new.iter().map(|item| (id(item), item)).collect();
+
+    let mut added: Vec<String> = new.keys().filter(|key| !old.contains_key(*key)).map(|key| key.to_string()).collect();
+    let mut removed: Vec<String> = old.keys().filter(|key| !new.contains_key(*key)).map(|key| key.to_string()).collect();
+    let mut modified: Vec<String> =
+        old.iter().filter(|(key, item)| new.get(*key).is_some_and(|other| other != *item)).map(|(key, _)| key.to_string()).collect();
+
+    added.sort();
+    removed.sort();
+    modified.sort();
+    (added, removed, modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{EdgeModel, NodeModel};
+
+    fn create_workflow() -> WorkflowModel {
+        let node = |id: &str, uses: &str, action: serde_json::Value| NodeModel {
+            id: id.to_string(),
+            uses: uses.to_string(),
+            action,
+            ..Default::default()
+        };
+        WorkflowModel {
+            id: "wf".to_string(),
+            nodes: vec![
+                node("start", "start", json!({})),
+                node("fetch", "http_request", json!({ "url": "https://api.example.com/v1" })),
+                node("notify", "http_request", json!({ "url": "https://hooks.example.com" })),
+            ],
+            edges: vec![EdgeModel {
+                id: "e1".to_string(),
+                source: "start".to_string(),
+                target: "fetch".to_string(),
+                source_handle: "source".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_changes_by_id() {
+        let old = create_workflow();
+        let mut new = create_workflow();
+        new.nodes[1].action = json!({ "url": "https://api.example.com/v2" });
+        new.nodes.retain(|node| node.id != "notify");
+        new.nodes.push(NodeModel {
+            id: "end".to_string(),
+            uses: "end".to_string(),
+            action: json!({}),
+            ..Default::default()
+        });
+        new.edges.push(EdgeModel {
+            id: "e2".to_string(),
+            source: "fetch".to_string(),
+            target: "end".to_string(),
+            source_handle: "source".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            old.diff(&new),
+            WorkflowDiff {
+                added_nodes: vec!["end".to_string()],
+                removed_nodes: vec!["notify".to_string()],
+                modified_nodes: vec!["fetch".to_string()],
+                added_edges: vec!["e2".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_identical_is_empty() {
+        assert!(create_workflow().diff(&create_workflow()).is_empty());
+    }
+}
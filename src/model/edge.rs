@@ -17,9 +17,10 @@ use serde::{Deserialize, Serialize};
 ///     source: "node1".to_string(),
 ///     target: "node2".to_string(),
 ///     source_handle: "source".to_string(), // or "true"/"false" for if_else
+///     ..Default::default()
 /// };
 /// ```
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EdgeModel {
     /// Unique identifier for this edge.
     pub id: String,
@@ -30,4 +31,8 @@ pub struct EdgeModel {
     /// Handle name on the source node (e.g., "source", "true", "false").
     /// Used for conditional branching in if_else nodes.
     pub source_handle: String,
+    /// Template selecting an array output of the source node, e.g. `{{#n1.items#}}`.
+    /// When set, the target node runs once per element.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fan_out_selector: Option<String>,
 }
@@ -0,0 +1,356 @@
+//! Reusable groups of nodes inlined into a workflow.
+//!
+//! A workflow declares fragments under `fragments` and instantiates one with a
+//! node `{"uses": "fragment", "action": {"fragment": "name", "params": {...}}}`.
+//! Expanding the workflow replaces each instance with a copy of the fragment's
+//! nodes and edges, their ids prefixed with `<instance id>/`, and its
+//! `{{%param%}}` placeholders replaced by the instance's parameters.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ActflowError, Result,
+    model::{EdgeModel, NodeModel, WorkflowModel},
+};
+
+/// Parameter placeholder: `{{%name%}}`
+const PARAM_PATTERN: &str = r"\{\{%([^%]+)%\}\}";
+/// Start of a node output reference: `{{#nodeId.`
+const NODE_REFERENCE_PATTERN: &str = r"\{\{#([^.#]+)\.";
+
+/// A named group of nodes and edges that workflows can instantiate several times.
+///
+/// Nodes inside the fragment reference each other by their local ids; once
+/// inlined, those references point to the instance's copies. Edges into an
+/// instance lead to the fragment's entry nodes (without incoming edges) and
+/// edges out of it leave from its exit nodes (without outgoing edges).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FragmentModel {
+    /// Parameter defaults, overridden by an instance's `params`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, Value>,
+    /// Nodes of the fragment; string fields may contain `{{%param%}}` placeholders.
+    pub nodes: Vec<NodeModel>,
+    /// Edges between the fragment's nodes.
+    #[serde(default)]
+    pub edges: Vec<EdgeModel>,
+}
+
+/// Action of a `fragment` node.
+#[derive(Deserialize)]
+struct FragmentInstance {
+    fragment: String,
+    #[serde(default)]
+    params: HashMap<String, Value>,
+}
+
+/// Nodes an instance was expanded into, by prefixed id.
+struct Expansion {
+    entries: Vec<String>,
+    exits: Vec<String>,
+}
+
+impl WorkflowModel {
+    /// Returns a copy of the workflow with every `fragment` node replaced by
+    /// the nodes and edges of the fragment it names.
+    ///
+    /// Returns [`ActflowError::Workflow`] for an unknown fragment, a fragment
+    /// containing another fragment node, or a placeholder without a value.
+    pub fn expand_fragments(&self) -> Result<WorkflowModel> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut expansions = HashMap::new();
+
+        for node in &self.nodes {
+            if !node.is_fragment() {
+                nodes.push(node.clone());
+                continue;
+            }
+            let instance: FragmentInstance =
+                serde_json::from_value(node.action.clone()).map_err(|e| ActflowError::Workflow(format!("fragment node '{}': {}", node.id, e)))?;
+            let fragment = self
+                .fragments
+                .get(&instance.fragment)
+                .ok_or_else(|| ActflowError::Workflow(format!("fragment node '{}': unknown fragment '{}'", node.id, instance.fragment)))?;
+
+            let mut params = fragment.params.clone();
+            params.extend(instance.params);
+            let expansion = fragment
+                .inline(&node.id, &params, &mut nodes, &mut edges)
+                .map_err(|e| ActflowError::Workflow(format!("fragment node '{}': {}", node.id, e)))?;
+            expansions.insert(node.id.clone(), expansion);
+        }
+
+        // Reconnect edges attached to instances to the entry and exit nodes of their expansion
+        for edge in &self.edges {
+            let sources = match expansions.get(&edge.source) {
+                Some(expansion) => expansion.exits.clone(),
+                None => vec![edge.source.clone()],
+            };
+            let targets = match expansions.get(&edge.target) {
+                Some(expansion) => expansion.entries.clone(),
+                None => vec![edge.target.clone()],
+            };
+            let fan_out = sources.len() * targets.len() > 1;
+            for source in &sources {
+                for target in &targets {
+                    let id = if fan_out {
+                        format!("{}/{}/{}", edge.id, source, target)
+                    } else {
+                        edge.id.clone()
+                    };
+                    edges.push(EdgeModel {
+                        id,
+                        source: source.clone(),
+                        target: target.clone(),
+                        ..edge.clone()
+                    });
+                }
+            }
+        }
+
+        Ok(WorkflowModel {
+            nodes,
+            edges,
+            fragments: HashMap::new(),
+            ..self.clone()
+        })
+    }
+}
+
+impl FragmentModel {
+    /// Appends a copy of the fragment for the instance `prefix` to `nodes` and `edges`.
+    fn inline(
+        &self,
+        prefix: &str,
+        params: &HashMap<String, Value>,
+        nodes: &mut Vec<NodeModel>,
+        edges: &mut Vec<EdgeModel>,
+    ) -> std::result::Result<Expansion, String> {
+        if self.nodes.is_empty() {
+            return Err("fragment has no nodes".to_string());
+        }
+        let local: HashSet<&str> = self.nodes.iter().map(|node| node.id.as_str()).collect();
+        let prefixed = |id: &str| format!("{}/{}", prefix, id);
+        let substitution = Substitution {
+            prefix,
+            params,
+            local: &local,
+            param_re: Regex::new(PARAM_PATTERN).unwrap(),
+            reference_re: Regex::new(NODE_REFERENCE_PATTERN).unwrap(),
+        };
+
+        for node in &self.nodes {
+            if node.is_fragment() {
+                return Err(format!(
+                    "node '{}' instantiates a fragment, fragments cannot be nested",
+                    node.id
+                ));
+            }
+            let mut value = serde_json::to_value(node).map_err(|e| e.to_string())?;
+            substitution.apply(&mut value)?;
+            let mut node: NodeModel = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            node.id = prefixed(&node.id);
+            nodes.push(node);
+        }
+        for edge in &self.edges {
+            let mut value = serde_json::to_value(edge).map_err(|e| e.to_string())?;
+            substitution.apply(&mut value)?;
+            let mut edge: EdgeModel = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            edge.id = prefixed(&edge.id);
+            edge.source = prefixed(&edge.source);
+            edge.target = prefixed(&edge.target);
+            edges.push(edge);
+        }
+
+        let has_incoming: HashSet<&str> = self.edges.iter().map(|edge| edge.target.as_str()).collect();
+        let has_outgoing: HashSet<&str> = self.edges.iter().map(|edge| edge.source.as_str()).collect();
+        Ok(Expansion {
+            entries: self.nodes.iter().filter(|node| !has_incoming.contains(node.id.as_str())).map(|node| prefixed(&node.id)).collect(),
+            exits: self.nodes.iter().filter(|node| !has_outgoing.contains(node.id.as_str())).map(|node| prefixed(&node.id)).collect(),
+        })
+    }
+}
+
+/// Rewrites the strings of a fragment node or edge for one instance.
+struct Substitution<'a> {
+    prefix: &'a str,
+    params: &'a HashMap<String, Value>,
+    local: &'a HashSet<&'a str>,
+    param_re: Regex,
+    reference_re: Regex,
+}
+
+impl Substitution<'_> {
+    /// Replaces placeholders and points references to fragment nodes at the instance's copies.
+    ///
+    /// A string that is exactly one placeholder takes the parameter's JSON value,
+    /// so numbers and objects keep their type.
+    fn apply(
+        &self,
+        value: &mut Value,
+    ) -> std::result::Result<(), String> {
+        match value {
+            Value::String(s) => {
+                if let Some(caps) = self.param_re.captures(s)
+                    && caps[0].len() == s.len()
+                {
+                    *value = self.param(&caps[1])?.clone();
+                    return Ok(());
+                }
+
+                let mut missing = None;
+                let replaced = self.param_re.replace_all(s, |caps: &Captures| match self.param(&caps[1]) {
+                    Ok(Value::String(text)) => text.clone(),
+                    Ok(other) => other.to_string(),
+                    Err(e) => {
+                        missing.get_or_insert(e);
+                        String::new()
+                    }
+                });
+                if let Some(e) = missing {
+                    return Err(e);
+                }
+                let replaced = self.reference_re.replace_all(&replaced, |caps: &Captures| {
+                    if self.local.contains(&caps[1]) {
+                        format!("{{{{#{}/{}.", self.prefix, &caps[1])
+                    } else {
+                        caps[0].to_string()
+                    }
+                });
+                *s = replaced.into_owned();
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.apply(item)?;
+                }
+            }
+            Value::Object(map) => {
+                for item in map.values_mut() {
+                    self.apply(item)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn param(
+        &self,
+        name: &str,
+    ) -> std::result::Result<&Value, String> {
+        self.params.get(name).ok_or_else(|| format!("parameter '{}' is not set", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn create_workflow() -> WorkflowModel {
+        let node = |id: &str, uses: &str, action: Value| NodeModel {
+            id: id.to_string(),
+            uses: uses.to_string(),
+            action,
+            ..Default::default()
+        };
+        let edge = |id: &str, source: &str, target: &str| EdgeModel {
+            id: id.to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+            source_handle: "source".to_string(),
+            ..Default::default()
+        };
+        WorkflowModel {
+            id: "wf".to_string(),
+            nodes: vec![
+                node("start", "start", json!({})),
+                node(
+                    "users",
+                    "fragment",
+                    json!({ "fragment": "fetch", "params": { "path": "users", "timeout": 500 } }),
+                ),
+                node(
+                    "orders",
+                    "fragment",
+                    json!({ "fragment": "fetch", "params": { "path": "orders" } }),
+                ),
+                node("end", "end", json!({ "outputs": { "users": "{{#users/parse.items#}}" } })),
+            ],
+            edges: vec![edge("e1", "start", "users"), edge("e2", "users", "orders"), edge("e3", "orders", "end")],
+            fragments: HashMap::from([(
+                "fetch".to_string(),
+                FragmentModel {
+                    params: HashMap::from([("timeout".to_string(), json!(1000))]),
+                    nodes: vec![
+                        node(
+                            "request",
+                            "http_request",
+                            json!({ "url": "https://api.example.com/{{%path%}}?start={{#start.cursor#}}", "timeout": "{{%timeout%}}" }),
+                        ),
+                        node("parse", "end", json!({ "outputs": { "items": "{{#request.body.items#}}" } })),
+                    ],
+                    edges: vec![edge("e1", "request", "parse")],
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fragment_used_twice_with_different_params() {
+        let expanded = create_workflow().expand_fragments().unwrap();
+
+        let ids: Vec<&str> = expanded.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["start", "users/request", "users/parse", "orders/request", "orders/parse", "end"]
+        );
+        let action = |id: &str| expanded.nodes.iter().find(|node| node.id == id).unwrap().action.clone();
+        assert_eq!(
+            action("users/request"),
+            json!({ "url": "https://api.example.com/users?start={{#start.cursor#}}", "timeout": 500 })
+        );
+        assert_eq!(
+            action("orders/request"),
+            json!({ "url": "https://api.example.com/orders?start={{#start.cursor#}}", "timeout": 1000 })
+        );
+        assert_eq!(
+            action("orders/parse"),
+            json!({ "outputs": { "items": "{{#orders/request.body.items#}}" } })
+        );
+        assert_eq!(action("end"), json!({ "outputs": { "users": "{{#users/parse.items#}}" } }));
+
+        let edges: Vec<(&str, &str, &str)> = expanded.edges.iter().map(|edge| (edge.id.as_str(), edge.source.as_str(), edge.target.as_str())).collect();
+        assert_eq!(
+            edges,
+            [
+                ("users/e1", "users/request", "users/parse"),
+                ("orders/e1", "orders/request", "orders/parse"),
+                ("e1", "start", "users/request"),
+                ("e2", "users/parse", "orders/request"),
+                ("e3", "orders/parse", "end"),
+            ]
+        );
+        assert!(expanded.fragments.is_empty());
+    }
+
+    #[test]
+    fn test_fragment_errors() {
+        let mut workflow = create_workflow();
+        workflow.nodes[1].action = json!({ "fragment": "missing" });
+        let err = workflow.expand_fragments().unwrap_err().to_string();
+        assert!(err.contains("fragment node 'users': unknown fragment 'missing'"), "{}", err);
+
+        let mut workflow = create_workflow();
+        workflow.nodes[2].action = json!({ "fragment": "fetch" });
+        let err = workflow.expand_fragments().unwrap_err().to_string();
+        assert!(err.contains("fragment node 'orders': parameter 'path' is not set"), "{}", err);
+    }
+}
@@ -1,7 +1,11 @@
+mod diff;
 mod edge;
+mod fragment;
 mod node;
 mod workflow;
 
+pub use diff::WorkflowDiff;
 pub use edge::EdgeModel;
+pub use fragment::FragmentModel;
 pub use node::NodeModel;
-pub use workflow::WorkflowModel;
+pub use workflow::{EnvValue, FailurePolicy, HttpDefaults, JsonContentTypes, WorkflowFormat, WorkflowModel};
@@ -25,7 +25,7 @@ use serde::{Deserialize, Serialize};
 ///     ..Default::default()
 /// };
 /// ```
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct NodeModel {
     /// Unique identifier for this node within the workflow.
     pub id: String,
@@ -35,15 +35,63 @@ pub struct NodeModel {
     pub desc: String,
     /// Action type to use (e.g., "start", "http_request", "code", "if_else", "agent", "end").
     pub uses: String,
-    /// Error handling strategy: "fail_branch" or "continue".
+    /// Error handling strategy: "fail_branch" (continue along `fail_branch` edges,
+    /// with the error as `{{#nodeId.error#}}`) or "default_value" (succeed with `default_value`).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_strategy: Option<String>,
-    /// Retry configuration for failed executions.
+    /// Treat any failure as a skip: the node ends without outputs and the
+    /// workflow continues past it. Only used when no `error_strategy` is set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub continue_on_error: bool,
+    /// Retry configuration for failed executions, e.g. `{ "times": 3, "interval": 100 }`.
+    /// `times` and `interval` may be templates resolved before the node runs,
+    /// with `default_times` and `default_interval` used when they do not resolve.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retry: Option<serde_json::Value>,
     /// Execution timeout in milliseconds.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
+    /// Named inputs resolved from templates before the action runs,
+    /// e.g. `[{"variable": "user_id", "value_selector": "{{#n1.body.id#}}"}]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<serde_json::Value>,
+    /// Skip this node without executing it; successors still run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    /// Template evaluated before the node runs, e.g. `{{~FLAGS.beta_off~}}`. The node is
+    /// skipped like a disabled one unless it resolves to `""`, `"false"`, `"0"` or `"null"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_if: Option<String>,
+    /// When `skip_if` skips the node, also skip the nodes reached only through it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_subtree: bool,
+    /// JSON template reshaping the action's outputs before they are stored,
+    /// e.g. `{"id": "{{#fetch.body.data.id#}}"}`. It reads the node's raw outputs
+    /// under its own ID and must produce an object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_transform: Option<serde_json::Value>,
+    /// Name of a lock held while the node runs. Nodes sharing a key never run
+    /// concurrently, across all processes of the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency_key: Option<String>,
     /// Action-specific configuration parameters.
     pub action: serde_json::Value,
 }
+
+impl NodeModel {
+    /// Returns `true` for annotation-only nodes (`"uses": "annotation"`).
+    ///
+    /// Annotation nodes, and edges to or from them, are left out of the
+    /// executed graph: they never run and don't count toward completion.
+    pub fn is_annotation(&self) -> bool {
+        self.uses == "annotation"
+    }
+
+    /// Returns `true` for nodes instantiating a fragment (`"uses": "fragment"`).
+    ///
+    /// They are replaced by the fragment's nodes when the workflow is built,
+    /// see [`WorkflowModel::expand_fragments`](crate::WorkflowModel::expand_fragments).
+    pub fn is_fragment(&self) -> bool {
+        self.uses == "fragment"
+    }
+}
@@ -1,12 +1,18 @@
 //! Workflow model for defining complete workflow structures.
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
 
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     ActflowError, Result,
-    model::{EdgeModel, NodeModel},
+    model::{EdgeModel, FragmentModel, NodeModel},
 };
 
 /// Represents a complete workflow definition.
@@ -25,7 +31,7 @@ use crate::{
 ///     id: "my_workflow".to_string(),
 ///     name: "My Workflow".to_string(),
 ///     desc: "A sample workflow".to_string(),
-///     env: HashMap::from([("API_KEY".to_string(), "secret".to_string())]),
+///     env: HashMap::from([("API_KEY".to_string(), "secret".into())]),
 ///     nodes: vec![
 ///         NodeModel {
 ///             id: "start".to_string(),
@@ -35,6 +41,7 @@ use crate::{
 ///         },
 ///     ],
 ///     edges: vec![],
+///     ..Default::default()
 /// };
 /// ```
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -45,12 +52,178 @@ pub struct WorkflowModel {
     pub name: String,
     /// Description of the workflow's purpose.
     pub desc: String,
-    /// Environment variables accessible to all nodes via `{{#env.KEY#}}`.
-    pub env: HashMap<String, String>,
+    /// Environment variables accessible to all nodes via `{{$KEY$}}`.
+    pub env: HashMap<String, EnvValue>,
     /// List of nodes that make up the workflow.
     pub nodes: Vec<NodeModel>,
     /// List of edges defining the execution flow between nodes.
     pub edges: Vec<EdgeModel>,
+    /// Defaults applied to every `http_request` node in the workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpDefaults>,
+    /// Typed constants accessible to all nodes via `{{~NAME~}}` or `{{~NAME.key~}}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vars: HashMap<String, serde_json::Value>,
+    /// How the workflow reacts when a node fails while sibling branches are still running.
+    #[serde(default, skip_serializing_if = "FailurePolicy::is_fail_fast")]
+    pub failure_policy: FailurePolicy,
+    /// Reusable node groups, instantiated by `"uses": "fragment"` nodes.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub fragments: HashMap<String, FragmentModel>,
+}
+
+/// Value of a workflow environment variable.
+///
+/// Plain strings are used as-is. Binary values such as certificates or keys
+/// are written as `{"base64": "..."}`; templates see the base64 text and can
+/// decode it with `{{ base64decode({{$KEY$}}) }}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnvValue {
+    /// Plain text value.
+    Text(String),
+    /// Base64-encoded bytes.
+    Base64 {
+        /// Standard base64 encoding of the value.
+        base64: String,
+    },
+}
+
+impl EnvValue {
+    /// Returns the value as seen by templates.
+    pub fn as_str(&self) -> &str {
+        match self {
+            EnvValue::Text(text) => text,
+            EnvValue::Base64 {
+                base64,
+            } => base64,
+        }
+    }
+
+    /// Returns the raw bytes of the value, decoding base64 values.
+    pub fn decode(&self) -> Result<Vec<u8>> {
+        match self {
+            EnvValue::Text(text) => Ok(text.as_bytes().to_vec()),
+            EnvValue::Base64 {
+                base64,
+            } => STANDARD.decode(base64).map_err(|e| ActflowError::Workflow(format!("invalid base64: {}", e))),
+        }
+    }
+}
+
+impl From<&str> for EnvValue {
+    fn from(value: &str) -> Self {
+        EnvValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for EnvValue {
+    fn from(value: String) -> Self {
+        EnvValue::Text(value)
+    }
+}
+
+/// Workflow-level policy for node failures in parallel branches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Fail the workflow on the first node error, stopping sibling branches.
+    #[default]
+    FailFast,
+    /// Let nodes already running finish, then fail. No new nodes are started
+    /// after the first error, but the outputs of finished siblings are kept.
+    WaitAll,
+}
+
+impl FailurePolicy {
+    /// Returns `true` for the default `fail_fast` policy.
+    pub fn is_fail_fast(&self) -> bool {
+        *self == FailurePolicy::FailFast
+    }
+}
+
+/// Workflow-level defaults for `http_request` nodes.
+///
+/// Node-level settings take precedence: a node with an absolute URL ignores
+/// `base_url`, and a header set on the node overrides the default one.
+/// Values support template variables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpDefaults {
+    /// Prefix for relative request URLs (e.g., "https://api.example.com/v1").
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Headers sent with every request unless the node sets the same header.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Regular expressions for response content types whose bodies are parsed as JSON,
+    /// e.g. `"^text/json$"`, in addition to `application/json` and `*+json`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub json_content_types: Vec<String>,
+}
+
+impl HttpDefaults {
+    /// Compiles the `json_content_types` patterns.
+    ///
+    /// Returns [`ActflowError::Workflow`] if a pattern is not a valid regex.
+    pub fn compile_json_content_types(&self) -> Result<JsonContentTypes> {
+        let patterns = self
+            .json_content_types
+            .iter()
+            .map(|pattern| Regex::new(pattern).map_err(|e| ActflowError::Workflow(format!("invalid json_content_types pattern '{}': {}", pattern, e))))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(JsonContentTypes {
+            patterns,
+        })
+    }
+}
+
+/// Response content types whose bodies are parsed as JSON, compiled from
+/// [`HttpDefaults::json_content_types`].
+#[derive(Debug, Clone, Default)]
+pub struct JsonContentTypes {
+    patterns: Vec<Regex>,
+}
+
+impl JsonContentTypes {
+    /// Returns `true` if a response with the `mime` content type (without
+    /// parameters) holds JSON.
+    pub fn matches(
+        &self,
+        mime: &str,
+    ) -> bool {
+        mime.eq_ignore_ascii_case("application/json") || mime.ends_with("+json") || self.patterns.iter().any(|regex| regex.is_match(mime))
+    }
+}
+
+/// Serialization format of a stored workflow, see [`WorkflowModel::encode`].
+///
+/// Variants depend on the enabled features, so matches need a wildcard arm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WorkflowFormat {
+    /// Plain JSON, without a format marker.
+    #[default]
+    Json,
+    /// Gzip-compressed JSON.
+    Gzip,
+    /// MessagePack, see [`WorkflowModel::to_bytes`].
+    ///
+    /// Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+}
+
+impl WorkflowFormat {
+    /// Marker byte put before the payload. JSON needs none, as a JSON object
+    /// never starts with one of these bytes.
+    fn marker(self) -> Option<u8> {
+        match self {
+            WorkflowFormat::Json => None,
+            WorkflowFormat::Gzip => Some(0x01),
+            #[cfg(feature = "msgpack")]
+            WorkflowFormat::MsgPack => Some(0x02),
+        }
+    }
 }
 
 impl WorkflowModel {
@@ -70,4 +243,142 @@ impl WorkflowModel {
             Err(e) => Err(ActflowError::Workflow(format!("{}", e))),
         }
     }
+
+    /// Parses a workflow from MessagePack bytes produced by [`WorkflowModel::to_bytes`].
+    ///
+    /// Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes).map_err(|e| ActflowError::Workflow(format!("{}", e)))
+    }
+
+    /// Serializes the workflow to MessagePack bytes.
+    ///
+    /// Structs are encoded as maps keyed by field name, so optional fields
+    /// omitted from the JSON form round-trip the same way.
+    /// Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec_named(self).map_err(|e| ActflowError::Workflow(format!("{}", e)))
+    }
+
+    /// Serializes the workflow for storage in the given format.
+    ///
+    /// Formats other than JSON start with a marker byte, so [`WorkflowModel::decode`]
+    /// reads any of them, and workflows stored as plain JSON stay readable.
+    pub fn encode(
+        &self,
+        format: WorkflowFormat,
+    ) -> Result<Vec<u8>> {
+        let mut bytes: Vec<u8> = format.marker().into_iter().collect();
+        match format {
+            WorkflowFormat::Json => bytes.extend(serde_json::to_vec(self)?),
+            WorkflowFormat::Gzip => {
+                let mut encoder = GzEncoder::new(bytes, Compression::default());
+                encoder.write_all(&serde_json::to_vec(self)?)?;
+                bytes = encoder.finish()?;
+            }
+            #[cfg(feature = "msgpack")]
+            WorkflowFormat::MsgPack => bytes.extend(self.to_bytes()?),
+        }
+        Ok(bytes)
+    }
+
+    /// Parses a workflow produced by [`WorkflowModel::encode`] in any format.
+    ///
+    /// Returns [`ActflowError::Workflow`] if the data is malformed or its format
+    /// is not supported by the enabled features.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&marker) if Some(marker) == WorkflowFormat::Gzip.marker() => {
+                let mut json = Vec::new();
+                GzDecoder::new(&bytes[1..]).read_to_end(&mut json).map_err(|e| ActflowError::Workflow(format!("invalid gzip workflow: {}", e)))?;
+                serde_json::from_slice(&json).map_err(|e| ActflowError::Workflow(format!("{}", e)))
+            }
+            #[cfg(feature = "msgpack")]
+            Some(&marker) if Some(marker) == WorkflowFormat::MsgPack.marker() => Self::from_bytes(&bytes[1..]),
+            Some(&marker) if marker < 0x20 && !marker.is_ascii_whitespace() => Err(ActflowError::Workflow(format!(
+                "unsupported workflow format marker 0x{:02x}",
+                marker
+            ))),
+            _ => serde_json::from_slice(bytes).map_err(|e| ActflowError::Workflow(format!("{}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = r#"{
+        "id": "wf",
+        "name": "Workflow",
+        "desc": "round trip",
+        "env": {"API_KEY": "secret"},
+        "vars": {"limits": {"retries": 3, "ratio": 0.5}, "tags": ["a", "b"], "none": null},
+        "http": {"base_url": "https://api.example.com", "headers": {"x-token": "t"}},
+        "nodes": [
+            {"id": "start", "title": "Start", "desc": "", "uses": "start", "action": {}},
+            {"id": "code", "title": "Code", "desc": "", "uses": "code", "retry": {"times": 2}, "disabled": true, "action": {"language": "javascript", "code": "function main() { return {}; }"}}
+        ],
+        "edges": [{"id": "e1", "source": "start", "target": "code", "source_handle": "source"}]
+    }"#;
+
+    #[test]
+    fn test_encode_round_trip() {
+        let mut workflow = WorkflowModel::from_json(WORKFLOW).unwrap();
+        // Repetitive graphs are where compression pays off
+        for i in 0..50 {
+            let mut node = workflow.nodes[1].clone();
+            node.id = format!("code{}", i);
+            workflow.nodes.push(node);
+        }
+
+        let json = workflow.encode(WorkflowFormat::Json).unwrap();
+        assert_eq!(json, serde_json::to_vec(&workflow).unwrap());
+        let gzip = workflow.encode(WorkflowFormat::Gzip).unwrap();
+        assert_eq!(gzip[0], 0x01);
+        assert!(gzip.len() < json.len() / 4);
+
+        for bytes in [&json, &gzip] {
+            let decoded = WorkflowModel::decode(bytes).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&workflow).unwrap()
+            );
+        }
+
+        assert!(matches!(WorkflowModel::decode(&[0x01, 0x1f]), Err(ActflowError::Workflow(_))));
+        assert_eq!(
+            WorkflowModel::decode(&[0x07]).err().unwrap().to_string(),
+            "unsupported workflow format marker 0x07"
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_bytes_round_trip() {
+        let workflow = WorkflowModel::from_json(WORKFLOW).unwrap();
+        let bytes = workflow.to_bytes().unwrap();
+        let decoded = WorkflowModel::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&workflow).unwrap()
+        );
+        assert!(bytes.len() < serde_json::to_vec(&workflow).unwrap().len());
+
+        let encoded = workflow.encode(WorkflowFormat::MsgPack).unwrap();
+        assert_eq!(encoded[1..], bytes[..]);
+        assert_eq!(
+            serde_json::to_value(WorkflowModel::decode(&encoded).unwrap()).unwrap(),
+            serde_json::to_value(&workflow).unwrap()
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_from_bytes_invalid() {
+        assert!(matches!(WorkflowModel::from_bytes(&[0xc1]), Err(ActflowError::Workflow(_))));
+    }
 }
@@ -5,13 +5,23 @@
 
 use std::sync::{Arc, RwLock};
 
-use futures::future::BoxFuture;
-use tokio::runtime::Runtime;
+use futures::{
+    StreamExt,
+    future::BoxFuture,
+    stream::{self, BoxStream},
+};
+use tokio::{
+    runtime::Runtime,
+    sync::{
+        broadcast::{self, error::RecvError},
+        watch,
+    },
+};
 
 use crate::{
     ShareLock,
     common::{BroadcastQueue, Shutdown},
-    events::{Event, Log, Message},
+    events::{Event, GraphEvent, Log, Message, WorkflowEvent},
     runtime::ProcessId,
 };
 
@@ -44,8 +54,8 @@ const EVENT_QUEUE_SIZE: usize = 2048;
 /// Maximum number of logs in the log queue.
 const LOG_QUEUE_SIZE: usize = 4096;
 
-/// Synchronous event handler type.
-pub type WorkflowEventHandle = Arc<dyn Fn(&Event<Message>) + Send + Sync>;
+/// Synchronous event handler type, returning `true` once it is finished and can be removed.
+pub type WorkflowEventHandle = Arc<dyn Fn(&Event<Message>) -> bool + Send + Sync>;
 /// Synchronous log handler type.
 pub type WorkflowLogHandle = Arc<dyn Fn(&Event<Log>) + Send + Sync>;
 /// Asynchronous event handler type.
@@ -135,6 +145,9 @@ pub struct Channel {
     events_async: ShareLock<Vec<WorkflowEventHandleAsync>>,
     /// Registered asynchronous log handlers.
     logs_async: ShareLock<Vec<WorkflowLogHandleAsync>>,
+    /// Number of events the listener has handed to the synchronous handlers,
+    /// `None` until it starts listening.
+    dispatched: Arc<watch::Sender<Option<u64>>>,
     /// Tokio runtime for spawning async tasks.
     runtime: Arc<Runtime>,
     /// Shutdown coordinator.
@@ -151,6 +164,7 @@ impl Channel {
             logs: Arc::new(RwLock::new(Vec::new())),
             events_async: Arc::new(RwLock::new(Vec::new())),
             logs_async: Arc::new(RwLock::new(Vec::new())),
+            dispatched: Arc::new(watch::Sender::new(None)),
             runtime,
             shutdown: Arc::new(Shutdown::new()),
         }
@@ -173,6 +187,9 @@ impl Channel {
     pub(crate) fn listen(&self) {
         let mut event_queue = self.event_queue.subscribe();
         let mut log_queue = self.log_queue.subscribe();
+        // Events sent before subscribing are never dispatched; count them as handled
+        let dispatched = self.dispatched.clone();
+        dispatched.send_replace(Some(self.event_queue.sent()));
         let events = self.events.clone();
         let logs = self.logs.clone();
         let events_async = self.events_async.clone();
@@ -183,11 +200,19 @@ impl Channel {
             loop {
                 tokio::select! {
                     _ = shutdown.wait() => break,
-                    Ok(e) = event_queue.recv() => {
-                        let evt = e.clone();
-                        dispatch_event!(events, &evt);
-                        dispatch_event_async!(events_async, &e);
-                    }
+                    res = event_queue.recv() => match res {
+                        Ok(e) => {
+                            let evt = e.clone();
+                            let finished: Vec<WorkflowEventHandle> = events.read().unwrap().iter().filter(|handle| handle(&evt)).cloned().collect();
+                            if !finished.is_empty() {
+                                events.write().unwrap().retain(|handle| !finished.iter().any(|f| Arc::ptr_eq(f, handle)));
+                            }
+                            dispatch_event_async!(events_async, &e);
+                            dispatched.send_modify(|count| *count = count.map(|c| c + 1));
+                        }
+                        Err(RecvError::Lagged(skipped)) => dispatched.send_modify(|count| *count = count.map(|c| c + skipped)),
+                        Err(RecvError::Closed) => break,
+                    },
                     Ok(log) = log_queue.recv() => {
                         let l = log.clone();
                         dispatch_event!(logs, &l);
@@ -198,6 +223,31 @@ impl Channel {
         });
     }
 
+    /// Waits until every event sent so far has been handed to the synchronous
+    /// event handlers, such as the recorders behind `Process::recent_events`.
+    ///
+    /// Returns immediately if the channel is not listening, and early if it
+    /// shuts down. Asynchronous handlers may still be running.
+    pub(crate) async fn flush_events(&self) {
+        let target = self.event_queue.sent();
+        let mut dispatched = self.dispatched.subscribe();
+        tokio::select! {
+            _ = self.shutdown.wait() => {}
+            _ = dispatched.wait_for(|count| count.is_none_or(|count| count >= target)) => {}
+        }
+    }
+
+    /// Returns the number of registered synchronous event handlers.
+    #[cfg(test)]
+    pub(crate) fn event_handler_count(&self) -> usize {
+        self.events.read().unwrap().len()
+    }
+
+    /// Returns `true` once the channel has been shut down.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.shutdown.is_terminated()
+    }
+
     /// Signals the channel to stop listening.
     pub(crate) fn shutdown(&self) {
         self.shutdown.shutdown();
@@ -224,6 +274,28 @@ pub struct ChannelEvent {
 
 #[allow(unused)]
 impl ChannelEvent {
+    /// Returns a stream of the matching events sent from now on.
+    ///
+    /// Events dropped because the consumer fell too far behind are skipped. The
+    /// stream ends when the channel shuts down.
+    pub fn stream(&self) -> BoxStream<'static, Event<Message>> {
+        let glob = self.glob.clone();
+        subscribe_stream(self.channel.event_queue.subscribe(), self.channel.shutdown.clone(), move |e| {
+            is_match(&glob, e)
+        })
+    }
+
+    /// Returns a stream of the matching logs sent from now on.
+    ///
+    /// Logs dropped because the consumer fell too far behind are skipped. The
+    /// stream ends when the channel shuts down.
+    pub fn log_stream(&self) -> BoxStream<'static, Event<Log>> {
+        let glob = self.glob.clone();
+        subscribe_stream(self.channel.log_queue.subscribe(), self.channel.shutdown.clone(), move |e| {
+            is_match_log(&glob, e)
+        })
+    }
+
     /// Creates a new event subscriber with the given options.
     pub fn channel(
         channel: Arc<Channel>,
@@ -249,6 +321,24 @@ impl ChannelEvent {
             if e.event.is_complete() && is_match(&glob, e) {
                 f(e.pid.clone());
             }
+            false
+        }));
+    }
+
+    /// Registers a handler for aborted workflows, called with the abort reason.
+    pub fn on_abort(
+        &self,
+        f: impl Fn(ProcessId, String) + Send + Sync + 'static,
+    ) {
+        let glob = self.glob.clone();
+
+        self.channel.events.write().unwrap().push(Arc::new(move |e| {
+            if let GraphEvent::Workflow(WorkflowEvent::Aborted(aborted)) = &e.event
+                && is_match(&glob, e)
+            {
+                f(e.pid.clone(), aborted.reason.clone());
+            }
+            false
         }));
     }
 
@@ -263,6 +353,7 @@ impl ChannelEvent {
             if e.event.is_error() && is_match(&glob, e) {
                 f(e);
             }
+            false
         }));
     }
 
@@ -277,9 +368,30 @@ impl ChannelEvent {
             if is_match(&glob, e) {
                 f(e);
             }
+            false
         }));
     }
 
+    /// Registers a handler for all matching events that is removed once it returns `true`.
+    pub fn on_event_until(
+        &self,
+        f: impl Fn(&Event<Message>) -> bool + Send + Sync + 'static,
+    ) {
+        let glob = self.glob.clone();
+
+        self.channel.events.write().unwrap().push(Arc::new(move |e| is_match(&glob, e) && f(e)));
+    }
+
+    /// Like [`on_event_until`](Self::on_event_until), but runs before the handlers registered so far.
+    pub(crate) fn on_event_until_first(
+        &self,
+        f: impl Fn(&Event<Message>) -> bool + Send + Sync + 'static,
+    ) {
+        let glob = self.glob.clone();
+
+        self.channel.events.write().unwrap().insert(0, Arc::new(move |e| is_match(&glob, e) && f(e)));
+    }
+
     /// Registers a handler for log events.
     pub fn on_log(
         &self,
@@ -331,6 +443,27 @@ impl ChannelEvent {
     }
 }
 
+/// Turns a broadcast receiver into a stream of the items accepted by `filter`.
+fn subscribe_stream<T: Clone + Send + 'static>(
+    receiver: broadcast::Receiver<T>,
+    shutdown: Arc<Shutdown>,
+    filter: impl Fn(&T) -> bool + Send + 'static,
+) -> BoxStream<'static, T> {
+    stream::unfold((receiver, shutdown, filter), |(mut receiver, shutdown, filter)| async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.wait() => return None,
+                res = receiver.recv() => match res {
+                    Ok(item) if filter(&item) => return Some((item, (receiver, shutdown, filter))),
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
 /// Checks if an event matches the glob patterns.
 fn is_match(
     glob: &(globset::GlobMatcher, globset::GlobMatcher),
@@ -3,22 +3,51 @@
 //! The context provides runtime state and utilities for node execution,
 //! including environment variables, node outputs, and event emission.
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
-    common::{MemCache, Shutdown, Vars},
-    events::{Event, Log},
+    HttpDefaults, JsonContentTypes, Result,
+    common::{Clock, MemCache, Shutdown, SystemClock, Vars},
+    dispatcher::{ConcurrencyLocks, SerialGate},
+    events::{Event, GraphEvent, Log, Message, NodeEvent},
     runtime::{Channel, ProcessId},
-    utils,
-    workflow::node::NodeId,
+    workflow::{
+        consts::INCOMING_NAMESPACE,
+        functions::TemplateFunctions,
+        interceptor::ActionInterceptors,
+        node::NodeId,
+        template::{DEFAULT_TEMPLATE_DEPTH, NumberFormat},
+    },
 };
 
+/// Timestamps of a node execution, in milliseconds of the process clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeTiming {
+    /// When the node became ready to run.
+    pub ready_time: i64,
+    /// When the node started executing, after waiting for its turn.
+    pub start_time: i64,
+    /// When the node finished executing; `None` while it is running.
+    pub end_time: Option<i64>,
+    /// Time spent ready but waiting on a concurrency cap, `start_time - ready_time`.
+    pub queue_wait_ms: i64,
+}
+
 /// Execution context shared across all nodes in a workflow process.
 ///
 /// The context maintains:
 /// - Process ID for identification
 /// - Environment variables accessible via `{{#env.KEY#}}`
 /// - Node outputs accessible via `{{#nodeId.key#}}`
+/// - Node inputs resolved before each node runs
+/// - Process inputs accessible via `{{@input.key@}}`
+/// - Workflow variables accessible via `{{~NAME.key~}}`
 /// - Event channel for emitting logs and events
 /// - Shutdown signal for graceful termination
 ///
@@ -33,6 +62,42 @@ pub struct Context {
     env: Arc<MemCache<String, String>>,
     /// Node outputs cache, keyed by node ID.
     outputs: Arc<MemCache<NodeId, Vars>>,
+    /// Resolved node inputs cache, keyed by node ID.
+    inputs: Arc<MemCache<NodeId, Vars>>,
+    /// Execution timestamps, keyed by node ID.
+    timings: Arc<MemCache<NodeId, NodeTiming>>,
+    /// Inputs the process was triggered with.
+    process_inputs: Arc<Vars>,
+    /// ID tracing the run across systems, attached to every log and event.
+    correlation_id: Arc<String>,
+    /// Labels the process was built with.
+    labels: Arc<HashMap<String, String>>,
+    /// Workflow-level typed constants.
+    vars: Arc<Vars>,
+    /// Defaults for `http_request` nodes.
+    http_defaults: Arc<HttpDefaults>,
+    /// Response content types parsed as JSON, compiled from the HTTP defaults.
+    json_content_types: Arc<JsonContentTypes>,
+    /// Functions callable from templates.
+    functions: Arc<TemplateFunctions>,
+    /// Interceptors called around every action.
+    interceptors: Arc<ActionInterceptors>,
+    /// Maximum number of passes made when resolving a template.
+    template_depth: usize,
+    /// How numbers are rendered in template strings.
+    number_format: NumberFormat,
+    /// Time source for timestamps, timeouts and retry delays.
+    clock: Arc<dyn Clock>,
+    /// Cancellation signals of running nodes, keyed by node ID.
+    cancellations: Arc<MemCache<NodeId, Shutdown>>,
+    /// Locks for node concurrency keys, shared across processes.
+    concurrency_locks: Arc<ConcurrencyLocks>,
+    /// Predecessors of the running node, in edge order, read through `{{#__incoming__.key#}}`.
+    incoming: Arc<Vec<NodeId>>,
+    /// Readers of each node's outputs, set when outputs are evicted once consumed.
+    output_consumers: Option<Arc<HashMap<NodeId, HashSet<NodeId>>>>,
+    /// Gate running one node at a time, set for deterministic runs.
+    serial_gate: Option<Arc<SerialGate>>,
     /// Event channel for broadcasting events and logs.
     channel: Arc<Channel>,
     /// Shutdown coordinator for graceful termination.
@@ -51,14 +116,244 @@ impl Context {
         channel: Arc<Channel>,
     ) -> Self {
         Self {
+            correlation_id: Arc::new(pid.clone()),
             pid,
             env: Arc::new(MemCache::new(1024)),
             outputs: Arc::new(MemCache::new(1024)),
+            inputs: Arc::new(MemCache::new(1024)),
+            timings: Arc::new(MemCache::new(1024)),
+            process_inputs: Arc::new(Vars::new()),
+            labels: Arc::new(HashMap::new()),
+            vars: Arc::new(Vars::new()),
+            http_defaults: Arc::new(HttpDefaults::default()),
+            json_content_types: Arc::new(JsonContentTypes::default()),
+            functions: Arc::new(TemplateFunctions::new()),
+            interceptors: Arc::new(ActionInterceptors::new()),
+            template_depth: DEFAULT_TEMPLATE_DEPTH,
+            number_format: NumberFormat::default(),
+            clock: Arc::new(SystemClock),
+            cancellations: Arc::new(MemCache::new(1024)),
+            concurrency_locks: Arc::new(ConcurrencyLocks::new()),
+            incoming: Arc::new(Vec::new()),
+            output_consumers: None,
+            serial_gate: None,
             channel,
             shutdown: Arc::new(Shutdown::new()),
         }
     }
 
+    /// Sets the inputs the process was triggered with.
+    pub fn with_process_inputs(
+        mut self,
+        inputs: Vars,
+    ) -> Self {
+        self.process_inputs = Arc::new(inputs);
+        self
+    }
+
+    /// Returns the inputs the process was triggered with.
+    pub fn process_inputs(&self) -> &Vars {
+        &self.process_inputs
+    }
+
+    /// Sets the ID attached to every log and event, in place of the process ID.
+    pub fn with_correlation_id(
+        mut self,
+        correlation_id: String,
+    ) -> Self {
+        self.correlation_id = Arc::new(correlation_id);
+        self
+    }
+
+    /// Returns the ID attached to every log and event.
+    pub fn correlation_id(&self) -> String {
+        self.correlation_id.to_string()
+    }
+
+    /// Sets the labels the process was built with.
+    pub fn with_labels(
+        mut self,
+        labels: HashMap<String, String>,
+    ) -> Self {
+        self.labels = Arc::new(labels);
+        self
+    }
+
+    /// Returns the labels the process was built with.
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Sets the workflow-level typed constants.
+    pub fn with_vars(
+        mut self,
+        vars: Vars,
+    ) -> Self {
+        self.vars = Arc::new(vars);
+        self
+    }
+
+    /// Returns the workflow-level typed constants.
+    pub fn vars(&self) -> &Vars {
+        &self.vars
+    }
+
+    /// Sets the defaults applied to `http_request` nodes.
+    ///
+    /// Returns [`ActflowError::Workflow`] if a `json_content_types` pattern is not a valid regex.
+    pub fn with_http_defaults(
+        mut self,
+        defaults: HttpDefaults,
+    ) -> Result<Self> {
+        self.json_content_types = Arc::new(defaults.compile_json_content_types()?);
+        self.http_defaults = Arc::new(defaults);
+        Ok(self)
+    }
+
+    /// Returns the defaults applied to `http_request` nodes.
+    pub fn http_defaults(&self) -> &HttpDefaults {
+        &self.http_defaults
+    }
+
+    /// Returns the response content types parsed as JSON.
+    pub fn json_content_types(&self) -> &JsonContentTypes {
+        &self.json_content_types
+    }
+
+    /// Sets the registry of functions callable from templates.
+    pub fn with_functions(
+        mut self,
+        functions: Arc<TemplateFunctions>,
+    ) -> Self {
+        self.functions = functions;
+        self
+    }
+
+    /// Returns the registry of functions callable from templates.
+    pub fn functions(&self) -> Arc<TemplateFunctions> {
+        self.functions.clone()
+    }
+
+    /// Sets the maximum number of passes made when resolving a template,
+    /// re-resolving values that contain templates themselves.
+    pub fn with_template_depth(
+        mut self,
+        depth: usize,
+    ) -> Self {
+        self.template_depth = depth;
+        self
+    }
+
+    /// Returns the maximum number of passes made when resolving a template.
+    pub fn template_depth(&self) -> usize {
+        self.template_depth
+    }
+
+    /// Sets how numbers are rendered when substituted into template strings.
+    pub fn with_number_format(
+        mut self,
+        number_format: NumberFormat,
+    ) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Returns how numbers are rendered when substituted into template strings.
+    pub fn number_format(&self) -> NumberFormat {
+        self.number_format
+    }
+
+    /// Sets the interceptors called around every action.
+    pub fn with_interceptors(
+        mut self,
+        interceptors: Arc<ActionInterceptors>,
+    ) -> Self {
+        self.interceptors = interceptors;
+        self
+    }
+
+    /// Returns the interceptors called around every action.
+    pub fn interceptors(&self) -> Arc<ActionInterceptors> {
+        self.interceptors.clone()
+    }
+
+    /// Sets the locks taken for node concurrency keys.
+    pub fn with_concurrency_locks(
+        mut self,
+        locks: Arc<ConcurrencyLocks>,
+    ) -> Self {
+        self.concurrency_locks = locks;
+        self
+    }
+
+    /// Returns the locks taken for node concurrency keys.
+    pub fn concurrency_locks(&self) -> Arc<ConcurrencyLocks> {
+        self.concurrency_locks.clone()
+    }
+
+    /// Sets the time source.
+    pub fn with_clock(
+        mut self,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the time source.
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Runs nodes one at a time through `gate`.
+    pub fn with_serial_gate(
+        mut self,
+        gate: Arc<SerialGate>,
+    ) -> Self {
+        self.serial_gate = Some(gate);
+        self
+    }
+
+    /// Returns the gate serializing node execution, if any.
+    pub fn serial_gate(&self) -> Option<Arc<SerialGate>> {
+        self.serial_gate.clone()
+    }
+
+    /// Registers a fresh cancellation signal for a node about to run.
+    pub fn track_cancellation(
+        &self,
+        nid: &NodeId,
+    ) -> Shutdown {
+        let cancellation = Shutdown::new();
+        self.cancellations.set(nid.clone(), cancellation.clone());
+        cancellation
+    }
+
+    /// Signals the node's cancellation, returning `false` if none is registered.
+    pub fn cancel_node(
+        &self,
+        nid: &NodeId,
+    ) -> bool {
+        match self.cancellations.get(nid) {
+            Some(cancellation) => {
+                cancellation.shutdown();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns a copy of the context with its own, empty inputs cache.
+    ///
+    /// Env, outputs and the event channel stay shared; used to run several
+    /// executions of one node side by side with different inputs.
+    pub fn fork(&self) -> Self {
+        Self {
+            inputs: Arc::new(MemCache::new(1024)),
+            ..self.clone()
+        }
+    }
+
     /// Returns the environment variables cache.
     pub fn env(&self) -> Arc<MemCache<String, String>> {
         self.env.clone()
@@ -69,6 +364,35 @@ impl Context {
         self.outputs.clone()
     }
 
+    /// Returns the approximate memory held by node outputs: the size of their
+    /// JSON serialization, in bytes.
+    pub fn outputs_byte_size(&self) -> usize {
+        self.outputs.iter().map(|(_, vars)| serde_json::to_vec(&vars).map(|bytes| bytes.len()).unwrap_or(0)).sum()
+    }
+
+    /// Drops the outputs of a node.
+    pub fn remove_output(
+        &self,
+        nid: &NodeId,
+    ) {
+        self.outputs.remove(nid);
+    }
+
+    /// Evicts a node's outputs once every node in its `consumers` entry has
+    /// completed, as computed by `Workflow::output_consumers`.
+    pub fn with_output_consumers(
+        mut self,
+        consumers: HashMap<NodeId, HashSet<NodeId>>,
+    ) -> Self {
+        self.output_consumers = Some(Arc::new(consumers));
+        self
+    }
+
+    /// Returns the readers of each node's outputs, if consumed outputs are evicted.
+    pub fn output_consumers(&self) -> Option<Arc<HashMap<NodeId, HashSet<NodeId>>>> {
+        self.output_consumers.clone()
+    }
+
     /// Stores the output of a node execution.
     ///
     /// # Arguments
@@ -83,11 +407,103 @@ impl Context {
         self.outputs.set(nid, outputs);
     }
 
+    /// Sets the predecessors whose outputs the running node reads as `__incoming__`.
+    ///
+    /// Returns a copy sharing every cache with this context, for the node's execution.
+    pub fn with_incoming(
+        &self,
+        incoming: Vec<NodeId>,
+    ) -> Self {
+        Self {
+            incoming: Arc::new(incoming),
+            ..self.clone()
+        }
+    }
+
+    /// Looks up an RFC 6901 JSON pointer (e.g. `/body/items/0`) in the variables
+    /// addressable under `node_id`: its outputs once it has run, otherwise its
+    /// resolved inputs.
+    ///
+    /// Under `__incoming__` the pointer is looked up in the outputs of the
+    /// running node's predecessors, the last one in edge order that has it
+    /// winning, so a join node reads a merged view of its incoming branches.
+    pub fn resolve_path(
+        &self,
+        node_id: &str,
+        pointer: &str,
+    ) -> Option<Value> {
+        if node_id == INCOMING_NAMESPACE {
+            return self.incoming.iter().rev().find_map(|nid| {
+                let value: Value = self.outputs.get(nid)?.into();
+                value.pointer(pointer).cloned()
+            });
+        }
+
+        let node_id = node_id.to_string();
+        let vars = self.outputs.get(&node_id).or_else(|| self.inputs.get(&node_id))?;
+        let value: Value = vars.into();
+        value.pointer(pointer).cloned()
+    }
+
+    /// Returns the resolved node inputs cache.
+    pub fn inputs(&self) -> Arc<MemCache<NodeId, Vars>> {
+        self.inputs.clone()
+    }
+
+    /// Stores the resolved inputs of a node before it runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `nid` - Node identifier
+    /// * `inputs` - Input variables resolved from the node's input mapping
+    pub fn add_inputs(
+        &self,
+        nid: NodeId,
+        inputs: Vars,
+    ) {
+        self.inputs.set(nid, inputs);
+    }
+
+    /// Returns the node execution timings cache.
+    pub fn timings(&self) -> Arc<MemCache<NodeId, NodeTiming>> {
+        self.timings.clone()
+    }
+
+    /// Stores the execution timestamps of a node.
+    pub fn add_timing(
+        &self,
+        nid: NodeId,
+        timing: NodeTiming,
+    ) {
+        self.timings.set(nid, timing);
+    }
+
     /// Returns the event channel.
     pub fn channel(&self) -> Arc<Channel> {
         self.channel.clone()
     }
 
+    /// Returns a snapshot of the process state for debugging.
+    ///
+    /// The snapshot holds the process ID, env, process inputs, workflow vars,
+    /// and the recorded outputs and resolved inputs of every node.
+    /// Requires the `debug` feature.
+    #[cfg(feature = "debug")]
+    pub fn dump(&self) -> Value {
+        let nodes =
+            |cache: &MemCache<NodeId, Vars>| -> serde_json::Map<String, Value> { cache.iter().map(|(nid, vars)| (nid.to_string(), vars.into())).collect() };
+        let env: serde_json::Map<String, Value> = self.env.iter().map(|(k, v)| (k.to_string(), v.into())).collect();
+
+        serde_json::json!({
+            "pid": self.pid,
+            "env": env,
+            "process_inputs": Value::from(self.process_inputs.as_ref().clone()),
+            "vars": Value::from(self.vars.as_ref().clone()),
+            "outputs": nodes(&self.outputs),
+            "inputs": nodes(&self.inputs),
+        })
+    }
+
     /// Emits a log message from a node.
     ///
     /// # Arguments
@@ -101,13 +517,69 @@ impl Context {
     ) {
         let log = Log {
             pid: self.pid.clone(),
+            correlation_id: self.correlation_id(),
             nid,
             content,
-            timestamp: utils::time::time_millis(),
+            timestamp: self.clock.now_millis(),
+            seq: None,
+            phase: None,
         };
         let _ = self.channel.log_queue().send(Event::new(&log));
     }
 
+    /// Emits a log message carrying its position in the node's log stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `nid` - Node identifier that generated the log
+    /// * `content` - Log message content
+    /// * `seq` - Position of the log within the node's log stream
+    /// * `phase` - Phase of the node's work the log belongs to
+    pub fn emit_sequenced_log(
+        &self,
+        nid: NodeId,
+        content: String,
+        seq: u64,
+        phase: Option<String>,
+    ) {
+        let log = Log {
+            pid: self.pid.clone(),
+            correlation_id: self.correlation_id(),
+            nid,
+            content,
+            timestamp: self.clock.now_millis(),
+            seq: Some(seq),
+            phase,
+        };
+        let _ = self.channel.log_queue().send(Event::new(&log));
+    }
+
+    /// Emits a custom domain event from a node.
+    ///
+    /// Subscribers receive it as [`NodeEvent::Custom`] and can filter on `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `nid` - Node identifier that generated the event
+    /// * `name` - Event name
+    /// * `payload` - Event payload
+    pub fn emit_custom(
+        &self,
+        nid: NodeId,
+        name: String,
+        payload: Vars,
+    ) {
+        let _ = self.channel.event_queue().send(Event::new(&Message {
+            pid: self.pid.clone(),
+            correlation_id: self.correlation_id(),
+            nid,
+            event: GraphEvent::Node(NodeEvent::Custom {
+                name,
+                payload,
+            }),
+        }));
+    }
+
     /// Returns the process identifier.
     pub fn pid(&self) -> ProcessId {
         self.pid.to_owned()
@@ -139,3 +611,71 @@ impl Context {
         self.shutdown.wait()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::runtime::{ChannelEvent, ChannelOptions};
+
+    #[test]
+    fn test_emit_custom_reaches_subscriber() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let channel = Arc::new(Channel::new(runtime));
+        channel.listen();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(channel.clone(), ChannelOptions::default()).on_event(move |e| {
+            if let GraphEvent::Node(NodeEvent::Custom {
+                name,
+                payload,
+            }) = &e.event
+            {
+                let _ = tx.send((e.nid.clone(), name.clone(), payload.clone()));
+            }
+        });
+
+        let ctx = Context::new("pid".to_string(), channel);
+        ctx.emit_custom(
+            "n1".to_string(),
+            "payment_authorized".to_string(),
+            Vars::new().with("amount", 10),
+        );
+
+        let (nid, name, payload) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(nid, "n1");
+        assert_eq!(name, "payment_authorized");
+        assert_eq!(payload.get::<i64>("amount"), Some(10));
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let ctx = Context::new("pid".to_string(), Arc::new(Channel::new(runtime)));
+        ctx.add_output(
+            "n1".to_string(),
+            Vars::new().with("body", json!({ "items": [{ "id": 1 }, { "id": 2 }] })),
+        );
+
+        assert_eq!(ctx.resolve_path("n1", "/body/items/1/id"), Some(json!(2)));
+        assert_eq!(ctx.resolve_path("n1", "/body/items/2"), None);
+        assert_eq!(ctx.resolve_path("n2", "/body"), None);
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn test_dump() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let ctx = Context::new("pid".to_string(), Arc::new(Channel::new(runtime)));
+        ctx.env().set("API_URL".to_string(), "https://api.example.com".to_string());
+        ctx.add_output("n1".to_string(), Vars::new().with("status_code", 200));
+
+        let dump = ctx.dump();
+        assert_eq!(dump["pid"], json!("pid"));
+        assert_eq!(dump["env"], json!({ "API_URL": "https://api.example.com" }));
+        assert_eq!(dump["outputs"], json!({ "n1": { "status_code": 200 } }));
+    }
+}
@@ -0,0 +1,62 @@
+//! Dead letters of processes that failed terminally.
+//!
+//! Once error strategies and retries are exhausted and a workflow fails, a
+//! [`DeadLetter`] describing the failure is handed to every sink registered
+//! with [`Engine::on_dead_letter`](crate::Engine::on_dead_letter), so operators
+//! can inspect and reprocess it.
+
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ShareLock, common::Vars, runtime::ProcessId, workflow::node::NodeId};
+
+/// Failure record of a process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Id of the failed process.
+    pub pid: ProcessId,
+    /// Id of the workflow the process ran.
+    pub wid: String,
+    /// Node whose failure failed the workflow; empty if it failed before any node ran.
+    pub nid: NodeId,
+    /// Error that failed the workflow.
+    pub error: String,
+    /// Inputs the process was triggered with.
+    pub inputs: Vars,
+}
+
+type DeadLetterSink = dyn Fn(&DeadLetter) + Send + Sync;
+
+/// Sinks receiving the dead letters of an engine's processes.
+#[derive(Clone, Default)]
+pub struct DeadLetters {
+    sinks: ShareLock<Vec<Arc<DeadLetterSink>>>,
+}
+
+impl DeadLetters {
+    /// Creates an empty set of sinks.
+    pub fn new() -> Self {
+        Self {
+            sinks: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Adds a sink.
+    pub fn register(
+        &self,
+        sink: impl Fn(&DeadLetter) + Send + Sync + 'static,
+    ) {
+        self.sinks.write().unwrap().push(Arc::new(sink));
+    }
+
+    /// Hands `letter` to every sink in registration order.
+    pub fn send(
+        &self,
+        letter: &DeadLetter,
+    ) {
+        for sink in self.sinks.read().unwrap().iter() {
+            sink(letter);
+        }
+    }
+}
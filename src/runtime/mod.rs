@@ -1,7 +1,9 @@
 mod channel;
 mod context;
+mod dead_letter;
 mod process;
 
 pub use channel::{Channel, ChannelEvent, ChannelOptions};
-pub use context::Context;
-pub use process::{Process, ProcessId, WorkflowCommand};
+pub use context::{Context, NodeTiming};
+pub use dead_letter::{DeadLetter, DeadLetters};
+pub use process::{DEFAULT_EVENT_HISTORY, EffectiveConfig, Process, ProcessId, ProcessOptions, ProcessState, RunStatus, RunSummary, WorkflowCommand};
@@ -3,34 +3,269 @@
 //! A process represents a running instance of a workflow. It manages
 //! the execution lifecycle, including starting, aborting, and collecting outputs.
 
-use std::sync::Arc;
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use tokio::runtime::Runtime;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::{runtime::Runtime, sync::oneshot};
 
 use crate::{
-    Result,
-    common::{Queue, Vars},
-    dispatcher::Dispatcher,
-    events::{GraphEvent, WorkflowEvent},
-    model::WorkflowModel,
-    runtime::{Channel, ChannelOptions, Context, channel::ChannelEvent},
+    ActflowError, Result,
+    common::{Clock, Queue, SystemClock, Vars},
+    dispatcher::{ConcurrencyLocks, Dispatcher, SerialGate},
+    events::{ErrorReason, Event, GraphEvent, Message, NodeEvent, WorkflowEvent},
+    model::{EnvValue, FailurePolicy, WorkflowModel},
+    runtime::{Channel, ChannelOptions, Context, DeadLetters, NodeTiming, channel::ChannelEvent},
     utils,
-    workflow::Workflow,
+    workflow::{
+        Workflow,
+        edge::EdgeId,
+        functions::TemplateFunctions,
+        interceptor::ActionInterceptors,
+        node::{NodeExecutionStatus, NodeId, NodeState},
+        template::{DEFAULT_TEMPLATE_DEPTH, NumberFormat},
+    },
 };
 
 /// Maximum number of commands that can be queued for a process.
 const COMMAND_QUEUE_SIZE: usize = 100;
 
+/// Default number of recent events kept per process.
+pub const DEFAULT_EVENT_HISTORY: usize = 100;
+
 /// Unique identifier for a workflow process instance.
 pub type ProcessId = String;
 
+/// Overall status of a process run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum RunStatus {
+    /// The process has not finished yet.
+    #[default]
+    Running,
+    /// The workflow completed successfully.
+    Succeeded,
+    /// The workflow failed.
+    Failed,
+    /// The workflow was aborted.
+    Aborted,
+}
+
+/// Lifecycle state of a process, see [`Process::state`].
+///
+/// A process moves from `Pending` to `Running` when its workflow starts, and
+/// from `Running` to a terminal state. It is `Paused` while some node waits to
+/// be resumed and no other node is running, and may finish from there, e.g.
+/// when an end node on another branch completes. Transitions not allowed by [`can_transition_to`](Self::can_transition_to)
+/// are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ProcessState {
+    /// Built but not started yet.
+    #[default]
+    Pending,
+    /// The workflow is executing.
+    Running,
+    /// Every active node is waiting to be resumed.
+    Paused,
+    /// The workflow completed successfully.
+    Succeeded,
+    /// The workflow failed.
+    Failed,
+    /// The workflow was aborted.
+    Aborted,
+}
+
+impl ProcessState {
+    /// Returns `true` for the states a process never leaves.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ProcessState::Succeeded | ProcessState::Failed | ProcessState::Aborted)
+    }
+
+    /// Returns `true` if a process may move from this state to `next`.
+    pub fn can_transition_to(
+        &self,
+        next: ProcessState,
+    ) -> bool {
+        use ProcessState::*;
+        matches!(
+            (self, next),
+            (Pending, Running) | (Running, Paused | Succeeded | Failed | Aborted) | (Paused, Running | Succeeded | Failed | Aborted)
+        )
+    }
+}
+
+/// Settings in force for a process run, see [`Process::effective_config`].
+///
+/// Reflects the engine's settings and the workflow model after defaults
+/// have been applied, to explain why a run behaved the way it did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveConfig {
+    /// How node failures affect running sibling branches.
+    pub failure_policy: FailurePolicy,
+    /// Maximum number of nodes running at once; `None` if unbounded.
+    pub max_concurrency: Option<usize>,
+    /// Number of recent events kept for [`Process::recent_events`].
+    pub event_history: usize,
+    /// Maximum number of passes made when resolving a template.
+    pub template_depth: usize,
+    /// How numbers are rendered in template strings.
+    pub number_format: NumberFormat,
+    /// Whether outputs are dropped once every node reading them has completed.
+    pub evict_consumed_outputs: bool,
+    /// Names of the environment variables set for the run, sorted.
+    pub env_keys: Vec<String>,
+}
+
+/// At-a-glance summary of a process run, see [`Process::summary`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Terminal workflow status, or `Running` while the process is active.
+    pub status: RunStatus,
+    /// Time from start to completion, or to now while running.
+    /// `None` if the process has not been started.
+    pub duration: Option<Duration>,
+    /// Number of nodes that succeeded.
+    pub succeeded: usize,
+    /// Number of nodes that failed or raised an exception.
+    pub failed: usize,
+    /// Number of nodes skipped by branching or because they are disabled.
+    pub skipped: usize,
+}
+
+/// The finished part of a failed run, from which a retry continues.
+pub(crate) struct Checkpoint {
+    /// Outputs of the nodes that executed.
+    pub outputs: HashMap<NodeId, Vars>,
+    /// Nodes skipped by branching.
+    skipped_nodes: Vec<NodeId>,
+    /// Edges of the skipped branches.
+    skipped_edges: Vec<EdgeId>,
+}
+
+/// Settings a process is created with.
+///
+/// The engine fills in its own settings; callers of
+/// [`Engine::build_workflow_process_with`](crate::Engine::build_workflow_process_with)
+/// provide the per-run fields: `inputs`, `node_inputs`, `id`, `labels`,
+/// `correlation_id` and `external_id`.
+#[derive(Clone)]
+pub struct ProcessOptions {
+    /// Process inputs, referenced in templates as `{{@input.key@}}`.
+    pub inputs: Vars,
+    /// Initial data seeded as the outputs of the given nodes, referenced in
+    /// templates as `{{#nodeId.key#}}`. A seeded node that runs replaces its
+    /// entry with its own outputs.
+    pub node_inputs: HashMap<NodeId, Vars>,
+    /// Functions callable from templates.
+    pub functions: Arc<TemplateFunctions>,
+    /// Interceptors called around every action.
+    pub interceptors: Arc<ActionInterceptors>,
+    /// Locks for node concurrency keys, shared with the engine's other processes.
+    pub concurrency_locks: Arc<ConcurrencyLocks>,
+    /// Sinks receiving a record of the process if it fails.
+    pub dead_letters: Arc<DeadLetters>,
+    /// Maximum number of passes made when resolving a template.
+    pub template_depth: usize,
+    /// How numbers are rendered in template strings.
+    pub number_format: NumberFormat,
+    /// Drops a node's outputs once every node reading them has completed.
+    pub evict_consumed_outputs: bool,
+    /// Number of recent events kept for [`Process::recent_events`].
+    pub event_history: usize,
+    /// Time source for timestamps, timeouts and retry delays.
+    pub clock: Arc<dyn Clock>,
+    /// Process id to use instead of a random one.
+    pub id: Option<ProcessId>,
+    /// Runs one node at a time, in topological order.
+    pub serial: bool,
+    /// Labels attached to the process (e.g., `tenant_id`), echoed in its start event.
+    pub labels: HashMap<String, String>,
+    /// ID attached to the process's logs and events; the process id if `None`.
+    pub correlation_id: Option<String>,
+    /// Client-supplied key deduplicating runs, see
+    /// [`Engine::build_workflow_process_with`](crate::Engine::build_workflow_process_with).
+    pub external_id: Option<String>,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            inputs: Vars::new(),
+            node_inputs: HashMap::new(),
+            functions: Arc::new(TemplateFunctions::new()),
+            interceptors: Arc::new(ActionInterceptors::new()),
+            concurrency_locks: Arc::new(ConcurrencyLocks::new()),
+            dead_letters: Arc::new(DeadLetters::new()),
+            template_depth: DEFAULT_TEMPLATE_DEPTH,
+            number_format: NumberFormat::default(),
+            evict_consumed_outputs: false,
+            event_history: DEFAULT_EVENT_HISTORY,
+            clock: Arc::new(SystemClock),
+            id: None,
+            serial: false,
+            labels: HashMap::new(),
+            correlation_id: None,
+            external_id: None,
+        }
+    }
+}
+
+/// Counters, timestamps and state behind [`RunSummary`] and [`ProcessState`].
+#[derive(Default)]
+struct RunRecord {
+    started_at: Option<i64>,
+    finished_at: Option<i64>,
+    summary: RunSummary,
+    state: ProcessState,
+    /// Nodes waiting to be resumed.
+    paused: HashSet<NodeId>,
+    /// Nodes currently executing.
+    running: HashSet<NodeId>,
+}
+
+impl RunRecord {
+    /// Moves to `next`, ignoring transitions the state machine does not allow.
+    fn transition(
+        &mut self,
+        next: ProcessState,
+    ) {
+        if self.state.can_transition_to(next) {
+            self.state = next;
+        }
+    }
+
+    /// Pauses a started run while every active node waits to be resumed, and
+    /// runs it again once any node is running or nothing is waiting.
+    fn settle(&mut self) {
+        if !matches!(self.state, ProcessState::Running | ProcessState::Paused) {
+            return;
+        }
+        if !self.paused.is_empty() && self.running.is_empty() {
+            self.transition(ProcessState::Paused);
+        } else {
+            self.transition(ProcessState::Running);
+        }
+    }
+}
+
 /// Commands that can be sent to control a workflow process.
 #[derive(Debug, Clone)]
 pub enum WorkflowCommand {
     /// Start the workflow execution.
     Start,
-    /// Abort the workflow execution.
-    Abort,
+    /// Abort the workflow execution with a reason.
+    Abort(String),
+    /// Resume a paused node, completing it with the given outputs.
+    Resume(NodeId, Vars),
+    /// Cancel a running node, failing it with the reason "Cancelled".
+    CancelNode(NodeId),
 }
 
 /// A running instance of a workflow.
@@ -57,6 +292,26 @@ pub struct Process {
     command_queue: Arc<Queue<WorkflowCommand>>,
     /// Event channel for broadcasting process events.
     channel: Arc<Channel>,
+    /// The most recent events of this process, oldest first.
+    recent_events: Arc<Mutex<VecDeque<Event<Message>>>>,
+    /// Maximum number of events kept in `recent_events`.
+    event_history: usize,
+    /// Node counts and timestamps of this run.
+    run: Arc<Mutex<RunRecord>>,
+    /// Time source for the run timestamps.
+    clock: Arc<dyn Clock>,
+    /// Labels the process was built with.
+    labels: Arc<HashMap<String, String>>,
+    /// ID attached to the process's logs and events.
+    correlation_id: String,
+    /// Workflow definition the process was built from.
+    model: Arc<WorkflowModel>,
+    /// Inputs the process was triggered with.
+    inputs: Vars,
+    /// Settings in force for this run.
+    config: EffectiveConfig,
+    /// Host data attached to the process, keyed by type.
+    extensions: Arc<Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>>,
 }
 
 impl Process {
@@ -67,6 +322,7 @@ impl Process {
     /// * `model` - Workflow definition to execute
     /// * `channel` - Event channel for broadcasting events
     /// * `runtime` - Tokio runtime for async execution
+    /// * `options` - Inputs and settings of the run, see [`ProcessOptions`]
     ///
     /// # Returns
     ///
@@ -75,24 +331,95 @@ impl Process {
         model: &WorkflowModel,
         channel: Arc<Channel>,
         runtime: Arc<Runtime>,
+        options: ProcessOptions,
     ) -> Result<Arc<Process>> {
-        let pid = utils::longid();
+        let ProcessOptions {
+            inputs,
+            node_inputs,
+            functions,
+            interceptors,
+            concurrency_locks,
+            dead_letters,
+            template_depth,
+            number_format,
+            evict_consumed_outputs,
+            event_history,
+            clock,
+            id,
+            serial,
+            labels,
+            correlation_id,
+            external_id: _,
+        } = options;
+        let pid = id.unwrap_or_else(utils::longid);
+        let correlation_id = correlation_id.unwrap_or_else(|| pid.clone());
+        let mut env_keys: Vec<String> = model.env.keys().cloned().collect();
+        env_keys.sort();
+        let config = EffectiveConfig {
+            failure_policy: model.failure_policy,
+            // Deterministic runs execute one node at a time
+            max_concurrency: serial.then_some(1),
+            event_history,
+            template_depth,
+            number_format,
+            evict_consumed_outputs,
+            env_keys,
+        };
 
         let workflow = Workflow::try_from(model)?;
+        workflow.check_references(&node_inputs.keys().cloned().collect())?;
 
         let command_queue = Queue::new(COMMAND_QUEUE_SIZE);
 
-        let ctx = Arc::new(Context::new(pid.to_owned(), channel.clone()));
+        let process_inputs = inputs.clone();
+        let mut ctx = Context::new(pid.to_owned(), channel.clone())
+            .with_http_defaults(model.http.clone().unwrap_or_default())?
+            .with_functions(functions)
+            .with_interceptors(interceptors)
+            .with_concurrency_locks(concurrency_locks)
+            .with_template_depth(template_depth)
+            .with_number_format(number_format)
+            .with_clock(clock.clone())
+            .with_process_inputs(inputs)
+            .with_labels(labels.clone())
+            .with_correlation_id(correlation_id.clone())
+            .with_vars(Vars::from_iter(model.vars.clone()));
+        if serial {
+            // Cyclic workflows have no topological order; fall back to declaration order
+            let order = workflow.topological_order().unwrap_or_else(|_| workflow.get_all_node_ids());
+            ctx = ctx.with_serial_gate(Arc::new(SerialGate::new(order)));
+        }
+        if evict_consumed_outputs {
+            ctx = ctx.with_output_consumers(workflow.output_consumers());
+        }
+        let ctx = Arc::new(ctx);
 
-        // Set environment variables from workflow model
-        model.env.iter().for_each(|(k, v)| ctx.env().set(k.clone(), v.clone()));
+        // Set environment variables from workflow model, rejecting malformed base64 values early
+        for (k, v) in &model.env {
+            if let EnvValue::Base64 {
+                ..
+            } = v
+            {
+                v.decode().map_err(|e| ActflowError::Workflow(format!("env '{}': {}", k, e)))?;
+            }
+            ctx.env().set(k.clone(), v.as_str().to_string());
+        }
 
-        let dispatcher = Arc::new(Dispatcher::new(
-            ctx.clone(),
-            Arc::new(workflow),
-            command_queue.clone(),
-            runtime.clone(),
-        ));
+        // Seed initial node data, readable before those nodes have run
+        for (nid, vars) in node_inputs {
+            if !model.nodes.iter().any(|n| n.id == nid && !n.is_annotation()) {
+                return Err(ActflowError::Workflow(format!("cannot seed inputs for unknown node {}", nid)));
+            }
+            ctx.add_output(nid, vars);
+        }
+
+        let dispatcher = Arc::new(
+            Dispatcher::new(ctx.clone(), Arc::new(workflow), command_queue.clone(), runtime.clone())
+                .with_failure_policy(model.failure_policy)
+                .with_dead_letters(model.id.clone(), dead_letters),
+        );
+
+        let run = Arc::new(Mutex::new(RunRecord::default()));
 
         Ok(Arc::new(Process {
             id: pid,
@@ -100,9 +427,99 @@ impl Process {
             dispatcher,
             command_queue,
             channel,
+            recent_events: Arc::new(Mutex::new(VecDeque::with_capacity(event_history))),
+            event_history,
+            run,
+            clock,
+            labels: Arc::new(labels),
+            correlation_id,
+            model: Arc::new(model.clone()),
+            inputs: process_inputs,
+            config,
+            extensions: Arc::new(Mutex::new(HashMap::new())),
         }))
     }
 
+    /// Returns the labels the process was built with.
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Returns the ID attached to the process's logs and events.
+    pub fn correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    /// Attaches host data to the process, e.g. the originating request context.
+    ///
+    /// One value is kept per type; setting a value of the same type replaces it.
+    /// Clones of the process share their extensions.
+    pub fn set_extension<T: Any + Send + Sync>(
+        &self,
+        value: T,
+    ) {
+        self.extensions.lock().unwrap().insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Returns the host data of type `T` attached with [`Process::set_extension`].
+    pub fn get_extension<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let value = self.extensions.lock().unwrap().get(&TypeId::of::<T>())?.clone();
+        value.downcast().ok()
+    }
+
+    /// Returns the workflow definition the process was built from.
+    pub fn model(&self) -> &WorkflowModel {
+        &self.model
+    }
+
+    /// Returns the settings in force for this run.
+    pub fn effective_config(&self) -> &EffectiveConfig {
+        &self.config
+    }
+
+    /// Returns the inputs the process was triggered with.
+    pub fn inputs(&self) -> &Vars {
+        &self.inputs
+    }
+
+    /// Captures the nodes this run executed or skipped, with their outputs.
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        let workflow = self.dispatcher.workflow();
+        let outputs = self.dispatcher.outputs();
+        Checkpoint {
+            outputs: workflow
+                .get_nodes_in_state(NodeState::Executed)
+                .into_iter()
+                .map(|nid| {
+                    let vars = outputs.get::<serde_json::Value>(&nid).map(Vars::from).unwrap_or_default();
+                    (nid, vars)
+                })
+                .collect(),
+            skipped_nodes: workflow.get_nodes_in_state(NodeState::Skipped),
+            skipped_edges: workflow.get_edges_in_state(NodeState::Skipped),
+        }
+    }
+
+    /// Marks the nodes of `checkpoint` as finished, so starting the process
+    /// runs only what the checkpointed run left unfinished.
+    ///
+    /// The checkpoint's outputs must have been seeded when building the process.
+    pub(crate) fn restore(
+        &self,
+        checkpoint: &Checkpoint,
+    ) {
+        let workflow = self.dispatcher.workflow();
+        for nid in checkpoint.outputs.keys() {
+            workflow.mark_node_executed(nid);
+        }
+        for nid in &checkpoint.skipped_nodes {
+            workflow.mark_node_skipped(nid);
+        }
+        for eid in &checkpoint.skipped_edges {
+            workflow.mark_edge_skipped(eid);
+        }
+    }
+
     /// Returns the unique process identifier.
     pub fn id(&self) -> &str {
         &self.id
@@ -117,26 +534,83 @@ impl Process {
     ///
     /// This method:
     /// 1. Starts the dispatcher
-    /// 2. Registers event handlers for completion/failure/abort
+    /// 2. Registers event handlers tracking the run and its completion/failure/abort
     /// 3. Sends the start command to begin execution
     pub fn start(&self) {
+        self.run.lock().unwrap().started_at = Some(self.clock.now_millis());
         self.dispatcher.start();
 
-        let dispatcher = self.dispatcher.clone();
-
-        ChannelEvent::channel(self.channel.clone(), ChannelOptions::with_pid(self.id.to_owned())).on_event(move |event| {
-            let dispatcher = dispatcher.clone();
+        // Tally node results before any subscriber observes them; registered here
+        // so processes that are never started leave nothing behind
+        let record = self.run.clone();
+        let record_clock = self.clock.clone();
+        ChannelEvent::channel(self.channel.clone(), ChannelOptions::with_pid(self.id.to_owned())).on_event_until_first(move |event| {
+            let mut record = record.lock().unwrap();
             match &event.event {
-                GraphEvent::Workflow(e) => match e {
-                    WorkflowEvent::Succeeded | WorkflowEvent::Failed(_) | WorkflowEvent::Aborted(_) => {
-                        dispatcher.stop();
-                    }
-                    _ => {}
-                },
+                GraphEvent::Node(NodeEvent::Succeeded(_)) => record.summary.succeeded += 1,
+                GraphEvent::Node(NodeEvent::Error(_)) => record.summary.failed += 1,
+                GraphEvent::Node(NodeEvent::Skipped) => record.summary.skipped += 1,
+                GraphEvent::Workflow(WorkflowEvent::Start(_)) => record.transition(ProcessState::Running),
+                GraphEvent::Workflow(WorkflowEvent::Paused(_)) => record.transition(ProcessState::Paused),
+                GraphEvent::Workflow(workflow_event) => {
+                    let (status, state) = match workflow_event {
+                        WorkflowEvent::Succeeded => (RunStatus::Succeeded, ProcessState::Succeeded),
+                        WorkflowEvent::Failed(_) => (RunStatus::Failed, ProcessState::Failed),
+                        WorkflowEvent::Aborted(_) => (RunStatus::Aborted, ProcessState::Aborted),
+                        _ => return false,
+                    };
+                    record.summary.status = status;
+                    record.transition(state);
+                    record.finished_at = Some(record_clock.now_millis());
+                    return true;
+                }
                 _ => {}
             }
+
+            // Track running and paused nodes; a resumed node finishes without running again
+            if let GraphEvent::Node(node_event) = &event.event {
+                match node_event {
+                    NodeEvent::Running(_) => {
+                        record.running.insert(event.nid.clone());
+                    }
+                    NodeEvent::Paused(_) => {
+                        record.running.remove(&event.nid);
+                        record.paused.insert(event.nid.clone());
+                    }
+                    NodeEvent::Succeeded(_) | NodeEvent::Error(_) | NodeEvent::Skipped | NodeEvent::Stopped(_) => {
+                        record.running.remove(&event.nid);
+                        record.paused.remove(&event.nid);
+                    }
+                    _ => return false,
+                }
+                record.settle();
+            }
+            false
         });
 
+        let dispatcher = self.dispatcher.clone();
+
+        ChannelEvent::channel(self.channel.clone(), ChannelOptions::with_pid(self.id.to_owned())).on_event_until(move |event| {
+            if event.event.is_finished() {
+                dispatcher.stop();
+            }
+            event.event.is_finished()
+        });
+
+        // Record recent events so late subscribers can catch up, up to the final one
+        let recent_events = self.recent_events.clone();
+        let event_history = self.event_history;
+        if event_history > 0 {
+            ChannelEvent::channel(self.channel.clone(), ChannelOptions::with_pid(self.id.to_owned())).on_event_until(move |event| {
+                let mut recent_events = recent_events.lock().unwrap();
+                if recent_events.len() == event_history {
+                    recent_events.pop_front();
+                }
+                recent_events.push_back(event.clone());
+                event.event.is_finished()
+            });
+        }
+
         // Send start command to the command queue
         let _ = self.command_queue.send(WorkflowCommand::Start);
     }
@@ -145,7 +619,121 @@ impl Process {
     ///
     /// Sends an abort command to gracefully terminate the running workflow.
     pub fn abort(&self) {
-        let _ = self.command_queue.send(WorkflowCommand::Abort);
+        self.abort_with("Aborted by command");
+    }
+
+    /// Aborts the workflow execution, reporting `reason` to `on_abort` subscribers.
+    pub fn abort_with(
+        &self,
+        reason: &str,
+    ) {
+        let _ = self.command_queue.send(WorkflowCommand::Abort(reason.to_string()));
+    }
+
+    /// Resumes a node that paused itself, completing it with `outputs`.
+    ///
+    /// The dispatcher then continues scheduling the node's successors as if
+    /// the node had succeeded. Resuming a node that is not paused is ignored.
+    pub fn resume_node(
+        &self,
+        nid: &str,
+        outputs: Vars,
+    ) -> Result<()> {
+        self.command_queue.send(WorkflowCommand::Resume(nid.to_string(), outputs))
+    }
+
+    /// Cancels the node `nid` while it is running.
+    ///
+    /// The node fails with "Cancelled" and is handled by its error strategy,
+    /// e.g. continuing along its `fail_branch` edges. Without one, the workflow
+    /// fails as for any node error. Cancelling a node that is not running is ignored.
+    pub fn cancel_node(
+        &self,
+        nid: &str,
+    ) -> Result<()> {
+        self.command_queue.send(WorkflowCommand::CancelNode(nid.to_string()))
+    }
+
+    /// Waits until the node `nid` reaches `state`.
+    ///
+    /// The subscription is registered when this method is called, so call it
+    /// before [`start`](Self::start) to avoid missing fast transitions. The
+    /// returned future fails if the workflow finishes first.
+    pub fn wait_for_node(
+        &self,
+        nid: &str,
+        state: NodeExecutionStatus,
+    ) -> impl Future<Output = Result<()>> + use<> {
+        let (tx, rx) = oneshot::channel();
+        let tx = Mutex::new(Some(tx));
+        let nid = nid.to_string();
+
+        // Removed once the wait resolves
+        ChannelEvent::channel(self.channel.clone(), ChannelOptions::with_pid(self.id.to_owned())).on_event_until(move |event| {
+            let result = match &event.event {
+                GraphEvent::Node(node_event) if event.nid == nid && node_event_status(node_event) == Some(state) => Ok(()),
+                GraphEvent::Workflow(workflow_event @ (WorkflowEvent::Succeeded | WorkflowEvent::Failed(_) | WorkflowEvent::Aborted(_))) => {
+                    Err(ActflowError::Process(format!(
+                        "workflow finished ({}) before node {} reached {}",
+                        workflow_event.str(),
+                        nid,
+                        state.as_ref()
+                    )))
+                }
+                _ => return false,
+            };
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send(result);
+            }
+            true
+        });
+
+        async move { rx.await.map_err(|_| ActflowError::Process("event channel closed".to_string()))? }
+    }
+
+    /// Returns the most recent events of this process, oldest first.
+    ///
+    /// At most the configured event history is kept (see
+    /// [`EngineBuilder::event_history`](crate::EngineBuilder::event_history)).
+    /// Recording starts when the process is started.
+    pub fn recent_events(&self) -> Vec<Event<Message>> {
+        self.recent_events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Returns a summary of the run: its status, duration and node counts.
+    ///
+    /// Counts cover node events since the process was started, so a summary
+    /// taken mid-run reflects the nodes finished so far.
+    pub fn summary(&self) -> RunSummary {
+        let record = self.run.lock().unwrap();
+        let duration = record.started_at.map(|started_at| {
+            let finished_at = record.finished_at.unwrap_or_else(|| self.clock.now_millis());
+            Duration::from_millis(finished_at.saturating_sub(started_at).max(0) as u64)
+        });
+
+        RunSummary {
+            duration,
+            ..record.summary.clone()
+        }
+    }
+
+    /// Returns the current lifecycle state of the process.
+    pub fn state(&self) -> ProcessState {
+        self.run.lock().unwrap().state
+    }
+
+    /// Returns the execution timings of the nodes that have started, keyed by node id.
+    ///
+    /// `queue_wait_ms` shows how long a node waited for a concurrency cap
+    /// (serial mode or a shared `concurrency_key`) after it became ready.
+    pub fn node_timings(&self) -> HashMap<NodeId, NodeTiming> {
+        self.dispatcher.timings()
+    }
+
+    /// Returns the approximate memory held by node outputs: the size of their
+    /// JSON serialization, in bytes.
+    pub fn outputs_byte_size(&self) -> usize {
+        self.dispatcher.outputs_byte_size()
     }
 
     /// Returns the collected outputs from all executed nodes.
@@ -153,10 +741,782 @@ impl Process {
         self.dispatcher.outputs()
     }
 
+    /// Returns the outputs as JSON keyed by node id, with object keys sorted
+    /// at every level, so the same run always serializes the same way.
+    ///
+    /// Suited to golden-file tests of workflows; see
+    /// [`outputs_snapshot_excluding`](Self::outputs_snapshot_excluding) to
+    /// leave out values that change between runs.
+    pub fn outputs_snapshot(&self) -> Value {
+        snapshot(Value::from(self.get_outputs()), &mut Vec::new(), &GlobSet::empty())
+    }
+
+    /// Returns [`outputs_snapshot`](Self::outputs_snapshot) without the values
+    /// whose path matches one of `patterns`, such as timestamps and generated ids.
+    ///
+    /// Paths are dotted, starting with the node id, with array elements
+    /// addressed by index: `fetch.headers.date`, `*.id`, `**.created_at`.
+    /// `*` matches within one segment and `**` any number of segments.
+    ///
+    /// Returns [`ActflowError::Process`] if a pattern is invalid.
+    pub fn outputs_snapshot_excluding(
+        &self,
+        patterns: &[&str],
+    ) -> Result<Value> {
+        let mut excludes = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(&pattern.replace('.', "/"))
+                .literal_separator(true)
+                .build()
+                .map_err(|e| ActflowError::Process(format!("invalid snapshot exclude pattern '{}': {}", pattern, e)))?;
+            excludes.add(glob);
+        }
+        let excludes = excludes.build().map_err(|e| ActflowError::Process(e.to_string()))?;
+
+        Ok(snapshot(Value::from(self.get_outputs()), &mut Vec::new(), &excludes))
+    }
+
+    /// Deserializes the collected outputs into a caller-defined type.
+    ///
+    /// The outputs are an object keyed by node id, so `T` typically has one
+    /// field per node of interest:
+    ///
+    /// ```rust,ignore
+    /// #[derive(Deserialize)]
+    /// struct Outputs {
+    ///     n2: Doubled,
+    /// }
+    ///
+    /// let outputs: Outputs = process.outputs_as()?;
+    /// ```
+    pub fn outputs_as<T: DeserializeOwned>(&self) -> Result<T> {
+        let outputs: serde_json::Value = self.get_outputs().into();
+        serde_json::from_value(outputs).map_err(|e| ActflowError::Convert(format!("failed to convert process outputs: {}", e)))
+    }
+
     /// Checks if the workflow execution has completed.
     ///
     /// Returns `true` if the workflow has finished (success, failure, or abort).
     pub fn is_complete(&self) -> bool {
         self.dispatcher.is_complete()
     }
+
+    /// Waits until the events sent so far have been handled by the process's
+    /// own subscribers and every other synchronous event handler.
+    ///
+    /// Events are handled on the engine's runtime after they are sent, so
+    /// [`summary`](Self::summary), [`state`](Self::state) and
+    /// [`recent_events`](Self::recent_events) may lag behind
+    /// [`is_complete`](Self::is_complete); awaiting this first makes them
+    /// reflect every event sent before the call, e.g. in tests.
+    pub async fn flush_events(&self) {
+        self.channel.flush_events().await
+    }
+}
+
+/// Rebuilds `value` with sorted object keys, dropping the values below `path`
+/// whose `/`-joined path matches `excludes`.
+fn snapshot(
+    value: Value,
+    path: &mut Vec<String>,
+    excludes: &GlobSet,
+) -> Value {
+    let mut child = |key: String, value: Value| {
+        path.push(key);
+        let kept = (!excludes.is_match(path.join("/"))).then(|| snapshot(value, path, excludes));
+        path.pop();
+        kept
+    };
+
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(entries.into_iter().filter_map(|(key, value)| child(key.clone(), value).map(|value| (key, value))).collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().enumerate().filter_map(|(i, item)| child(i.to_string(), item)).collect()),
+        value => value,
+    }
+}
+
+/// Maps a node event to the execution status it reports, if any.
+fn node_event_status(event: &NodeEvent) -> Option<NodeExecutionStatus> {
+    match event {
+        NodeEvent::Succeeded(_) => Some(NodeExecutionStatus::Succeeded),
+        NodeEvent::Error(ErrorReason::Failed(_) | ErrorReason::Timeout) => Some(NodeExecutionStatus::Failed),
+        NodeEvent::Error(ErrorReason::Exception(_)) => Some(NodeExecutionStatus::Exception),
+        NodeEvent::Stopped(_) => Some(NodeExecutionStatus::Stopped),
+        NodeEvent::Paused(_) => Some(NodeExecutionStatus::Paused),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "js"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, mpsc},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::test_support::{branch_edge, edge, node, start_and_wait, start_node};
+    use crate::{
+        ActflowError, ActionInterceptor, ActionOutput, ChannelEvent, ChannelOptions, Context, EdgeModel, EffectiveConfig, Engine, EngineBuilder, EnvValue,
+        FailurePolicy, GraphEvent, Node, NodeEvent, NodeExecutionStatus, NodeModel, NumberFormat, ProcessState, RunStatus, RunSummary, Vars, WorkflowEvent,
+        WorkflowModel,
+        runtime::{DEFAULT_EVENT_HISTORY, ProcessOptions},
+    };
+
+    #[derive(Deserialize)]
+    struct Doubled {
+        doubled: i64,
+    }
+
+    #[derive(Deserialize)]
+    struct Outputs {
+        n2: Doubled,
+    }
+
+    fn create_double_workflow() -> WorkflowModel {
+        WorkflowModel {
+            id: "double".to_string(),
+            nodes: vec![
+                start_node("n1"),
+                NodeModel {
+                    id: "n2".to_string(),
+                    uses: "code".to_string(),
+                    action: json!({
+                        "variables": [{ "variable": "value", "value_selector": "21" }],
+                        "code_language": "javascript",
+                        "code": "function main({ value }) { return { doubled: value * 2 } }"
+                    }),
+                    ..Default::default()
+                },
+            ],
+            edges: vec![edge("e1", "n1", "n2")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let engine = EngineBuilder::new().event_history(10).template_depth(3).build().unwrap();
+        engine.launch();
+
+        let mut workflow = create_double_workflow();
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        assert_eq!(
+            *process.effective_config(),
+            EffectiveConfig {
+                failure_policy: FailurePolicy::FailFast,
+                max_concurrency: None,
+                event_history: 10,
+                template_depth: 3,
+                number_format: NumberFormat::Json,
+                evict_consumed_outputs: false,
+                env_keys: vec![],
+            }
+        );
+
+        workflow.failure_policy = FailurePolicy::WaitAll;
+        workflow.env = HashMap::from([("TOKEN".to_string(), EnvValue::from("secret")), ("API_URL".to_string(), EnvValue::from("http://api"))]);
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        let config = process.effective_config();
+        assert_eq!(config.failure_policy, FailurePolicy::WaitAll);
+        assert_eq!(config.env_keys, vec!["API_URL", "TOKEN"]);
+
+        let engine = EngineBuilder::new().deterministic().build().unwrap();
+        engine.launch();
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        assert_eq!(process.effective_config().max_concurrency, Some(1));
+        assert_eq!(process.effective_config().event_history, DEFAULT_EVENT_HISTORY);
+        assert_eq!(process.effective_config().template_depth, 1);
+    }
+
+    #[test]
+    fn test_node_timings_report_queue_wait() {
+        // Deterministic runs execute one node at a time
+        let engine = EngineBuilder::new().deterministic().build().unwrap();
+        engine.launch();
+
+        let mut workflow = create_double_workflow();
+        workflow.nodes.push(NodeModel {
+            id: "n3".to_string(),
+            ..workflow.nodes[1].clone()
+        });
+        workflow.edges.push(EdgeModel {
+            id: "e2".to_string(),
+            target: "n3".to_string(),
+            ..workflow.edges[0].clone()
+        });
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        let timings = process.node_timings();
+        assert_eq!(timings.len(), 3);
+        for timing in timings.values() {
+            assert_eq!(timing.queue_wait_ms, timing.start_time - timing.ready_time);
+            assert!(timing.end_time.is_some_and(|end_time| end_time >= timing.start_time));
+        }
+
+        // n2 and n3 became ready together; the one started second waited for the other to finish
+        let (first, second) = if timings["n2"].start_time < timings["n3"].start_time {
+            (timings["n2"], timings["n3"])
+        } else {
+            (timings["n3"], timings["n2"])
+        };
+        assert!(second.ready_time < first.end_time.unwrap());
+        assert!(second.start_time >= first.end_time.unwrap());
+        assert!(second.queue_wait_ms > 0);
+
+        engine.shutdown();
+    }
+
+    /// `n1 -> big -> reader -> last`, where only `reader` reads the large outputs of `big`.
+    fn create_large_output_workflow() -> WorkflowModel {
+        let code_node = |id: &str, selector: &str, code: &str| NodeModel {
+            id: id.to_string(),
+            uses: "code".to_string(),
+            action: json!({
+                "variables": [{ "variable": "value", "value_selector": selector }],
+                "code_language": "javascript",
+                "code": code
+            }),
+            ..Default::default()
+        };
+        WorkflowModel {
+            id: "large_output".to_string(),
+            nodes: vec![
+                start_node("n1"),
+                code_node("big", "0", "function main() { return { data: 'x'.repeat(10000) } }"),
+                code_node(
+                    "reader",
+                    "{{#big.data#}}",
+                    "function main({ value }) { return { length: value.length } }",
+                ),
+                code_node(
+                    "last",
+                    "{{#reader.length#}}",
+                    "function main({ value }) { return { length: value } }",
+                ),
+            ],
+            edges: vec![edge("e1", "n1", "big"), edge("e2", "big", "reader"), edge("e3", "reader", "last")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evict_consumed_outputs() {
+        let run = |engine: &Engine| {
+            engine.launch();
+            let process = engine.build_workflow_process(&create_large_output_workflow()).unwrap();
+            start_and_wait(engine, &process);
+            process
+        };
+
+        let kept = run(&EngineBuilder::new().build().unwrap());
+        assert!(kept.get_outputs().get::<serde_json::Value>("big").is_some());
+        assert!(kept.outputs_byte_size() > 10000);
+
+        let engine = EngineBuilder::new().evict_consumed_outputs().build().unwrap();
+        let evicted = run(&engine);
+        assert!(evicted.effective_config().evict_consumed_outputs);
+        let outputs = evicted.get_outputs();
+        // `big` and `reader` were dropped once their only readers completed
+        assert!(outputs.get::<serde_json::Value>("big").is_none());
+        assert!(outputs.get::<serde_json::Value>("reader").is_none());
+        // ...after `reader` and `last` had resolved their templates from them
+        assert_eq!(outputs.get::<serde_json::Value>("last"), Some(json!({ "length": 10000 })));
+        assert!(evicted.outputs_byte_size() < 1000);
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_extensions() {
+        #[derive(Debug, PartialEq)]
+        struct RequestContext {
+            request_id: String,
+        }
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let process = engine.build_workflow_process(&create_double_workflow()).unwrap();
+        assert!(process.get_extension::<RequestContext>().is_none());
+        process.set_extension(RequestContext {
+            request_id: "req-1".to_string(),
+        });
+        process.set_extension(7_u32);
+        process.set_extension(8_u32);
+
+        // Event handlers reach the originating request through a clone of the process
+        let (tx, rx) = mpsc::channel();
+        let handler_process = process.clone();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_complete(move |_| {
+            let _ = tx.send(handler_process.get_extension::<RequestContext>());
+        });
+        process.start();
+
+        let context = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(context.request_id, "req-1");
+        assert_eq!(process.get_extension::<u32>().as_deref(), Some(&8));
+        assert!(process.get_extension::<String>().is_none());
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_seed_node_inputs() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let code_node = |id: &str, selector: &str| NodeModel {
+            id: id.to_string(),
+            uses: "code".to_string(),
+            action: json!({
+                "variables": [{ "variable": "value", "value_selector": selector }],
+                "code_language": "javascript",
+                "code": "function main({ value }) { return { doubled: value * 2 } }"
+            }),
+            ..Default::default()
+        };
+        let workflow = WorkflowModel {
+            id: "seeded".to_string(),
+            nodes: vec![start_node("n1"), code_node("a", "{{#a.value#}}"), code_node("b", "{{#b.value#}}")],
+            edges: vec![edge("e1", "n1", "a"), edge("e2", "n1", "b")],
+            ..Default::default()
+        };
+
+        let node_inputs = HashMap::from([("a".to_string(), Vars::from(json!({ "value": 2 }))), ("b".to_string(), Vars::from(json!({ "value": 5 })))]);
+        let process = engine
+            .build_workflow_process_with(
+                &workflow,
+                ProcessOptions {
+                    node_inputs,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        start_and_wait(&engine, &process);
+
+        let outputs = process.get_outputs();
+        assert_eq!(outputs.get::<serde_json::Value>("a"), Some(json!({ "doubled": 4 })));
+        assert_eq!(outputs.get::<serde_json::Value>("b"), Some(json!({ "doubled": 10 })));
+
+        let unknown = HashMap::from([("missing".to_string(), Vars::new())]);
+        assert!(
+            engine
+                .build_workflow_process_with(
+                    &workflow,
+                    ProcessOptions {
+                        node_inputs: unknown,
+                        ..Default::default()
+                    },
+                )
+                .is_err()
+        );
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_base64_env_value() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let mut workflow = WorkflowModel {
+            id: "base64_env".to_string(),
+            env: HashMap::from([(
+                "GREETING".to_string(),
+                EnvValue::Base64 {
+                    base64: "aGVsbG8=".to_string(),
+                },
+            )]),
+            nodes: vec![
+                start_node("n1"),
+                NodeModel {
+                    id: "end".to_string(),
+                    uses: "end".to_string(),
+                    action: json!({ "outputs": { "raw": "{{$GREETING$}}", "greeting": "{{ base64decode({{$GREETING$}}) }}" } }),
+                    ..Default::default()
+                },
+            ],
+            edges: vec![edge("e1", "n1", "end")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        assert_eq!(
+            process.get_outputs().get::<serde_json::Value>("end"),
+            Some(json!({ "raw": "aGVsbG8=", "greeting": "hello" }))
+        );
+
+        workflow.env.insert(
+            "BROKEN".to_string(),
+            EnvValue::Base64 {
+                base64: "not base64!".to_string(),
+            },
+        );
+        let err = engine.build_workflow_process(&workflow).err().unwrap();
+        assert!(err.to_string().starts_with("env 'BROKEN': invalid base64"));
+
+        engine.shutdown();
+    }
+
+    /// Runs `workflow` and returns the process state seen after each workflow event.
+    fn observe_states(workflow: &WorkflowModel) -> Vec<ProcessState> {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        let process = engine.build_workflow_process(workflow).unwrap();
+        let mut states = vec![process.state()];
+
+        let (tx, rx) = mpsc::channel();
+        let observed = process.clone();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if let GraphEvent::Workflow(_) = &e.event {
+                let _ = tx.send((observed.state(), e.event.is_complete() || e.event.is_error()));
+            }
+        });
+        process.start();
+
+        loop {
+            let (state, finished) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            states.push(state);
+            if finished {
+                break;
+            }
+        }
+        engine.shutdown();
+        states
+    }
+
+    #[test]
+    fn test_state_transitions() {
+        assert_eq!(
+            observe_states(&create_double_workflow()),
+            vec![ProcessState::Pending, ProcessState::Running, ProcessState::Succeeded]
+        );
+
+        let mut failing = create_double_workflow();
+        failing.nodes[1].action["code"] = json!("function main() { throw new Error('boom') }");
+        assert_eq!(
+            observe_states(&failing),
+            vec![ProcessState::Pending, ProcessState::Running, ProcessState::Failed]
+        );
+
+        assert!(!ProcessState::Succeeded.can_transition_to(ProcessState::Running));
+        assert!(!ProcessState::Pending.can_transition_to(ProcessState::Succeeded));
+        assert!(ProcessState::Paused.can_transition_to(ProcessState::Running));
+        assert!(ProcessState::Paused.can_transition_to(ProcessState::Succeeded));
+    }
+
+    /// Pauses the listed nodes instead of running them.
+    struct PausingInterceptor(Vec<&'static str>);
+
+    impl ActionInterceptor for PausingInterceptor {
+        fn before_run(
+            &self,
+            _ctx: &Context,
+            node: &Node,
+        ) -> Option<ActionOutput> {
+            self.0.contains(&node.id.as_str()).then(ActionOutput::paused)
+        }
+    }
+
+    #[test]
+    fn test_end_node_completes_while_sibling_paused() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        engine.register_interceptor(PausingInterceptor(vec!["approve", "gate"]));
+
+        let code_node = |id: &str| NodeModel {
+            id: id.to_string(),
+            uses: "code".to_string(),
+            action: json!({
+                "variables": [],
+                "code_language": "javascript",
+                "code": "function main() { return {} }"
+            }),
+            ..Default::default()
+        };
+        let workflow = WorkflowModel {
+            id: "paused_sibling".to_string(),
+            nodes: vec![start_node("n1"), code_node("approve"), code_node("gate"), node("end", "end", json!({}))],
+            edges: vec![edge("e1", "n1", "approve"), edge("e2", "n1", "gate"), edge("e3", "gate", "end")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        process.start();
+
+        // Paused only once both branches wait and nothing is running
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while process.state() != ProcessState::Paused {
+            assert!(Instant::now() < deadline, "workflow did not pause");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // The end node finishes the workflow while `approve` is still paused
+        let (tx, rx) = mpsc::channel();
+        let observed = process.clone();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if e.event.is_finished() {
+                let _ = tx.send((e.event.clone(), observed.state()));
+            }
+        });
+        process.resume_node("gate", Vars::new()).unwrap();
+
+        let (event, state) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(event.is_complete());
+        assert_eq!(state, ProcessState::Succeeded);
+        assert_eq!(process.summary().status, RunStatus::Succeeded);
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_summary_counts_skipped_branch() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let code_node = |id: &str| NodeModel {
+            id: id.to_string(),
+            uses: "code".to_string(),
+            action: json!({
+                "variables": [],
+                "code_language": "javascript",
+                "code": "function main() { return {} }"
+            }),
+            ..Default::default()
+        };
+        let workflow = WorkflowModel {
+            id: "branching".to_string(),
+            nodes: vec![
+                start_node("n1"),
+                NodeModel {
+                    id: "cond".to_string(),
+                    uses: "if_else".to_string(),
+                    action: json!({
+                        "cases": [{
+                            "case_id": "true",
+                            "logical_operator": "and",
+                            "conditions": [{
+                                "variable_selector": "{{@input.flag@}}",
+                                "comparison_operator": "is",
+                                "value": "yes"
+                            }]
+                        }]
+                    }),
+                    ..Default::default()
+                },
+                code_node("yes"),
+                code_node("no"),
+            ],
+            edges: vec![branch_edge("e1", "n1", "cond", "source"), branch_edge("e2", "cond", "yes", "true"), branch_edge("e3", "cond", "no", "false")],
+            ..Default::default()
+        };
+        let process = engine
+            .build_workflow_process_with(
+                &workflow,
+                ProcessOptions {
+                    inputs: Vars::from(json!({ "flag": "yes" })),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(process.summary(), RunSummary::default());
+
+        start_and_wait(&engine, &process);
+
+        let summary = process.summary();
+        assert_eq!(summary.status, RunStatus::Succeeded);
+        assert_eq!((summary.succeeded, summary.failed, summary.skipped), (3, 0, 1));
+        assert!(summary.duration.is_some());
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_outputs_as() {
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let process = engine.build_workflow_process(&create_double_workflow()).unwrap();
+
+        start_and_wait(&engine, &process);
+
+        let outputs: Outputs = process.outputs_as().unwrap();
+        assert_eq!(outputs.n2.doubled, 42);
+
+        assert!(process.outputs_as::<Vec<i64>>().is_err());
+    }
+
+    #[test]
+    fn test_wait_for_node() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let engine = EngineBuilder::new().runtime(runtime.clone()).build().unwrap();
+        engine.launch();
+
+        let mut workflow = create_double_workflow();
+        workflow.nodes.push(node("n3", "end", json!({})));
+        workflow.edges.push(edge("e2", "n2", "n3"));
+        let handlers = engine.channel().event_handler_count();
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let n2_succeeded = process.wait_for_node("n2", NodeExecutionStatus::Succeeded);
+        let n2_paused = process.wait_for_node("n2", NodeExecutionStatus::Paused);
+        process.start();
+
+        runtime.block_on(async {
+            tokio::time::timeout(Duration::from_secs(5), n2_succeeded).await.unwrap().unwrap();
+            let err = tokio::time::timeout(Duration::from_secs(5), n2_paused).await.unwrap().unwrap_err();
+            assert!(err.to_string().contains("before node n2 reached paused"));
+            process.flush_events().await;
+        });
+
+        // The waits and the process's own handlers are gone once the run finished
+        assert_eq!(engine.channel().event_handler_count(), handlers);
+        assert!(process.recent_events().last().is_some_and(|e| e.event.is_complete()));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_abort_with_reason() {
+        // Accepts connections but never answers, keeping the http node running
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+
+        let mut workflow = create_double_workflow();
+        workflow.nodes[1] = NodeModel {
+            id: "n2".to_string(),
+            uses: "http_request".to_string(),
+            action: json!({
+                "url": format!("http://127.0.0.1:{}/", port),
+                "method": "GET",
+                "auth": { "auth_type": "no_auth" },
+                "headers": {},
+                "params": {},
+                "body": { "content_type": "none" },
+                "timeout": 10000
+            }),
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        let (running_tx, running_rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+            if e.nid == "n2" && matches!(e.event, GraphEvent::Node(NodeEvent::Running(_))) {
+                let _ = running_tx.send(());
+            }
+        });
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_abort(move |pid, reason| {
+            let _ = tx.send((pid, reason));
+        });
+
+        process.start();
+        running_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        process.abort_with("cancelled by user");
+
+        let (pid, reason) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(pid, process.id());
+        assert_eq!(reason, "cancelled by user");
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_flush_events() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let engine = EngineBuilder::new().runtime(runtime.clone()).build().unwrap();
+        engine.launch();
+
+        let process = engine.build_workflow_process(&create_double_workflow()).unwrap();
+        process.start();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !process.is_complete() {
+            assert!(Instant::now() < deadline, "workflow did not complete");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        runtime.block_on(process.flush_events());
+        assert_eq!(process.state(), ProcessState::Succeeded);
+        assert_eq!(process.summary().succeeded, 2);
+        assert!(process.recent_events().last().is_some_and(|e| e.event.is_complete()));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_outputs_snapshot() {
+        let engine = EngineBuilder::new().deterministic().build().unwrap();
+        engine.launch();
+
+        let mut workflow = create_double_workflow();
+        workflow.nodes[1].action = json!({
+            "variables": [{ "variable": "value", "value_selector": "21" }],
+            "code_language": "javascript",
+            "code": "function main({ value }) { return { id: Math.random(), doubled: value * 2, meta: { at: Date.now(), unit: 'x' }, items: [{ id: Math.random(), n: 1 }] } }"
+        });
+        let process = engine.build_workflow_process(&workflow).unwrap();
+        process.start();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !process.is_complete() {
+            assert!(Instant::now() < deadline, "workflow did not complete");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let snapshot = process.outputs_snapshot();
+        assert_eq!(
+            snapshot["n2"].as_object().unwrap().keys().collect::<Vec<_>>(),
+            vec!["doubled", "id", "items", "meta"]
+        );
+
+        let snapshot = process.outputs_snapshot_excluding(&["*.id", "**.at", "n2.items.*.id"]).unwrap();
+        let fixture = r#"{"n1":{},"n2":{"doubled":42,"items":[{"n":1}],"meta":{"unit":"x"}}}"#;
+        assert_eq!(serde_json::to_string(&snapshot).unwrap(), fixture);
+
+        assert!(matches!(
+            process.outputs_snapshot_excluding(&["n2.[id"]),
+            Err(ActflowError::Process(_))
+        ));
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_recent_events() {
+        let engine = EngineBuilder::new().event_history(3).build().unwrap();
+        engine.launch();
+
+        let process = engine.build_workflow_process(&create_double_workflow()).unwrap();
+        process.start();
+
+        // The recorder may see the final event just after other subscribers
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !process.recent_events().last().is_some_and(|e| e.event.is_complete()) {
+            assert!(Instant::now() < deadline, "workflow did not complete");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let events = process.recent_events();
+        assert_eq!(events.len(), 3);
+        assert!(events[0].nid == "n2" && matches!(events[0].event, GraphEvent::Node(NodeEvent::Running(_))));
+        assert!(events[1].nid == "n2" && matches!(events[1].event, GraphEvent::Node(NodeEvent::Succeeded(_))));
+        assert!(matches!(events[2].event, GraphEvent::Workflow(WorkflowEvent::Succeeded)));
+
+        engine.shutdown();
+    }
 }
@@ -0,0 +1,64 @@
+//! Fixtures shared by the unit tests.
+
+use std::{sync::mpsc, time::Duration};
+
+use serde_json::{Value, json};
+
+use crate::{ChannelEvent, ChannelOptions, EdgeModel, Engine, GraphEvent, NodeModel, runtime::Process};
+
+/// A node running the `uses` action configured with `action`.
+pub(crate) fn node(
+    id: &str,
+    uses: &str,
+    action: Value,
+) -> NodeModel {
+    NodeModel {
+        id: id.to_string(),
+        uses: uses.to_string(),
+        action,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn start_node(id: &str) -> NodeModel {
+    node(id, "start", json!({}))
+}
+
+pub(crate) fn edge(
+    id: &str,
+    source: &str,
+    target: &str,
+) -> EdgeModel {
+    branch_edge(id, source, target, "source")
+}
+
+/// An edge leaving `source` through `source_handle`, e.g. an if_else case.
+pub(crate) fn branch_edge(
+    id: &str,
+    source: &str,
+    target: &str,
+    source_handle: &str,
+) -> EdgeModel {
+    EdgeModel {
+        id: id.to_string(),
+        source: source.to_string(),
+        target: target.to_string(),
+        source_handle: source_handle.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Starts `process` and waits until its workflow succeeds or fails, returning that event.
+pub(crate) fn start_and_wait(
+    engine: &Engine,
+    process: &Process,
+) -> GraphEvent {
+    let (tx, rx) = mpsc::channel();
+    ChannelEvent::channel(engine.channel(), ChannelOptions::with_pid(process.id().to_string())).on_event(move |e| {
+        if e.event.is_complete() || e.event.is_error() {
+            let _ = tx.send(e.event.clone());
+        }
+    });
+    process.start();
+    rx.recv_timeout(Duration::from_secs(5)).unwrap()
+}
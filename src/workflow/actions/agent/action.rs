@@ -1,9 +1,15 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tokio_stream::StreamExt;
-use tonic::transport::Channel;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{
+    metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap},
+    transport::Channel,
+};
 
 use crate::{
     ActflowError, Result,
@@ -25,9 +31,65 @@ pub struct AgentAction {
     endpoint: String,
     /// Inputs to the agent
     inputs: serde_json::Value,
+    /// gRPC metadata sent with the request (e.g., auth tokens, trace headers),
+    /// values may contain templates
+    #[serde(default)]
+    metadata: HashMap<String, String>,
 }
 
 impl AgentAction {
+    /// Resolves the configured metadata and adds it to the request's metadata map.
+    fn apply_metadata(
+        &self,
+        ctx: &Context,
+        nid: &NodeId,
+        metadata: &mut MetadataMap,
+    ) -> Result<()> {
+        for (key, value) in &self.metadata {
+            let value = template::resolve_template_in(ctx, value, nid, &format!("metadata.{}", key))?;
+            let value =
+                AsciiMetadataValue::try_from(value.as_str()).map_err(|e| ActflowError::Action(format!("invalid value for metadata '{}': {}", key, e)))?;
+            metadata.insert(Self::metadata_key(key)?, value);
+        }
+        Ok(())
+    }
+
+    fn metadata_key(key: &str) -> Result<AsciiMetadataKey> {
+        AsciiMetadataKey::from_bytes(key.as_bytes()).map_err(|e| ActflowError::Action(format!("invalid metadata key '{}': {}", key, e)))
+    }
+
+    /// Forwards streamed logs to the workflow context until the agent sends its output.
+    ///
+    /// Logs are numbered in arrival order unless the agent numbers them itself.
+    /// Returns `None` if the stream ends without an output.
+    async fn relay_updates<S>(
+        ctx: &Context,
+        nid: &NodeId,
+        stream: &mut S,
+    ) -> Result<Option<pb::AgentOutput>>
+    where
+        S: Stream<Item = std::result::Result<pb::AgentUpdate, tonic::Status>> + Unpin,
+    {
+        let mut log_seq = 0;
+        while let Some(result) = stream.next().await {
+            let update = result.map_err(|e| ActflowError::Action(format!("Stream error: {}", e)))?;
+            match update.relay_message {
+                Some(RelayMessage::Log(log_content)) => {
+                    log_seq = if update.seq > 0 {
+                        update.seq
+                    } else {
+                        log_seq + 1
+                    };
+                    let phase = (!update.phase.is_empty()).then_some(update.phase);
+                    ctx.emit_sequenced_log(nid.clone(), log_content, log_seq, phase);
+                }
+                Some(RelayMessage::Output(output)) => return Ok(Some(output)),
+                None => {}
+            }
+        }
+        Ok(None)
+    }
+
     /// Map proto NodeExecutionStatus to workflow NodeExecutionStatus
     fn map_status(status: pb::NodeExecutionStatus) -> NodeExecutionStatus {
         match status {
@@ -45,8 +107,11 @@ impl AgentAction {
 #[typetag::serde]
 impl Action for AgentAction {
     fn create(params: serde_json::Value) -> Result<Self> {
-        jsonschema::validate(&params, &Self::schema())?;
+        jsonschema::validate(&Self::schema(), &params)?;
         let action = serde_json::from_value::<Self>(params)?;
+        for key in action.metadata.keys() {
+            Self::metadata_key(key)?;
+        }
         Ok(action)
     }
 
@@ -62,6 +127,11 @@ impl Action for AgentAction {
                 "inputs": {
                     "type": "object",
                     "description": "Inputs to the agent"
+                },
+                "metadata": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "gRPC metadata sent with the request, values may contain templates"
                 }
             }
         })
@@ -86,67 +156,38 @@ impl Action for AgentAction {
         let mut client = AgentServiceClient::new(channel);
 
         // Resolve template variables in inputs
-        let resolved_inputs = template::resolve_json_value(&ctx, &self.inputs)?;
+        let resolved_inputs = template::resolve_json_value_in(&ctx, &self.inputs, &nid, "inputs")?;
 
         // Build the request
-        let request = pb::RunRequest {
+        let mut request = tonic::Request::new(pb::RunRequest {
             pid: ctx.pid(),
             nid: nid.clone(),
             inputs: Some(json_to_prost_value(&resolved_inputs)),
-        };
+        });
+        self.apply_metadata(&ctx, &nid, request.metadata_mut())?;
 
         // Call the agent service (streaming response)
         let response = client.run(request).await.map_err(|e| ActflowError::Action(format!("gRPC call failed: {}", e)))?;
 
         let mut stream = response.into_inner();
 
-        let mut agent_output: Option<pb::AgentOutput> = None;
-
-        // Get shutdown future for cancellation
-        let shutdown = ctx.wait_shutdown();
-        tokio::pin!(shutdown);
-
-        loop {
-            tokio::select! {
-                // Check for shutdown signal
-                _ = &mut shutdown => {
-                    // Context shutdown - shutdown agent service
-                    let _ = client.shutdown(pb::Empty {}).await;
-                    return Ok(ActionOutput::stopped());
-                }
-                // Process next stream message
-                result = stream.next() => {
-                    match result {
-                        Some(Ok(update)) => {
-                            match update.relay_message {
-                                Some(RelayMessage::Log(log_content)) => {
-                                    // Forward log to workflow context
-                                    ctx.emit_log(nid.clone(), log_content);
-                                }
-                                Some(RelayMessage::Output(output)) => {
-                                    // Store the final output and break
-                                    agent_output = Some(output);
-                                    break;
-                                }
-                                None => {}
-                            }
-                        }
-                        Some(Err(e)) => {
-                            return Err(ActflowError::Action(format!("Stream error: {}", e)));
-                        }
-                        None => {
-                            // Stream ended without output
-                            break;
-                        }
-                    }
-                }
+        let agent_output = tokio::select! {
+            // Context shutdown - shutdown agent service
+            _ = ctx.wait_shutdown() => {
+                let _ = client.shutdown(pb::Empty {}).await;
+                return Ok(ActionOutput::stopped());
             }
-        }
+            result = Self::relay_updates(&ctx, &nid, &mut stream) => result?,
+        };
 
         // Process the final output
         match agent_output {
             Some(output) => {
                 let status = Self::map_status(output.status());
+                #[cfg(feature = "debug")]
+                if matches!(status, NodeExecutionStatus::Failed | NodeExecutionStatus::Exception) {
+                    ctx.emit_log(nid.clone(), format!("agent failed, context: {}", ctx.dump()));
+                }
                 let outputs: Vars = output.outputs.map(|v| prost_value_to_json(&v)).unwrap_or(serde_json::Value::Null).into();
 
                 let error = if output.error.is_empty() {
@@ -165,6 +206,7 @@ impl Action for AgentAction {
                     outputs,
                     error,
                     exception,
+                    retry_after: None,
                 })
             }
             None => Err(ActflowError::Action("Agent service did not return any output".to_string())),
@@ -226,3 +268,154 @@ fn prost_value_to_json(prost: &prost_types::Value) -> serde_json::Value {
         None => serde_json::Value::Null,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{pin::Pin, sync::mpsc, time::Duration};
+
+    use tonic::{
+        Request, Response, Status,
+        transport::{Server, server::TcpIncoming},
+    };
+
+    use super::*;
+    use crate::runtime::{Channel, ChannelEvent, ChannelOptions};
+    use pb::agent_service_server::{AgentService, AgentServiceServer};
+
+    /// Stub agent echoing the trace id it received as its output.
+    struct EchoMetadataAgent;
+
+    #[tonic::async_trait]
+    impl AgentService for EchoMetadataAgent {
+        type RunStream = Pin<Box<dyn Stream<Item = std::result::Result<pb::AgentUpdate, Status>> + Send>>;
+
+        async fn run(
+            &self,
+            request: Request<pb::RunRequest>,
+        ) -> std::result::Result<Response<Self::RunStream>, Status> {
+            let trace_id = request.metadata().get("x-trace-id").and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+            let output = pb::AgentOutput {
+                status: pb::NodeExecutionStatus::Succeeded as i32,
+                outputs: Some(json_to_prost_value(&serde_json::json!({ "trace_id": trace_id }))),
+                ..Default::default()
+            };
+            let update = pb::AgentUpdate {
+                relay_message: Some(RelayMessage::Output(output)),
+                ..Default::default()
+            };
+            Ok(Response::new(Box::pin(tokio_stream::iter(vec![Ok(update)]))))
+        }
+
+        async fn shutdown(
+            &self,
+            _request: Request<pb::Empty>,
+        ) -> std::result::Result<Response<pb::Empty>, Status> {
+            Ok(Response::new(pb::Empty {}))
+        }
+    }
+
+    /// Rejects calls that do not carry the expected bearer token.
+    fn check_auth(request: Request<()>) -> std::result::Result<Request<()>, Status> {
+        match request.metadata().get("authorization") {
+            Some(token) if token == "Bearer secret" => Ok(request),
+            _ => Err(Status::unauthenticated("missing token")),
+        }
+    }
+
+    #[test]
+    fn test_metadata_is_sent() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let channel = Arc::new(Channel::new(runtime.clone()));
+
+        let incoming = runtime.block_on(async { TcpIncoming::bind("127.0.0.1:0".parse().unwrap()) }).unwrap();
+        let endpoint = format!("http://{}", incoming.local_addr().unwrap());
+        runtime.spawn(Server::builder().add_service(AgentServiceServer::with_interceptor(EchoMetadataAgent, check_auth)).serve_with_incoming(incoming));
+
+        let ctx = Arc::new(Context::new("pid".to_string(), channel));
+        ctx.env().set("TOKEN".to_string(), "secret".to_string());
+        ctx.add_output("n1".to_string(), Vars::new().with("trace", "trace-42"));
+
+        let action = AgentAction::create(serde_json::json!({
+            "endpoint": endpoint,
+            "inputs": {},
+            "metadata": {
+                "authorization": "Bearer {{$TOKEN$}}",
+                "x-trace-id": "{{#n1.trace#}}"
+            }
+        }))
+        .unwrap();
+        let output = runtime.block_on(action.run(ctx.clone(), "agent".to_string())).unwrap();
+        assert_eq!(output.status, NodeExecutionStatus::Succeeded);
+        assert_eq!(output.outputs.get::<String>("trace_id"), Some("trace-42".to_string()));
+
+        // Without the token the interceptor rejects the call
+        let action = AgentAction::create(serde_json::json!({ "endpoint": endpoint, "inputs": {} })).unwrap();
+        let err = runtime.block_on(action.run(ctx.clone(), "agent".to_string())).unwrap_err();
+        assert!(err.to_string().contains("missing token"));
+    }
+
+    #[test]
+    fn test_invalid_metadata_key_rejected() {
+        let err = AgentAction::create(serde_json::json!({
+            "endpoint": "http://127.0.0.1:50051",
+            "inputs": {},
+            "metadata": { "bad key": "value" }
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("bad key"));
+    }
+
+    fn log_update(
+        content: &str,
+        seq: u64,
+        phase: &str,
+    ) -> std::result::Result<pb::AgentUpdate, tonic::Status> {
+        Ok(pb::AgentUpdate {
+            relay_message: Some(RelayMessage::Log(content.to_string())),
+            seq,
+            phase: phase.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_relay_updates_sequences_logs() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let channel = Arc::new(Channel::new(runtime.clone()));
+        channel.listen();
+
+        let (tx, rx) = mpsc::channel();
+        ChannelEvent::channel(channel.clone(), ChannelOptions::default()).on_log(move |log| {
+            let _ = tx.send((log.content.clone(), log.seq, log.phase.clone()));
+        });
+
+        // A stub agent: two unnumbered logs, one numbered by the agent, then the output
+        let mut stream = tokio_stream::iter(vec![
+            log_update("planning", 0, "plan"),
+            log_update("calling tool", 0, ""),
+            log_update("done", 7, "act"),
+            Ok(pb::AgentUpdate {
+                relay_message: Some(RelayMessage::Output(pb::AgentOutput {
+                    status: pb::NodeExecutionStatus::Succeeded as i32,
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }),
+            log_update("after output", 0, ""),
+        ]);
+
+        let ctx = Context::new("pid".to_string(), channel);
+        let output = runtime.block_on(AgentAction::relay_updates(&ctx, &"agent".to_string(), &mut stream)).unwrap();
+        assert_eq!(output.unwrap().status(), pb::NodeExecutionStatus::Succeeded);
+
+        let logs: Vec<_> = (0..3).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+        assert_eq!(
+            logs,
+            vec![
+                ("planning".to_string(), Some(1), Some("plan".to_string())),
+                ("calling tool".to_string(), Some(2), None),
+                ("done".to_string(), Some(7), Some("act".to_string())),
+            ]
+        );
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
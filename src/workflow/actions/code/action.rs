@@ -14,7 +14,11 @@ use crate::{
     },
 };
 
-use super::code_executor::{CodeLanguage, JavascriptExecutor, PythonExecutor};
+use super::code_executor::CodeLanguage;
+#[cfg(feature = "js")]
+use super::code_executor::JavascriptExecutor;
+#[cfg(feature = "python")]
+use super::code_executor::PythonExecutor;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Variable {
@@ -33,9 +37,15 @@ pub struct CodeAction {
 #[typetag::serde]
 impl Action for CodeAction {
     fn create(params: serde_json::Value) -> Result<Self> {
-        jsonschema::validate(&params, &Self::schema())?;
+        jsonschema::validate(&Self::schema(), &params)?;
         let action = serde_json::from_value::<Self>(params)?;
-        Ok(action)
+        match action.code_language {
+            #[cfg(not(feature = "js"))]
+            CodeLanguage::Javascript => Err(ActflowError::Node("javascript code support not compiled in".to_string())),
+            #[cfg(not(feature = "python"))]
+            CodeLanguage::Python3 => Err(ActflowError::Node("python code support not compiled in".to_string())),
+            _ => Ok(action),
+        }
     }
 
     fn schema() -> serde_json::Value {
@@ -81,21 +91,29 @@ impl Action for CodeAction {
     async fn run(
         &self,
         ctx: Arc<Context>,
-        _nid: NodeId,
+        nid: NodeId,
     ) -> Result<ActionOutput> {
         let mut params = Vars::new();
         for var in &self.variables {
             let value = template::resolve_template_to_values(&ctx, &var.value_selector)
-                .ok()
-                .and_then(|v| v.into_iter().next())
+                .map_err(|e| template::field_error(&nid, &format!("variables.{}", var.variable), e))?
+                .into_iter()
+                .next()
                 .ok_or_else(|| ActflowError::Runtime(format!("variable '{}' not found", var.variable)))?;
 
             params.set(&var.variable, value);
         }
 
-        let result = match self.code_language {
+        let result: serde_json::Value = match self.code_language {
+            #[cfg(feature = "python")]
             CodeLanguage::Python3 => PythonExecutor::execute(&self.code, params.into()),
+            #[cfg(feature = "js")]
             CodeLanguage::Javascript => JavascriptExecutor::execute(&self.code, params.into()),
+            // Rejected by `create`, but actions can also be deserialized directly
+            #[cfg(not(feature = "python"))]
+            CodeLanguage::Python3 => Err(ActflowError::Node("python code support not compiled in".to_string())),
+            #[cfg(not(feature = "js"))]
+            CodeLanguage::Javascript => Err(ActflowError::Node("javascript code support not compiled in".to_string())),
         }?;
         Ok(ActionOutput::success(result.into()))
     }
@@ -1,5 +1,7 @@
 use regex::Regex;
+#[cfg(feature = "js")]
 use rquickjs::{Context as JsContext, FromJs, Runtime as JsRuntime};
+#[cfg(feature = "python")]
 use rustpython_vm::{
     AsObject, Interpreter, PyObjectRef, VirtualMachine,
     builtins::{PyDict, PyFloat, PyInt, PyList, PyStr},
@@ -17,8 +19,14 @@ pub enum CodeLanguage {
     Javascript,
 }
 
+#[cfg(feature = "js")]
+/// Minimal `console` object so scripts that log don't fail in the bare QuickJS context
+const JS_CONSOLE_SHIM: &str = "globalThis.console = { log() {}, info() {}, warn() {}, error() {}, debug() {} };";
+
+#[cfg(feature = "js")]
 pub struct JavascriptExecutor;
 
+#[cfg(feature = "js")]
 impl JavascriptExecutor {
     /// Execute JavaScript code with parameters and return the result
     ///
@@ -39,6 +47,8 @@ impl JavascriptExecutor {
         let ctx = JsContext::full(&runtime).map_err(|e| ActflowError::Runtime(e.to_string()))?;
 
         ctx.with(|ctx| {
+            ctx.eval::<(), _>(JS_CONSOLE_SHIM).map_err(|e| ActflowError::Runtime(e.to_string()))?;
+
             // Evaluate the code to define the function
             if let Err(rquickjs::Error::Exception) = ctx.eval::<(), _>(code) {
                 let exception = rquickjs::Exception::from_js(&ctx, ctx.catch()).unwrap();
@@ -75,8 +85,10 @@ impl JavascriptExecutor {
     }
 }
 
+#[cfg(feature = "python")]
 pub struct PythonExecutor;
 
+#[cfg(feature = "python")]
 impl PythonExecutor {
     /// Execute Python code with parameters and return the result
     pub fn execute(
@@ -176,16 +188,16 @@ impl PythonExecutor {
             return Ok(Value::Bool(is_true));
         }
 
-        if let Some(i) = obj.payload::<PyInt>() {
-            if let Ok(n) = i.try_to_primitive::<i64>(vm) {
-                return Ok(Value::Number(n.into()));
-            }
+        if let Some(i) = obj.payload::<PyInt>()
+            && let Ok(n) = i.try_to_primitive::<i64>(vm)
+        {
+            return Ok(Value::Number(n.into()));
         }
 
-        if let Some(f) = obj.payload::<PyFloat>() {
-            if let Some(n) = serde_json::Number::from_f64(f.to_f64()) {
-                return Ok(Value::Number(n));
-            }
+        if let Some(f) = obj.payload::<PyFloat>()
+            && let Some(n) = serde_json::Number::from_f64(f.to_f64())
+        {
+            return Ok(Value::Number(n));
         }
 
         if let Some(s) = obj.payload::<PyStr>() {
@@ -218,8 +230,12 @@ impl PythonExecutor {
 mod test {
     use serde_json::json;
 
-    use super::{JavascriptExecutor, PythonExecutor};
+    #[cfg(feature = "js")]
+    use super::JavascriptExecutor;
+    #[cfg(feature = "python")]
+    use super::PythonExecutor;
 
+    #[cfg(feature = "js")]
     #[test]
     fn test_extract_function_name() {
         assert_eq!(
@@ -237,6 +253,7 @@ mod test {
         assert_eq!(JavascriptExecutor::extract_javascript_function_name("const x = 1;"), None);
     }
 
+    #[cfg(feature = "js")]
     #[test]
     fn test_execute_with_params() {
         let javascript_code = r#"
@@ -253,6 +270,7 @@ mod test {
         assert_eq!(result, json!({"result": 30}));
     }
 
+    #[cfg(feature = "js")]
     #[test]
     fn test_execute_string_concat() {
         let javascript_code = r#"
@@ -269,6 +287,7 @@ mod test {
         assert_eq!(result, json!({"message": "Hello, World!"}));
     }
 
+    #[cfg(feature = "js")]
     #[test]
     fn test_execute_with_console() {
         let javascript_code = r#"
@@ -285,6 +304,7 @@ mod test {
         assert_eq!(result, json!({"doubled": 10}));
     }
 
+    #[cfg(feature = "js")]
     #[test]
     fn test_execute_json_format() {
         // value is already a JS object, no need to JSON.parse
@@ -301,6 +321,7 @@ mod test {
         assert_eq!(result, json!({"status_code": 200, "message": "Hello World"}));
     }
 
+    #[cfg(feature = "js")]
     #[test]
     fn test_execute_custom_function_name() {
         // Function name is auto-detected from code
@@ -319,6 +340,7 @@ mod test {
     }
 
     // Python tests
+    #[cfg(feature = "python")]
     #[test]
     fn test_python_extract_function_name() {
         assert_eq!(
@@ -336,6 +358,7 @@ mod test {
         assert_eq!(PythonExecutor::extract_python_function_name("x = 1"), None);
     }
 
+    #[cfg(feature = "python")]
     #[test]
     fn test_python_execute_with_params() {
         let python_code = r#"
@@ -349,6 +372,7 @@ def main(params):
         assert_eq!(result, json!({"result": 30}));
     }
 
+    #[cfg(feature = "python")]
     #[test]
     fn test_python_execute_string_concat() {
         let python_code = r#"
@@ -362,6 +386,7 @@ def greet(params):
         assert_eq!(result, json!({"message": "Hello, World!"}));
     }
 
+    #[cfg(feature = "python")]
     #[test]
     fn test_python_execute_with_list() {
         let python_code = r#"
@@ -376,6 +401,7 @@ def process(params):
         assert_eq!(result, json!({"sum": 15, "count": 5}));
     }
 
+    #[cfg(feature = "python")]
     #[test]
     fn test_python_execute_with_bool() {
         let python_code = r#"
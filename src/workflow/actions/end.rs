@@ -2,29 +2,49 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{Map, Value, json};
 
 use crate::{
     Result,
     common::Vars,
     runtime::Context,
-    workflow::{actions::ActionType, node::NodeId},
+    workflow::{actions::ActionType, node::NodeId, template},
 };
 
 use super::{Action, ActionOutput};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct EndAction;
+/// Explicit terminal of a workflow.
+///
+/// Reaching an end node completes the workflow successfully, even if other
+/// branches are still running. Its `outputs` mapping declares the workflow
+/// result; values may contain templates, and a value that is a single
+/// reference like `{ "total": "{{#sum.value#}}" }` keeps the referenced type.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EndAction {
+    /// Result mapping, resolved when the node runs
+    #[serde(default)]
+    outputs: Map<String, Value>,
+}
 
 #[async_trait]
 #[typetag::serde]
 impl Action for EndAction {
-    fn create(_: serde_json::Value) -> Result<Self> {
-        Ok(EndAction)
+    fn create(params: serde_json::Value) -> Result<Self> {
+        jsonschema::validate(&Self::schema(), &params)?;
+        let action = serde_json::from_value::<Self>(params)?;
+        Ok(action)
     }
 
     fn schema() -> serde_json::Value {
-        json!({})
+        json!({
+            "type": "object",
+            "properties": {
+                "outputs": {
+                    "type": "object",
+                    "description": "Workflow result mapping, values may contain templates"
+                }
+            }
+        })
     }
 
     fn action_type(&self) -> ActionType {
@@ -33,9 +53,10 @@ impl Action for EndAction {
 
     async fn run(
         &self,
-        _: Arc<Context>,
-        _: NodeId,
+        ctx: Arc<Context>,
+        nid: NodeId,
     ) -> Result<ActionOutput> {
-        Ok(ActionOutput::success(Vars::new()))
+        let outputs = template::resolve_json_template(&ctx, &Value::Object(self.outputs.clone())).map_err(|e| template::field_error(&nid, "outputs", e))?;
+        Ok(ActionOutput::success(Vars::from(outputs)))
     }
 }
@@ -2,8 +2,13 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use base64::{Engine as _, engine::general_purpose::STANDARD};
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue};
+use hmac::{Hmac, Mac};
+use reqwest::{
+    Certificate, StatusCode,
+    header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue, InvalidHeaderValue, RETRY_AFTER},
+};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
 use crate::{
     ActflowError, Result,
@@ -32,6 +37,10 @@ pub struct HttpRequestAction {
     body: HttpBody,
     // http request timeout in milliseconds
     timeout: u64,
+    #[serde(default)]
+    tls: Option<TlsConfig>,
+    #[serde(default)]
+    signing: Option<SigningConfig>,
 }
 
 impl HttpRequestAction {
@@ -71,36 +80,99 @@ impl HttpRequestAction {
         Ok(())
     }
 
+    /// Build the HTTP client, trusting the configured CA certificate if any
+    fn build_client(
+        &self,
+        ctx: &Context,
+        nid: &str,
+    ) -> Result<reqwest::Client> {
+        let Some(ca_cert) = self.tls.as_ref().and_then(|tls| tls.ca_cert.as_ref()) else {
+            return Ok(reqwest::Client::new());
+        };
+
+        let pem = template::resolve_template_in(ctx, ca_cert, nid, "tls.ca_cert")?;
+        let certs =
+            Certificate::from_pem_bundle(pem.as_bytes()).map_err(|err| template::field_error(nid, "tls.ca_cert", ActflowError::Action(err.to_string())))?;
+        if certs.is_empty() {
+            return Err(template::field_error(
+                nid,
+                "tls.ca_cert",
+                ActflowError::Action("no PEM certificate found".to_string()),
+            ));
+        }
+
+        let mut builder = reqwest::Client::builder();
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+        builder.build().map_err(|err| ActflowError::Runtime(format!("Http error: {}", err)))
+    }
+
     fn build_request(
         &self,
         ctx: Arc<Context>,
+        nid: &str,
     ) -> Result<reqwest::RequestBuilder> {
-        // Resolve URL template
-        let resolved_url = template::resolve_template(&ctx, &self.url)?;
+        let defaults = ctx.http_defaults();
+
+        // Resolve URL template, prefixing relative URLs with the workflow's base URL
+        let mut resolved_url = template::resolve_template_in(&ctx, &self.url, nid, "url")?;
+        if let Some(base_url) = &defaults.base_url
+            && !resolved_url.contains("://")
+        {
+            let base_url = template::resolve_template_in(&ctx, base_url, nid, "http.base_url")?;
+            resolved_url = format!("{}/{}", base_url.trim_end_matches('/'), resolved_url.trim_start_matches('/'));
+        }
 
         let mut headers = HeaderMap::new();
         headers.insert(HeaderName::from_static("accept"), HeaderValue::from_static("*/*"));
 
+        // Workflow default headers, overridden below by auth and node headers
+        for (key, value) in &defaults.headers {
+            let resolved_value = template::resolve_template_in(&ctx, value, nid, &format!("http.headers.{}", key))?;
+            if is_omitted(value, &resolved_value) {
+                continue;
+            }
+            headers.insert(
+                key.parse::<HeaderName>().map_err(|err| ActflowError::Runtime(err.to_string()))?,
+                resolved_value.parse().map_err(|err: InvalidHeaderValue| ActflowError::Runtime(err.to_string()))?,
+            );
+        }
+
         // Apply authorization headers
         self.apply_auth_headers(&mut headers)?;
 
         for (key, value) in &self.headers {
             // Resolve header value template
-            let resolved_value = template::resolve_template(&ctx, value)?;
+            let resolved_value = template::resolve_template_in(&ctx, value, nid, &format!("headers.{}", key))?;
+            if is_omitted(value, &resolved_value) {
+                continue;
+            }
             headers.insert(
                 key.parse::<HeaderName>().map_err(|err| ActflowError::Runtime(err.to_string()))?,
                 resolved_value.parse().map_err(|err: InvalidHeaderValue| ActflowError::Runtime(err.to_string()))?,
             );
         }
 
+        // Describe the body unless the user set a content type explicitly
+        if self.body.data.is_some()
+            && !headers.contains_key(CONTENT_TYPE)
+            && let Some(mime_type) = self.body.content_type.mime_type()
+        {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(mime_type));
+        }
+
         let mut query = Vec::new();
         for (key, value) in &self.params {
             // Resolve query param value template
-            let resolved_value = template::resolve_template(&ctx, value)?;
+            let resolved_value = template::resolve_template_in(&ctx, value, nid, &format!("params.{}", key))?;
+            if is_omitted(value, &resolved_value) {
+                continue;
+            }
             query.push((key.clone(), resolved_value));
         }
 
-        let client = reqwest::Client::new();
+        let client = self.build_client(&ctx, nid)?;
 
         let mut request = client
             .request(
@@ -115,14 +187,14 @@ impl HttpRequestAction {
                 if let Some(text) = &self.body.data {
                     let data = text.as_str().ok_or(ActflowError::Action("content-type did not match the body content".to_string()))?;
                     // Resolve template in text body
-                    let resolved_data = template::resolve_template(&ctx, data)?;
+                    let resolved_data = template::resolve_template_in(&ctx, data, nid, "body")?;
                     request = request.body::<String>(resolved_data);
                 }
             }
             ContentType::Json => {
                 if let Some(json) = &self.body.data {
                     // Resolve templates in JSON body recursively
-                    let resolved_json = template::resolve_json_value(&ctx, json)?;
+                    let resolved_json = template::resolve_json_value_in(&ctx, json, nid, "body")?;
                     let body = serde_json::to_vec(&resolved_json)?;
                     request = request.body(body);
                 }
@@ -130,7 +202,7 @@ impl HttpRequestAction {
             ContentType::FormData | ContentType::UrlEncoded => {
                 if let Some(form) = &self.body.data {
                     // Resolve templates in form data
-                    let resolved_form = template::resolve_json_value(&ctx, form)?;
+                    let resolved_form = template::resolve_json_value_in(&ctx, form, nid, "body")?;
                     let data = resolved_form.as_object().ok_or(ActflowError::Action("content-type did not match the body content".to_string()))?;
                     request = request.form(data);
                 }
@@ -148,15 +220,83 @@ impl HttpRequestAction {
         // Set timeout
         request = request.timeout(Duration::from_millis(self.timeout));
 
-        Ok(request)
+        match &self.signing {
+            Some(signing) => self.sign_request(&ctx, nid, signing, request),
+            None => Ok(request),
+        }
+    }
+
+    /// Add an HMAC-SHA256 signature of the body bytes as sent to the request.
+    ///
+    /// JSON bodies are serialized compactly with sorted keys, so receivers can
+    /// recompute the signature from the parsed payload; a missing body is
+    /// signed as empty.
+    fn sign_request(
+        &self,
+        ctx: &Context,
+        nid: &str,
+        signing: &SigningConfig,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::RequestBuilder> {
+        let key = template::resolve_template_in(ctx, &signing.key, nid, "signing.key")?;
+        if key.is_empty() {
+            return Err(template::field_error(
+                nid,
+                "signing.key",
+                ActflowError::Action("signing key is empty".to_string()),
+            ));
+        }
+        let header_name: HeaderName = signing
+            .header
+            .parse()
+            .map_err(|err: reqwest::header::InvalidHeaderName| template::field_error(nid, "signing.header", ActflowError::Action(err.to_string())))?;
+
+        let (client, request) = request.build_split();
+        let mut request = request.map_err(|err| ActflowError::Runtime(format!("Http error: {}", err)))?;
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).map_err(|err| ActflowError::Action(err.to_string()))?;
+        mac.update(body);
+        let digest = mac.finalize().into_bytes();
+        let signature = match signing.encoding {
+            SignatureEncoding::Hex => digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>(),
+            SignatureEncoding::Base64 => STANDARD.encode(digest),
+        };
+
+        request.headers_mut().insert(
+            header_name,
+            format!("{}{}", signing.prefix, signature)
+                .parse()
+                .map_err(|err: InvalidHeaderValue| template::field_error(nid, "signing.prefix", ActflowError::Action(err.to_string())))?,
+        );
+        Ok(reqwest::RequestBuilder::from_parts(client, request))
+    }
+}
+
+/// Whether a header or param resolved empty because of a missing optional
+/// `{{#nodeId.key?#}}` reference, and should be left out of the request
+fn is_omitted(
+    template: &str,
+    resolved: &str,
+) -> bool {
+    resolved.is_empty() && template::has_optional_reference(template)
+}
+
+/// Parse a `Retry-After` header value, either delay-seconds or an HTTP-date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
     }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO))
 }
 
 #[async_trait]
 #[typetag::serde]
 impl Action for HttpRequestAction {
     fn create(params: serde_json::Value) -> Result<Self> {
-        jsonschema::validate(&params, &Self::schema())?;
+        jsonschema::validate(&Self::schema(), &params)?;
         let action = serde_json::from_value::<Self>(params)?;
         Ok(action)
     }
@@ -224,6 +364,34 @@ impl Action for HttpRequestAction {
                     "type": "integer",
                     "minimum": 0,
                     "description": "Request timeout in milliseconds"
+                },
+                "tls": {
+                    "type": ["object", "null"],
+                    "properties": {
+                        "ca_cert": {
+                            "type": ["string", "null"],
+                            "description": "PEM-encoded CA certificate to trust, supports template variables"
+                        }
+                    }
+                },
+                "signing": {
+                    "type": ["object", "null"],
+                    "required": ["key"],
+                    "properties": {
+                        "key": {
+                            "type": "string",
+                            "description": "HMAC-SHA256 signing key, supports template variables"
+                        },
+                        "header": {
+                            "type": "string",
+                            "description": "Header carrying the signature, defaults to X-Signature"
+                        },
+                        "prefix": {
+                            "type": "string",
+                            "description": "Text put before the signature, e.g. sha256="
+                        },
+                        "encoding": { "type": "string", "enum": ["hex", "base64"] }
+                    }
                 }
             }
         })
@@ -236,13 +404,20 @@ impl Action for HttpRequestAction {
     async fn run(
         &self,
         ctx: Arc<Context>,
-        _nid: NodeId,
+        nid: NodeId,
     ) -> Result<ActionOutput> {
         let mut outputs = Vars::new();
 
-        let request = self.build_request(ctx.clone())?;
+        let request = self.build_request(ctx.clone(), &nid)?;
         let res = request.send().await.map_err(|err| ActflowError::Runtime(format!("Http error: {}", err)))?;
 
+        // Fail with the server's suggested delay so the dispatcher can honor it when retrying
+        if matches!(res.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE)
+            && let Some(delay) = res.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok()).and_then(parse_retry_after)
+        {
+            return Ok(ActionOutput::failed(format!("Http status {}", res.status())).with_retry_after(delay));
+        }
+
         outputs.insert(STATUS_CODE_KEY.to_string(), res.status().as_u16().into());
 
         // Convert HeaderMap to a serializable HashMap
@@ -252,11 +427,456 @@ impl Action for HttpRequestAction {
             serde_json::to_value(headers_map).map_err(|err| ActflowError::Runtime(err.to_string()))?,
         );
 
-        outputs.insert(
-            BODY_KEY.to_string(),
-            res.text().await.map_err(|err| ActflowError::Runtime(err.to_string()))?.into(),
-        );
+        // HEAD responses never carry a body, even when headers describe one
+        if matches!(self.method, HttpRequestMethod::HEAD) {
+            outputs.insert(BODY_KEY.to_string(), serde_json::Value::Null);
+            return Ok(ActionOutput::success(outputs));
+        }
+
+        // JSON responses are stored structurally so templates can address fields inside the body
+        let is_json = match res.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).and_then(|v| v.split(';').next()) {
+            Some(mime) => ctx.json_content_types().matches(mime.trim()),
+            None => false,
+        };
+        let text = res.text().await.map_err(|err| ActflowError::Runtime(err.to_string()))?;
+        let body = if is_json && !text.is_empty() {
+            serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+        } else {
+            text.into()
+        };
+        outputs.insert(BODY_KEY.to_string(), body);
 
         Ok(ActionOutput::success(outputs))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::Arc,
+        thread,
+        time::Instant,
+    };
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        EngineBuilder, HttpDefaults, NodeExecutionStatus, NodeModel, WorkflowModel,
+        runtime::Channel,
+        test_support::{edge, start_and_wait, start_node},
+    };
+
+    fn create_test_context() -> Arc<Context> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let channel = Arc::new(Channel::new(Arc::new(runtime)));
+        Arc::new(Context::new("test-pid".to_string(), channel))
+    }
+
+    fn create_action(params: serde_json::Value) -> HttpRequestAction {
+        let mut action = json!({
+            "url": "http://localhost/",
+            "method": "GET",
+            "auth": { "auth_type": "no_auth" },
+            "headers": {},
+            "params": {},
+            "body": { "content_type": "none" },
+            "timeout": 1000
+        });
+        action.as_object_mut().unwrap().extend(params.as_object().unwrap().clone());
+        HttpRequestAction::create(action).unwrap()
+    }
+
+    #[test]
+    fn test_build_request_with_http_defaults() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let channel = Arc::new(Channel::new(Arc::new(runtime)));
+        let ctx = Arc::new(
+            Context::new("test-pid".to_string(), channel)
+                .with_http_defaults(HttpDefaults {
+                    base_url: Some("http://api.local/v1/".to_string()),
+                    headers: HashMap::from([("x-api-key".to_string(), "default-key".to_string()), ("x-tenant".to_string(), "acme".to_string())]),
+                    ..Default::default()
+                })
+                .unwrap(),
+        );
+
+        let action = create_action(json!({ "url": "/users", "headers": { "x-api-key": "node-key" } }));
+        let request = action.build_request(ctx.clone(), "n1").unwrap().build().unwrap();
+
+        assert_eq!(request.url().as_str(), "http://api.local/v1/users");
+        assert_eq!(request.headers()["x-api-key"], "node-key");
+        assert_eq!(request.headers()["x-tenant"], "acme");
+
+        // Absolute URLs are left untouched
+        let action = create_action(json!({ "url": "http://other.local/ping" }));
+        let request = action.build_request(ctx, "n1").unwrap().build().unwrap();
+        assert_eq!(request.url().as_str(), "http://other.local/ping");
+    }
+
+    #[test]
+    fn test_build_request_with_mapped_input() {
+        let ctx = create_test_context();
+        let mut inputs = Vars::new();
+        inputs.set("user_id", "42");
+        ctx.add_inputs("n2".to_string(), inputs);
+
+        let action = create_action(json!({ "url": "http://localhost/users/{{#n2.user_id#}}" }));
+        let request = action.build_request(ctx, "n2").unwrap().build().unwrap();
+
+        assert_eq!(request.url().as_str(), "http://localhost/users/42");
+    }
+
+    #[test]
+    fn test_build_request_content_type() {
+        let cases = [
+            ("json", json!({ "a": 1 }), "application/json"),
+            ("urlencoded", json!({ "a": "1" }), "application/x-www-form-urlencoded"),
+            ("form-data", json!({ "a": "1" }), "application/x-www-form-urlencoded"),
+            ("text", json!("hello"), "text/plain; charset=utf-8"),
+            ("html", json!("<p>hello</p>"), "text/html; charset=utf-8"),
+            ("binary", json!("aGVsbG8="), "application/octet-stream"),
+        ];
+        for (content_type, data, expected) in cases {
+            let action = create_action(json!({ "method": "POST", "body": { "content_type": content_type, "data": data } }));
+            let request = action.build_request(create_test_context(), "n1").unwrap().build().unwrap();
+            assert_eq!(request.headers()[CONTENT_TYPE], expected, "content type {}", content_type);
+        }
+
+        // An explicit header wins
+        let action = create_action(json!({
+            "method": "POST",
+            "headers": { "Content-Type": "application/vnd.api+json" },
+            "body": { "content_type": "json", "data": { "a": 1 } }
+        }));
+        let request = action.build_request(create_test_context(), "n1").unwrap().build().unwrap();
+        assert_eq!(request.headers()[CONTENT_TYPE], "application/vnd.api+json");
+
+        // No body, no content type
+        let request = create_action(json!({})).build_request(create_test_context(), "n1").unwrap().build().unwrap();
+        assert!(!request.headers().contains_key(CONTENT_TYPE));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(30)).format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let delay = parse_retry_after(&date).unwrap();
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn test_retry_honors_retry_after() {
+        // Answers 429 with `Retry-After: 1` once, then 200
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let mut arrivals = Vec::new();
+            for response in ["HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\n", "HTTP/1.1 200 OK\r\n"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                arrivals.push(Instant::now());
+                let _ = stream.read(&mut [0; 4096]).unwrap();
+                stream.write_all(format!("{}Content-Length: 2\r\nConnection: close\r\n\r\nok", response).as_bytes()).unwrap();
+            }
+            arrivals
+        });
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        let workflow = WorkflowModel {
+            id: "retry_after".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    id: "fetch".to_string(),
+                    uses: "http_request".to_string(),
+                    retry: Some(json!({ "times": 1, "interval": 0 })),
+                    action: json!({
+                        "url": format!("http://127.0.0.1:{}/", port),
+                        "method": "GET",
+                        "auth": { "auth_type": "no_auth" },
+                        "headers": {},
+                        "params": {},
+                        "body": { "content_type": "none" },
+                        "timeout": 5000
+                    }),
+                    ..Default::default()
+                },
+            ],
+            edges: vec![edge("e1", "start", "fetch")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        assert!(start_and_wait(&engine, &process).is_complete());
+
+        let arrivals = server.join().unwrap();
+        assert!(arrivals[1] - arrivals[0] >= Duration::from_millis(950));
+        assert_eq!(
+            process.get_outputs().get::<serde_json::Value>("fetch").unwrap()["status_code"],
+            200
+        );
+
+        engine.shutdown();
+    }
+
+    /// Self-signed CA certificate, base64-encoded as it would be stored in a workflow's env.
+    const TEST_CA_CERT_BASE64: &str = "LS0tLS1CRUdJTiBDRVJUSUZJQ0FURS0tLS0tCk1JSUJqRENDQVRHZ0F3SUJBZ0lVSnBjREw5Qm05eVBiOUJNbUFpajFyVDBGZGZNd0NnWUlLb1pJemowRUF3SXcKR2pFWU1CWUdBMVVFQXd3UFlXTjBabXh2ZHkxMFpYTjBMV05oTUNBWERUSTJNVEF4TnpJd01ERXdNVm9ZRHpJeApNall3T1RJek1qQXdNVEF4V2pBYU1SZ3dGZ1lEVlFRRERBOWhZM1JtYkc5M0xYUmxjM1F0WTJFd1dUQVRCZ2NxCmhrak9QUUlCQmdncWhrak9QUU1CQndOQ0FBUnZKK2hBUkpVQkQvQ3lvd1RBV2kwYk9BN1EvQm1tam9vc0NCYkwKVHdsYXM1UmI1NW5DZGVkeGpreTNMdVZOT2Q2UU5HNzJEZXVuczZzZm5RNVZjZFB5bzFNd1VUQWRCZ05WSFE0RQpGZ1FVc0s1T3V3SUNvM2RtcHhUbVBzSXJWL2NPWDRJd0h3WURWUjBqQkJnd0ZvQVVzSzVPdXdJQ28zZG1weFRtClBzSXJWL2NPWDRJd0R3WURWUjBUQVFIL0JBVXdBd0VCL3pBS0JnZ3Foa2pPUFFRREFnTkpBREJHQWlFQXE3dHQKNnh1NzBJY2JFWDFmU1J2ZDdRYllUaW9rcFVlMG53b2VPNmpsVCtrQ0lRQ1JoS1dMWWlBZzZ1S3c4aGZkeEVWUgpCdndQTld6TjZuamRrSHFzV0k1VlNBPT0KLS0tLS1FTkQgQ0VSVElGSUNBVEUtLS0tLQo=";
+
+    #[test]
+    fn test_build_request_with_ca_cert_from_env() {
+        let ctx = create_test_context();
+        ctx.env().set("CA_CERT".to_string(), TEST_CA_CERT_BASE64.to_string());
+
+        let action = create_action(json!({ "url": "https://localhost/", "tls": { "ca_cert": "{{ base64decode({{$CA_CERT$}}) }}" } }));
+        let request = action.build_request(ctx.clone(), "fetch").unwrap().build().unwrap();
+        assert_eq!(request.url().as_str(), "https://localhost/");
+
+        // Undecoded base64 is not a PEM certificate
+        let action = create_action(json!({ "tls": { "ca_cert": "{{$CA_CERT$}}" } }));
+        let err = action.build_request(ctx, "fetch").err().unwrap();
+        assert_eq!(err.to_string(), "node 'fetch' field 'tls.ca_cert': no PEM certificate found");
+    }
+
+    #[test]
+    fn test_build_request_with_signature() {
+        let ctx = create_test_context();
+        ctx.env().set("WEBHOOK_SECRET".to_string(), "whsec_test".to_string());
+
+        // Keys are sorted, so the signature does not depend on their declared order
+        let action = create_action(json!({
+            "method": "POST",
+            "body": { "content_type": "json", "data": { "id": 1, "event": "ping" } },
+            "signing": { "key": "{{$WEBHOOK_SECRET$}}", "prefix": "sha256=" }
+        }));
+        let request = action.build_request(ctx.clone(), "notify").unwrap().build().unwrap();
+        assert_eq!(request.body().unwrap().as_bytes().unwrap(), br#"{"event":"ping","id":1}"#);
+        assert_eq!(
+            request.headers()["X-Signature"],
+            "sha256=95d7c35a503f7cf99020000b96956f202f3aca8c078aadf342349a90912a0128"
+        );
+
+        let action = create_action(json!({
+            "method": "POST",
+            "body": { "content_type": "json", "data": { "event": "ping", "id": 1 } },
+            "signing": { "key": "{{$WEBHOOK_SECRET$}}", "header": "x-hub-signature", "encoding": "base64" }
+        }));
+        let request = action.build_request(ctx.clone(), "notify").unwrap().build().unwrap();
+        assert_eq!(
+            request.headers()["x-hub-signature"],
+            "ldfDWlA/fPmQIAALlpVvIC86yowHiq3zQjSakJEqASg="
+        );
+
+        // No body signs the empty payload
+        let action = create_action(json!({ "signing": { "key": "{{$WEBHOOK_SECRET$}}" } }));
+        let request = action.build_request(ctx.clone(), "notify").unwrap().build().unwrap();
+        assert_eq!(
+            request.headers()["X-Signature"],
+            "43c0f4d23c8e8841358fad4624b1a592799222b29f25bb59baea43cdcb522ed1"
+        );
+
+        let action = create_action(json!({ "signing": { "key": "{{$MISSING_SECRET$}}" } }));
+        assert!(action.build_request(ctx, "notify").is_err());
+    }
+
+    #[test]
+    fn test_build_request_omits_missing_optional_params() {
+        let ctx = create_test_context();
+        let mut vars = Vars::new();
+        vars.set("page", 2);
+        ctx.add_output("n1".to_string(), vars);
+
+        let action = create_action(json!({
+            "params": { "page": "{{#n1.page?#}}", "cursor": "{{#n1.cursor?#}}", "q": "" },
+            "headers": { "x-trace": "{{#n1.trace?#}}", "x-label": "page-{{#n1.label?#}}" }
+        }));
+        let request = action.build_request(ctx, "fetch").unwrap().build().unwrap();
+
+        let mut query: Vec<(String, String)> = request.url().query_pairs().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        query.sort();
+        assert_eq!(
+            query,
+            vec![("page".to_string(), "2".to_string()), ("q".to_string(), "".to_string())]
+        );
+        assert!(!request.headers().contains_key("x-trace"));
+        assert_eq!(request.headers()["x-label"], "page-");
+    }
+
+    #[test]
+    fn test_build_request_error_names_node_and_field() {
+        let action = create_action(json!({ "headers": { "x-user": "{{#n1.user#}}" } }));
+        let err = action.build_request(create_test_context(), "fetch").err().unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "node 'fetch' field 'headers.x-user': variable '{{#n1.user#}}' not found"
+        );
+    }
+
+    #[cfg(feature = "js")]
+    #[test]
+    fn test_output_transform_reshapes_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]).unwrap();
+            let body = r#"{"data":{"id":42,"name":"Alice"}}"#;
+            let _ = stream.write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .as_bytes(),
+            );
+        });
+
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        let workflow = WorkflowModel {
+            id: "output_transform".to_string(),
+            nodes: vec![
+                start_node("start"),
+                NodeModel {
+                    id: "fetch".to_string(),
+                    uses: "http_request".to_string(),
+                    output_transform: Some(json!({ "id": "{{#fetch.body.data.id#}}" })),
+                    action: json!({
+                        "url": format!("http://127.0.0.1:{}/", port),
+                        "method": "GET",
+                        "auth": { "auth_type": "no_auth" },
+                        "headers": {},
+                        "params": {},
+                        "body": { "content_type": "none" },
+                        "timeout": 5000
+                    }),
+                    ..Default::default()
+                },
+                NodeModel {
+                    id: "next".to_string(),
+                    uses: "code".to_string(),
+                    action: json!({
+                        "variables": [{ "variable": "fetched", "value_selector": "{{#fetch.id#}}" }],
+                        "code_language": "javascript",
+                        "code": "function main({ fetched }) { return { next_id: fetched + 1 } }"
+                    }),
+                    ..Default::default()
+                },
+            ],
+            edges: vec![edge("e1", "start", "fetch"), edge("e2", "fetch", "next")],
+            ..Default::default()
+        };
+        let process = engine.build_workflow_process(&workflow).unwrap();
+
+        assert!(start_and_wait(&engine, &process).is_complete());
+
+        let outputs = process.get_outputs();
+        assert_eq!(outputs.get::<serde_json::Value>("fetch"), Some(json!({ "id": 42 })));
+        assert_eq!(outputs.get::<serde_json::Value>("next"), Some(json!({ "next_id": 43 })));
+
+        engine.shutdown();
+    }
+
+    /// Answers a single request with `response`; the handle yields the request line received.
+    fn serve_once(response: &'static str) -> (u16, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or_default().to_string()
+        });
+        (port, server)
+    }
+
+    #[test]
+    fn test_head_request_has_no_body() {
+        // Headers describe a JSON body that a HEAD response never sends
+        let (port, server) = serve_once("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 42\r\nX-Total: 7\r\n\r\n");
+        let action = create_action(json!({ "url": format!("http://127.0.0.1:{}/", port), "method": "HEAD" }));
+
+        // Keep the context, and with it the channel's runtime, alive outside `block_on`
+        let ctx = create_test_context();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let output = runtime.block_on(action.run(ctx.clone(), "head".to_string())).unwrap();
+        assert!(server.join().unwrap().starts_with("HEAD "));
+        assert_eq!(output.status, NodeExecutionStatus::Succeeded);
+        assert_eq!(output.outputs.get::<u16>(STATUS_CODE_KEY), Some(200));
+        assert_eq!(
+            output.outputs.get::<HashMap<String, String>>(HEADERS_KEY).unwrap()["x-total"],
+            "7"
+        );
+        assert_eq!(output.outputs.get::<serde_json::Value>(BODY_KEY), Some(serde_json::Value::Null));
+    }
+
+    #[test]
+    fn test_configured_json_content_types() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let fetch = |response: &'static str, json_content_types: &[&str]| {
+            let (port, server) = serve_once(response);
+            let action = create_action(json!({ "url": format!("http://127.0.0.1:{}/", port) }));
+            let channel = Arc::new(Channel::new(Arc::new(tokio::runtime::Runtime::new().unwrap())));
+            let ctx = Arc::new(
+                Context::new("test-pid".to_string(), channel)
+                    .with_http_defaults(HttpDefaults {
+                        json_content_types: json_content_types.iter().map(|pattern| pattern.to_string()).collect(),
+                        ..Default::default()
+                    })
+                    .unwrap(),
+            );
+            // Keep the context, and with it the channel's runtime, alive outside `block_on`
+            let output = runtime.block_on(action.run(ctx.clone(), "fetch".to_string()));
+            server.join().unwrap();
+            output.unwrap().outputs.get::<serde_json::Value>(BODY_KEY).unwrap()
+        };
+
+        let vnd = "HTTP/1.1 200 OK\r\nContent-Type: application/vnd.api+json\r\nContent-Length: 17\r\nConnection: close\r\n\r\n{\"data\":{\"id\":1}}";
+        assert_eq!(fetch(vnd, &["^application/vnd\\.api\\+json$"]), json!({ "data": { "id": 1 } }));
+
+        let text_json = "HTTP/1.1 200 OK\r\nContent-Type: text/json; charset=utf-8\r\nContent-Length: 7\r\nConnection: close\r\n\r\n{\"a\":1}";
+        assert_eq!(fetch(text_json, &[]), json!("{\"a\":1}"));
+        assert_eq!(fetch(text_json, &["^text/json$"]), json!({ "a": 1 }));
+
+        // A bad pattern fails the build instead of the request
+        let engine = EngineBuilder::new().build().unwrap();
+        engine.launch();
+        let workflow = WorkflowModel {
+            nodes: vec![start_node("start")],
+            http: Some(HttpDefaults {
+                json_content_types: vec!["(".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let err = engine.build_workflow_process(&workflow).err().unwrap();
+        assert!(err.to_string().contains("invalid json_content_types pattern"), "{}", err);
+
+        engine.shutdown();
+    }
+
+    #[test]
+    fn test_options_request_empty_body() {
+        let (port, server) = serve_once("HTTP/1.1 204 No Content\r\nContent-Type: application/json\r\nAllow: GET, HEAD, OPTIONS\r\n\r\n");
+        let action = create_action(json!({ "url": format!("http://127.0.0.1:{}/", port), "method": "OPTIONS" }));
+
+        let ctx = create_test_context();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let output = runtime.block_on(action.run(ctx.clone(), "options".to_string())).unwrap();
+        assert!(server.join().unwrap().starts_with("OPTIONS "));
+        assert_eq!(output.outputs.get::<u16>(STATUS_CODE_KEY), Some(204));
+        assert_eq!(
+            output.outputs.get::<HashMap<String, String>>(HEADERS_KEY).unwrap()["allow"],
+            "GET, HEAD, OPTIONS"
+        );
+        assert_eq!(output.outputs.get::<String>(BODY_KEY), Some(String::new()));
+    }
+}
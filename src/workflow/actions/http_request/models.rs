@@ -16,6 +16,7 @@ pub enum ApiKeyType {
     Custom,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, strum::AsRefStr)]
 pub enum HttpRequestMethod {
     GET,
@@ -52,6 +53,22 @@ pub enum ContentType {
     Audio,
 }
 
+impl ContentType {
+    /// Default `Content-Type` header value for a body of this type.
+    ///
+    /// Form-data bodies are sent url-encoded, so they share that media type.
+    pub fn mime_type(&self) -> Option<&'static str> {
+        match self {
+            ContentType::None => None,
+            ContentType::Text => Some("text/plain; charset=utf-8"),
+            ContentType::Html => Some("text/html; charset=utf-8"),
+            ContentType::Json => Some("application/json"),
+            ContentType::UrlEncoded | ContentType::FormData => Some("application/x-www-form-urlencoded"),
+            ContentType::Binary | ContentType::Image | ContentType::Video | ContentType::Audio => Some("application/octet-stream"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthorizationConfig {
     pub auth_type: AuthorizationType,
@@ -64,3 +81,40 @@ pub struct HttpBody {
     pub content_type: ContentType,
     pub data: Option<JsonValue>,
 }
+
+/// TLS settings for a request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Extra PEM-encoded CA certificate(s) to trust, supports template variables
+    /// (e.g., `{{ base64decode({{$CA_CERT$}}) }}` for a base64 env value).
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+}
+
+/// Encoding of a request signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// HMAC-SHA256 signing of the request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningConfig {
+    /// Signing key, supports template variables (e.g., `{{$WEBHOOK_SECRET$}}`).
+    pub key: String,
+    /// Header carrying the signature.
+    #[serde(default = "default_signature_header")]
+    pub header: String,
+    /// Text put before the signature, e.g. `sha256=`.
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+}
+
+fn default_signature_header() -> String {
+    "X-Signature".to_string()
+}
@@ -3,9 +3,10 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use serde_json_path::JsonPath;
 
 use crate::{
-    Result,
+    ActflowError, Result,
     common::Vars,
     runtime::Context,
     workflow::{
@@ -26,7 +27,6 @@ pub struct IfElseAction {
 impl IfElseAction {
     /// Evaluate a single comparison
     fn evaluate_comparison(
-        &self,
         actual: &Option<Value>,
         operator: ComparisonOperator,
         expected: &Option<ConditionValue>,
@@ -54,14 +54,13 @@ impl IfElseAction {
                 let Some(actual_val) = actual else {
                     return false;
                 };
-                self.evaluate_with_value(actual_val, operator, expected)
+                Self::evaluate_with_value(actual_val, operator, expected)
             }
         }
     }
 
     /// Evaluate comparison operators that require a value
     fn evaluate_with_value(
-        &self,
         actual: &Value,
         operator: ComparisonOperator,
         expected: &Option<ConditionValue>,
@@ -72,27 +71,26 @@ impl IfElseAction {
         };
 
         match operator {
-            ComparisonOperator::Contains => self.eval_contains(actual, expected),
-            ComparisonOperator::NotContains => !self.eval_contains(actual, expected),
-            ComparisonOperator::StartWith => self.eval_starts_with(actual, expected),
-            ComparisonOperator::EndWith => self.eval_ends_with(actual, expected),
-            ComparisonOperator::Is => self.eval_is(actual, expected),
-            ComparisonOperator::IsNot => !self.eval_is(actual, expected),
-            ComparisonOperator::In => self.eval_in(actual, expected),
-            ComparisonOperator::NotIn => !self.eval_in(actual, expected),
-            ComparisonOperator::AllOf => self.eval_all_of(actual, expected),
-            ComparisonOperator::Eq => self.eval_eq(actual, expected),
-            ComparisonOperator::Ne => !self.eval_eq(actual, expected),
-            ComparisonOperator::Gt => self.eval_cmp(actual, expected, |a, b| a > b),
-            ComparisonOperator::Lt => self.eval_cmp(actual, expected, |a, b| a < b),
-            ComparisonOperator::Ge => self.eval_cmp(actual, expected, |a, b| a >= b),
-            ComparisonOperator::Le => self.eval_cmp(actual, expected, |a, b| a <= b),
+            ComparisonOperator::Contains => Self::eval_contains(actual, expected),
+            ComparisonOperator::NotContains => !Self::eval_contains(actual, expected),
+            ComparisonOperator::StartWith => Self::eval_starts_with(actual, expected),
+            ComparisonOperator::EndWith => Self::eval_ends_with(actual, expected),
+            ComparisonOperator::Is => Self::eval_is(actual, expected),
+            ComparisonOperator::IsNot => !Self::eval_is(actual, expected),
+            ComparisonOperator::In => Self::eval_in(actual, expected),
+            ComparisonOperator::NotIn => !Self::eval_in(actual, expected),
+            ComparisonOperator::AllOf => Self::eval_all_of(actual, expected),
+            ComparisonOperator::Eq => Self::eval_eq(actual, expected),
+            ComparisonOperator::Ne => !Self::eval_eq(actual, expected),
+            ComparisonOperator::Gt => Self::eval_cmp(actual, expected, |a, b| a > b),
+            ComparisonOperator::Lt => Self::eval_cmp(actual, expected, |a, b| a < b),
+            ComparisonOperator::Ge => Self::eval_cmp(actual, expected, |a, b| a >= b),
+            ComparisonOperator::Le => Self::eval_cmp(actual, expected, |a, b| a <= b),
             _ => false,
         }
     }
 
     fn eval_contains(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
@@ -104,7 +102,6 @@ impl IfElseAction {
     }
 
     fn eval_starts_with(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
@@ -115,7 +112,6 @@ impl IfElseAction {
     }
 
     fn eval_ends_with(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
@@ -126,7 +122,6 @@ impl IfElseAction {
     }
 
     fn eval_is(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
@@ -138,14 +133,17 @@ impl IfElseAction {
     }
 
     fn eval_in(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
         match expected {
             ConditionValue::List(list) => match actual {
                 Value::String(s) => list.contains(s),
-                Value::Number(n) => list.contains(&n.to_string()),
+                // Compare numerically where the entry parses as a number, so `1` matches "1.0"
+                Value::Number(n) => list.iter().any(|e| match (n.as_f64(), e.trim().parse::<f64>()) {
+                    (Some(a), Ok(e)) => a == e,
+                    _ => *e == n.to_string(),
+                }),
                 _ => false,
             },
             ConditionValue::Str(s) => match actual {
@@ -156,7 +154,6 @@ impl IfElseAction {
     }
 
     fn eval_all_of(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
@@ -167,7 +164,6 @@ impl IfElseAction {
     }
 
     fn eval_eq(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
     ) -> bool {
@@ -185,7 +181,6 @@ impl IfElseAction {
     }
 
     fn eval_cmp<F>(
-        &self,
         actual: &Value,
         expected: &ConditionValue,
         cmp: F,
@@ -205,9 +200,49 @@ impl IfElseAction {
         }
     }
 
-    /// Process conditions for a single case
-    fn process_conditions(
-        &self,
+    /// Resolves templates in the expected side of a condition, so values can be
+    /// compared against other nodes' outputs, e.g. `{{#b.count#}}`.
+    ///
+    /// A value that fails to resolve is treated as missing.
+    fn resolve_expected(
+        ctx: &Context,
+        expected: &Option<ConditionValue>,
+    ) -> Option<ConditionValue> {
+        let resolve = |s: &String| {
+            if s.contains("{{") {
+                template::resolve_template(ctx, s).ok()
+            } else {
+                Some(s.clone())
+            }
+        };
+
+        match expected.as_ref()? {
+            ConditionValue::Str(s) => resolve(s).map(ConditionValue::Str),
+            ConditionValue::List(list) => list.iter().map(resolve).collect::<Option<Vec<_>>>().map(ConditionValue::List),
+        }
+    }
+
+    /// Resolves a condition's `variable_selector`.
+    ///
+    /// A selector starting with `$` is a JSONPath expression evaluated against
+    /// the outputs of all nodes keyed by node id, e.g. `$.n1.items[0].status`;
+    /// the first match is used. Any other selector is a `{{#node.key#}}` template.
+    fn resolve_selector(
+        ctx: &Context,
+        selector: &str,
+    ) -> Option<Value> {
+        if !selector.starts_with('$') {
+            return template::resolve_template_to_values(ctx, selector).ok().and_then(|v| v.into_iter().next());
+        }
+
+        let path = JsonPath::parse(selector).ok()?;
+        let outputs: serde_json::Map<String, Value> = ctx.outputs().iter().map(|(nid, vars)| (nid.to_string(), vars.into())).collect();
+        path.query(&Value::Object(outputs)).first().cloned()
+    }
+
+    /// Process conditions for a single case, also used for conditions outside
+    /// of an `if_else` case, e.g. a poll's stop condition
+    pub(crate) fn process_conditions(
         ctx: &Context,
         conditions: &[Condition],
         logical_operator: LogicalOperator,
@@ -215,18 +250,17 @@ impl IfElseAction {
         let mut results = vec![];
 
         for condition in conditions {
-            let actual_value = template::resolve_template_to_values(ctx, &condition.variable_selector).ok().and_then(|v| v.into_iter().next());
-            let result = self.evaluate_comparison(&actual_value, condition.comparison_operator, &condition.value);
+            let actual_value = Self::resolve_selector(ctx, &condition.variable_selector);
+            let expected_value = Self::resolve_expected(ctx, &condition.value);
+            let result = Self::evaluate_comparison(&actual_value, condition.comparison_operator, &expected_value);
 
             results.push(result);
         }
 
-        let final_result = match logical_operator {
-            LogicalOperator::And => results.iter().all(|r| r.clone()),
-            LogicalOperator::Or => results.iter().any(|r| r.clone()),
-        };
-
-        final_result
+        match logical_operator {
+            LogicalOperator::And => results.iter().all(|r| *r),
+            LogicalOperator::Or => results.iter().any(|r| *r),
+        }
     }
 }
 
@@ -234,8 +268,22 @@ impl IfElseAction {
 #[typetag::serde]
 impl Action for IfElseAction {
     fn create(params: serde_json::Value) -> Result<Self> {
-        jsonschema::validate(&params, &Self::schema())?;
+        jsonschema::validate(&Self::schema(), &params)?;
         let action = serde_json::from_value::<Self>(params)?;
+
+        // Reject malformed JSONPath selectors up front rather than never matching
+        for (i, case) in action.cases.iter().enumerate() {
+            for (j, condition) in case.conditions.iter().enumerate() {
+                if condition.variable_selector.starts_with('$')
+                    && let Err(e) = JsonPath::parse(&condition.variable_selector)
+                {
+                    return Err(ActflowError::Validation {
+                        path: format!("/cases/{}/conditions/{}/variable_selector", i, j),
+                        message: format!("invalid JSONPath '{}': {}", condition.variable_selector, e),
+                    });
+                }
+            }
+        }
         Ok(action)
     }
 
@@ -306,7 +354,7 @@ impl Action for IfElseAction {
         let mut final_result = false;
 
         for case in &self.cases {
-            let case_result = self.process_conditions(&ctx, &case.conditions, case.logical_operator);
+            let case_result = Self::process_conditions(&ctx, &case.conditions, case.logical_operator);
 
             // Short-circuit: break if a case passes
             if case_result {
@@ -321,3 +369,164 @@ impl Action for IfElseAction {
         Ok(ActionOutput::success(outputs))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ActflowError, runtime::Channel};
+
+    fn list(items: &[&str]) -> ConditionValue {
+        ConditionValue::List(items.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_eval_in_numeric_list() {
+        assert!(IfElseAction::eval_in(&json!(1), &list(&["1", "2"])));
+        assert!(IfElseAction::eval_in(&json!(1.5), &list(&["1.50"])));
+        assert!(IfElseAction::eval_in(&json!(1.0), &list(&["1"])));
+        assert!(!IfElseAction::eval_in(&json!(3), &list(&["1", "2"])));
+    }
+
+    #[test]
+    fn test_eval_in_mixed_list() {
+        assert!(IfElseAction::eval_in(&json!(2), &list(&["a", "2"])));
+        assert!(IfElseAction::eval_in(&json!("a"), &list(&["a", "2"])));
+        assert!(!IfElseAction::eval_in(&json!(2), &list(&["a", "b"])));
+    }
+
+    #[test]
+    fn test_expected_value_template() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ctx = Context::new("pid".to_string(), Arc::new(Channel::new(Arc::new(runtime))));
+        ctx.add_output("a".to_string(), Vars::new().with("count", 3));
+        ctx.add_output("b".to_string(), Vars::new().with("count", 3));
+        ctx.add_output("c".to_string(), Vars::new().with("count", 5));
+
+        let condition = |operator: &str, value: serde_json::Value| -> Vec<Condition> {
+            vec![
+                serde_json::from_value(json!({
+                    "variable_selector": "{{#a.count#}}",
+                    "comparison_operator": operator,
+                    "value": value
+                }))
+                .unwrap(),
+            ]
+        };
+
+        assert!(IfElseAction::process_conditions(
+            &ctx,
+            &condition("eq", json!("{{#b.count#}}")),
+            LogicalOperator::And
+        ));
+        assert!(!IfElseAction::process_conditions(
+            &ctx,
+            &condition("eq", json!("{{#c.count#}}")),
+            LogicalOperator::And
+        ));
+        assert!(IfElseAction::process_conditions(
+            &ctx,
+            &condition("lt", json!("{{#c.count#}}")),
+            LogicalOperator::And
+        ));
+        assert!(IfElseAction::process_conditions(
+            &ctx,
+            &condition("in", json!(["{{#c.count#}}", "{{#b.count#}}"])),
+            LogicalOperator::And
+        ));
+        // An unresolvable reference never matches
+        assert!(!IfElseAction::process_conditions(
+            &ctx,
+            &condition("eq", json!("{{#missing.count#}}")),
+            LogicalOperator::And
+        ));
+    }
+
+    #[test]
+    fn test_json_path_selector() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ctx = Context::new("pid".to_string(), Arc::new(Channel::new(Arc::new(runtime))));
+        ctx.add_output(
+            "fetch".to_string(),
+            Vars::new().with("items", json!([{ "status": "shipped" }, { "status": "pending" }])),
+        );
+
+        let condition = |selector: &str, value: &str| -> Vec<Condition> {
+            vec![
+                serde_json::from_value(json!({
+                    "variable_selector": selector,
+                    "comparison_operator": "eq",
+                    "value": value
+                }))
+                .unwrap(),
+            ]
+        };
+
+        assert!(IfElseAction::process_conditions(
+            &ctx,
+            &condition("$.fetch.items[0].status", "shipped"),
+            LogicalOperator::And
+        ));
+        assert!(IfElseAction::process_conditions(
+            &ctx,
+            &condition("$['fetch'].items[-1].status", "pending"),
+            LogicalOperator::And
+        ));
+        assert!(!IfElseAction::process_conditions(
+            &ctx,
+            &condition("$.fetch.items[5].status", "shipped"),
+            LogicalOperator::And
+        ));
+        // The template form keeps working
+        assert!(IfElseAction::process_conditions(
+            &ctx,
+            &condition("{{#fetch.items.0.status#}}", "shipped"),
+            LogicalOperator::And
+        ));
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_json_path() {
+        let err = IfElseAction::create(json!({
+            "cases": [{
+                "case_id": "a",
+                "logical_operator": "and",
+                "conditions": [{ "variable_selector": "$.fetch.items[", "comparison_operator": "eq", "value": "x" }]
+            }]
+        }))
+        .err()
+        .unwrap();
+
+        match err {
+            ActflowError::Validation {
+                path,
+                message,
+            } => {
+                assert_eq!(path, "/cases/0/conditions/0/variable_selector");
+                assert!(message.contains("invalid JSONPath"), "{}", message);
+            }
+            e => panic!("expected a validation error, got {}", e),
+        }
+    }
+
+    #[test]
+    fn test_create_invalid_case_path() {
+        let err = IfElseAction::create(json!({
+            "cases": [
+                { "case_id": "a", "logical_operator": "and", "conditions": [] },
+                { "case_id": "b", "logical_operator": "xor", "conditions": [] }
+            ]
+        }))
+        .err()
+        .unwrap();
+
+        match err {
+            ActflowError::Validation {
+                path,
+                ..
+            } => assert_eq!(path, "/cases/1/logical_operator"),
+            e => panic!("expected a validation error, got {}", e),
+        }
+    }
+}
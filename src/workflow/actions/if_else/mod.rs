@@ -2,3 +2,4 @@ mod action;
 mod models;
 
 pub use action::IfElseAction;
+pub(crate) use models::{Condition, LogicalOperator};
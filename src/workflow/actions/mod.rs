@@ -1,11 +1,16 @@
 mod agent;
+#[cfg(any(feature = "js", feature = "python"))]
 mod code;
 mod end;
 mod http_request;
 mod if_else;
+mod poll;
+#[cfg(feature = "nats")]
+mod publish;
 mod start;
+mod weighted;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -18,11 +23,16 @@ use crate::{
 };
 
 pub use agent::AgentAction;
+#[cfg(any(feature = "js", feature = "python"))]
 pub use code::CodeAction;
 pub use end::EndAction;
 pub use http_request::HttpRequestAction;
 pub use if_else::IfElseAction;
+pub use poll::PollAction;
+#[cfg(feature = "nats")]
+pub use publish::PublishAction;
 pub use start::StartAction;
+pub use weighted::WeightedAction;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr, strum::EnumString)]
 #[serde(rename_all = "snake_case")]
@@ -35,7 +45,10 @@ pub enum ActionType {
     End,
     HttpRequest,
     IfElse,
+    Poll,
+    Publish,
     Start,
+    Weighted,
 }
 
 #[async_trait]
@@ -97,6 +110,8 @@ pub struct ActionOutput {
     pub error: Option<String>,
     /// action exception message
     pub exception: Option<String>,
+    /// suggested delay before retrying a failure, overriding the node's retry interval
+    pub retry_after: Option<Duration>,
 }
 
 impl ActionOutput {
@@ -107,6 +122,7 @@ impl ActionOutput {
             outputs,
             error: None,
             exception: None,
+            retry_after: None,
         }
     }
 
@@ -117,6 +133,7 @@ impl ActionOutput {
             outputs: Vars::new(),
             error: Some(error),
             exception: None,
+            retry_after: None,
         }
     }
 
@@ -127,9 +144,19 @@ impl ActionOutput {
             outputs: Vars::new(),
             error: None,
             exception: Some(message),
+            retry_after: None,
         }
     }
 
+    /// Suggest a delay before the failed action is retried
+    pub fn with_retry_after(
+        mut self,
+        delay: Duration,
+    ) -> Self {
+        self.retry_after = Some(delay);
+        self
+    }
+
     /// Create a stopped action output
     pub fn stopped() -> Self {
         Self {
@@ -137,6 +164,18 @@ impl ActionOutput {
             outputs: Vars::new(),
             error: None,
             exception: None,
+            retry_after: None,
+        }
+    }
+
+    /// Create a paused action output, completed later with the outputs given to `resume_node`
+    pub fn paused() -> Self {
+        Self {
+            status: NodeExecutionStatus::Paused,
+            outputs: Vars::new(),
+            error: None,
+            exception: None,
+            retry_after: None,
         }
     }
 }
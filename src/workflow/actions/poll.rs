@@ -0,0 +1,330 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    ActflowError, Result,
+    common::Vars,
+    runtime::Context,
+    workflow::{
+        actions::ActionType,
+        node::{NodeExecutionStatus, NodeId},
+    },
+};
+
+use super::{
+    Action, ActionOutput, HttpRequestAction,
+    if_else::{Condition, IfElseAction, LogicalOperator},
+};
+
+const ATTEMPTS_KEY: &str = "attempts";
+
+/// Condition ending a poll, evaluated against each response.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollUntil {
+    #[serde(default)]
+    pub logical_operator: LogicalOperator,
+    pub conditions: Vec<Condition>,
+}
+
+/// Repeats an HTTP request until its response meets a condition.
+///
+/// While the conditions are evaluated, the latest response is addressable
+/// through the node's own id, e.g. `{{#wait.body.status#}}`. The delay between
+/// polls starts at `interval` and is multiplied by `backoff` after each one, up
+/// to `max_interval`. Polling fails once `max_attempts` requests were made or
+/// `timeout` milliseconds have passed, and stops when the process shuts down.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollAction {
+    request: HttpRequestAction,
+    until: PollUntil,
+    // delay between polls in milliseconds
+    interval: u64,
+    #[serde(default = "default_backoff")]
+    backoff: f64,
+    #[serde(default)]
+    max_interval: Option<u64>,
+    #[serde(default)]
+    max_attempts: Option<u64>,
+    // overall polling timeout in milliseconds
+    #[serde(default)]
+    timeout: Option<u64>,
+}
+
+fn default_backoff() -> f64 {
+    1.0
+}
+
+impl PollAction {
+    /// Returns the delay before the poll following `attempt` (1-based).
+    fn delay(
+        &self,
+        attempt: u64,
+    ) -> Duration {
+        let delay = self.interval as f64 * self.backoff.powi(attempt.saturating_sub(1).min(i32::MAX as u64) as i32);
+        let delay = match self.max_interval {
+            Some(max_interval) => delay.min(max_interval as f64),
+            None => delay,
+        };
+        Duration::from_millis(delay as u64)
+    }
+
+    /// Whether `response` meets the `until` conditions.
+    fn is_done(
+        &self,
+        ctx: &Context,
+        nid: &NodeId,
+        response: &Vars,
+    ) -> bool {
+        // Expose the response through the node's id without touching the shared inputs
+        let view = ctx.fork();
+        view.add_inputs(nid.clone(), response.clone());
+        IfElseAction::process_conditions(&view, &self.until.conditions, self.until.logical_operator)
+    }
+}
+
+#[async_trait]
+#[typetag::serde]
+impl Action for PollAction {
+    fn create(params: serde_json::Value) -> Result<Self> {
+        jsonschema::validate(&Self::schema(), &params)?;
+        let action = serde_json::from_value::<Self>(params)?;
+        if !action.backoff.is_finite() || action.backoff < 1.0 {
+            return Err(ActflowError::Action(format!(
+                "invalid backoff {}: must be at least 1",
+                action.backoff
+            )));
+        }
+        Ok(action)
+    }
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["request", "until", "interval"],
+            "properties": {
+                "request": HttpRequestAction::schema(),
+                "until": {
+                    "type": "object",
+                    "required": ["conditions"],
+                    "properties": {
+                        "logical_operator": { "type": "string", "enum": ["and", "or"] },
+                        "conditions": {
+                            "type": "array",
+                            "minItems": 1,
+                            "items": {
+                                "type": "object",
+                                "required": ["variable_selector", "comparison_operator"],
+                                "properties": {
+                                    "variable_selector": { "type": "string" },
+                                    "comparison_operator": { "type": "string" },
+                                    "value": {}
+                                }
+                            }
+                        }
+                    },
+                    "description": "Conditions ending the poll, in the form of if_else conditions"
+                },
+                "interval": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Delay between polls in milliseconds"
+                },
+                "backoff": {
+                    "type": "number",
+                    "minimum": 1,
+                    "description": "Factor applied to the delay after each poll"
+                },
+                "max_interval": {
+                    "type": ["integer", "null"],
+                    "minimum": 0,
+                    "description": "Upper bound of the delay between polls in milliseconds"
+                },
+                "max_attempts": {
+                    "type": ["integer", "null"],
+                    "minimum": 1,
+                    "description": "Number of requests after which polling fails"
+                },
+                "timeout": {
+                    "type": ["integer", "null"],
+                    "minimum": 0,
+                    "description": "Overall polling timeout in milliseconds"
+                }
+            }
+        })
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::Poll
+    }
+
+    async fn run(
+        &self,
+        ctx: Arc<Context>,
+        nid: NodeId,
+    ) -> Result<ActionOutput> {
+        let clock = ctx.clock();
+        let deadline = self.timeout.map(|timeout| clock.now_millis() + timeout as i64);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let output = self.request.run(ctx.clone(), nid.clone()).await?;
+            if output.status != NodeExecutionStatus::Succeeded {
+                return Ok(output);
+            }
+
+            let mut outputs = output.outputs;
+            if self.is_done(&ctx, &nid, &outputs) {
+                outputs.insert(ATTEMPTS_KEY.to_string(), attempt.into());
+                return Ok(ActionOutput::success(outputs));
+            }
+
+            if self.max_attempts.is_some_and(|max_attempts| attempt >= max_attempts) {
+                return Ok(ActionOutput::failed(format!(
+                    "poll condition not met after {} attempts",
+                    attempt
+                )));
+            }
+            let delay = self.delay(attempt);
+            if deadline.is_some_and(|deadline| clock.now_millis() + delay.as_millis() as i64 > deadline) {
+                return Ok(ActionOutput::failed(format!(
+                    "poll condition not met within {}ms",
+                    self.timeout.unwrap_or_default()
+                )));
+            }
+
+            tokio::select! {
+                _ = ctx.wait_shutdown() => return Ok(ActionOutput::stopped()),
+                _ = clock.sleep(delay) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+    use crate::runtime::Channel;
+
+    /// Answers one request per body with a JSON response; the handle yields the number of requests served.
+    fn serve(bodies: Vec<&'static str>) -> (u16, thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            for body in &bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 4096]).unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            bodies.len()
+        });
+        (port, server)
+    }
+
+    fn create_action(
+        port: u16,
+        params: serde_json::Value,
+    ) -> PollAction {
+        let mut action = json!({
+            "request": {
+                "url": format!("http://127.0.0.1:{}/jobs/1", port),
+                "method": "GET",
+                "auth": { "auth_type": "no_auth" },
+                "headers": {},
+                "params": {},
+                "body": { "content_type": "none" },
+                "timeout": 5000
+            },
+            "until": {
+                "conditions": [{ "variable_selector": "{{#wait.body.status#}}", "comparison_operator": "is", "value": "done" }]
+            },
+            "interval": 10
+        });
+        action.as_object_mut().unwrap().extend(params.as_object().unwrap().clone());
+        PollAction::create(action).unwrap()
+    }
+
+    fn run(action: &PollAction) -> ActionOutput {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let channel = Arc::new(Channel::new(Arc::new(tokio::runtime::Runtime::new().unwrap())));
+        // Keep the context, and with it the channel's runtime, alive outside `block_on`
+        let ctx = Arc::new(Context::new("test-pid".to_string(), channel));
+        let output = runtime.block_on(action.run(ctx.clone(), "wait".to_string())).unwrap();
+        assert!(ctx.inputs().get(&"wait".to_string()).is_none());
+        output
+    }
+
+    #[test]
+    fn test_poll_until_done() {
+        let (port, server) = serve(vec![
+            r#"{"status":"pending"}"#,
+            r#"{"status":"pending"}"#,
+            r#"{"status":"done","result":7}"#,
+        ]);
+        let action = create_action(port, json!({ "max_attempts": 5 }));
+
+        let output = run(&action);
+        assert_eq!(server.join().unwrap(), 3);
+        assert_eq!(output.status, NodeExecutionStatus::Succeeded);
+        assert_eq!(output.outputs.get::<u64>(ATTEMPTS_KEY), Some(3));
+        assert_eq!(
+            output.outputs.get::<serde_json::Value>("body"),
+            Some(json!({ "status": "done", "result": 7 }))
+        );
+    }
+
+    #[test]
+    fn test_poll_gives_up_after_max_attempts() {
+        let (port, server) = serve(vec![r#"{"status":"pending"}"#, r#"{"status":"pending"}"#]);
+        let action = create_action(port, json!({ "max_attempts": 2 }));
+
+        let output = run(&action);
+        assert_eq!(server.join().unwrap(), 2);
+        assert_eq!(output.status, NodeExecutionStatus::Failed);
+        assert_eq!(output.error.as_deref(), Some("poll condition not met after 2 attempts"));
+    }
+
+    #[test]
+    fn test_poll_stops_on_shutdown() {
+        let (port, server) = serve(vec![r#"{"status":"pending"}"#]);
+        let action = create_action(port, json!({ "interval": 60000 }));
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let channel = Arc::new(Channel::new(Arc::new(tokio::runtime::Runtime::new().unwrap())));
+        let ctx = Arc::new(Context::new("test-pid".to_string(), channel));
+        let output = runtime.block_on(async {
+            let poll = action.run(ctx.clone(), "wait".to_string());
+            let shutdown = async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                ctx.done();
+            };
+            let (output, _) = tokio::join!(poll, shutdown);
+            output.unwrap()
+        });
+        assert_eq!(server.join().unwrap(), 1);
+        assert_eq!(output.status, NodeExecutionStatus::Stopped);
+    }
+
+    #[test]
+    fn test_delay_backoff() {
+        let action = create_action(0, json!({ "interval": 100, "backoff": 2.0, "max_interval": 500 }));
+
+        let delays: Vec<u64> = (1..=5).map(|attempt| action.delay(attempt).as_millis() as u64).collect();
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+        assert!(PollAction::create(json!({ "request": {}, "until": { "conditions": [] }, "interval": 10 })).is_err());
+    }
+}
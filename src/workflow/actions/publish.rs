@@ -0,0 +1,195 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    ActflowError, Result,
+    common::Vars,
+    runtime::Context,
+    workflow::{actions::ActionType, node::NodeId, template},
+};
+
+use super::{Action, ActionOutput};
+
+/// Default connection timeout in milliseconds.
+const DEFAULT_TIMEOUT: u64 = 5000;
+
+/// Publishes a message to a NATS subject.
+///
+/// `subject` and `payload` may contain templates. A string payload is sent
+/// as-is after resolution; any other JSON value is resolved field by field
+/// and sent serialized. The node succeeds once the server has acknowledged
+/// the message with a flush, and fails on connection or publish errors.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishAction {
+    /// Server URL, e.g. `nats://127.0.0.1:4222`
+    url: String,
+    /// Subject the message is published to
+    subject: String,
+    /// Message body
+    #[serde(default)]
+    payload: Value,
+    /// Connection timeout in milliseconds
+    #[serde(default)]
+    timeout: Option<u64>,
+}
+
+impl PublishAction {
+    /// Resolves the payload into the bytes to send.
+    fn resolve_payload(
+        &self,
+        ctx: &Context,
+        nid: &str,
+    ) -> Result<Vec<u8>> {
+        match &self.payload {
+            Value::String(s) => Ok(template::resolve_template(ctx, s).map_err(|e| template::field_error(nid, "payload", e))?.into_bytes()),
+            value => {
+                let resolved = template::resolve_json_template(ctx, value).map_err(|e| template::field_error(nid, "payload", e))?;
+                Ok(serde_json::to_vec(&resolved)?)
+            }
+        }
+    }
+}
+
+#[async_trait]
+#[typetag::serde]
+impl Action for PublishAction {
+    fn create(params: serde_json::Value) -> Result<Self> {
+        jsonschema::validate(&Self::schema(), &params)?;
+        let action = serde_json::from_value::<Self>(params)?;
+        Ok(action)
+    }
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "description": "NATS server URL" },
+                "subject": { "type": "string", "description": "Subject to publish to, may contain templates" },
+                "payload": { "description": "Message body, may contain templates" },
+                "timeout": { "type": "integer", "minimum": 0, "description": "Connection timeout in milliseconds" }
+            },
+            "required": ["url", "subject"]
+        })
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::Publish
+    }
+
+    async fn run(
+        &self,
+        ctx: Arc<Context>,
+        nid: NodeId,
+    ) -> Result<ActionOutput> {
+        let url = template::resolve_template(&ctx, &self.url).map_err(|e| template::field_error(&nid, "url", e))?;
+        let subject = template::resolve_template(&ctx, &self.subject).map_err(|e| template::field_error(&nid, "subject", e))?;
+        let payload = self.resolve_payload(&ctx, &nid)?;
+        let bytes = payload.len();
+
+        let client = async_nats::ConnectOptions::new()
+            .connection_timeout(Duration::from_millis(self.timeout.unwrap_or(DEFAULT_TIMEOUT)))
+            .connect(url.as_str())
+            .await
+            .map_err(|err| ActflowError::Runtime(format!("Nats error: {}", err)))?;
+        client.publish(subject.clone(), payload.into()).await.map_err(|err| ActflowError::Runtime(format!("Nats error: {}", err)))?;
+        // The server answering the flush confirms it received the message
+        client.flush().await.map_err(|err| ActflowError::Runtime(format!("Nats error: {}", err)))?;
+
+        Ok(ActionOutput::success(Vars::new().with("subject", subject).with("bytes", bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::runtime::Channel;
+
+    /// Minimal NATS server accepting one client and forwarding each published (subject, payload).
+    async fn mock_server() -> (u16, mpsc::UnboundedReceiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+            writer.write_all(b"INFO {\"server_id\":\"mock\",\"version\":\"2.10.0\",\"proto\":1,\"max_payload\":1048576,\"headers\":true}\r\n").await.unwrap();
+
+            let mut line = String::new();
+            while reader.read_line(&mut line).await.unwrap_or(0) > 0 {
+                let mut parts = line.split_whitespace();
+                match parts.next() {
+                    Some("PING") => writer.write_all(b"PONG\r\n").await.unwrap(),
+                    Some("PUB") => {
+                        let subject = parts.next().unwrap().to_string();
+                        let len: usize = parts.last().unwrap().parse().unwrap();
+                        let mut payload = vec![0; len + 2];
+                        reader.read_exact(&mut payload).await.unwrap();
+                        payload.truncate(len);
+                        let _ = tx.send((subject, String::from_utf8(payload).unwrap()));
+                    }
+                    _ => {}
+                }
+                line.clear();
+            }
+        });
+        (port, rx)
+    }
+
+    #[test]
+    fn test_publish_resolves_subject_and_payload() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ctx = Arc::new(Context::new(
+            "pid".to_string(),
+            Arc::new(Channel::new(Arc::new(tokio::runtime::Runtime::new().unwrap()))),
+        ));
+        ctx.add_output("order".to_string(), Vars::new().with("id", 42).with("region", "eu"));
+
+        let (port, mut published) = runtime.block_on(mock_server());
+        let action = PublishAction::create(json!({
+            "url": format!("nats://127.0.0.1:{}", port),
+            "subject": "orders.{{#order.region#}}.created",
+            "payload": { "id": "{{#order.id#}}" }
+        }))
+        .unwrap();
+
+        let output = runtime.block_on(action.run(ctx.clone(), "publish".to_string())).unwrap();
+        assert_eq!(output.outputs.get::<String>("subject").unwrap(), "orders.eu.created");
+        assert_eq!(output.outputs.get::<usize>("bytes"), Some(9));
+
+        let (subject, payload) = runtime.block_on(published.recv()).unwrap();
+        assert_eq!(subject, "orders.eu.created");
+        assert_eq!(payload, r#"{"id":42}"#);
+    }
+
+    #[test]
+    fn test_publish_fails_without_broker() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ctx = Arc::new(Context::new(
+            "pid".to_string(),
+            Arc::new(Channel::new(Arc::new(tokio::runtime::Runtime::new().unwrap()))),
+        ));
+        // Reserve a port nobody listens on
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let action = PublishAction::create(json!({
+            "url": format!("nats://127.0.0.1:{}", port),
+            "subject": "orders",
+            "payload": "hello",
+            "timeout": 500
+        }))
+        .unwrap();
+
+        let err = runtime.block_on(action.run(ctx.clone(), "publish".to_string())).err().unwrap();
+        assert!(err.to_string().contains("Nats error"), "{}", err);
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rand::{SeedableRng, distributions::WeightedIndex, prelude::Distribution, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    ActflowError, Result,
+    common::Vars,
+    runtime::Context,
+    workflow::{actions::ActionType, consts::WEIGHTED_SELECTED, node::NodeId},
+};
+
+use super::{Action, ActionOutput};
+
+/// A branch of a weighted node, taken through the edges whose `source_handle` is its id.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeightedBranch {
+    /// Branch id, matched against outgoing edges' `source_handle`
+    pub id: String,
+    /// Relative weight; a branch with weight 0 is never selected
+    pub weight: f64,
+}
+
+/// Picks one outgoing branch at random, in proportion to the branch weights.
+///
+/// Useful for A/B testing and canary routing. Unselected branches are skipped
+/// like those of an `if_else` node. With a `seed`, every run selects the same branch.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeightedAction {
+    branches: Vec<WeightedBranch>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+impl WeightedAction {
+    /// Selects a branch id using `rng`.
+    fn select(
+        &self,
+        rng: &mut impl rand::Rng,
+    ) -> &str {
+        // Weights are validated on creation
+        let index = WeightedIndex::new(self.branches.iter().map(|branch| branch.weight)).unwrap();
+        &self.branches[index.sample(rng)].id
+    }
+}
+
+#[async_trait]
+#[typetag::serde]
+impl Action for WeightedAction {
+    fn create(params: serde_json::Value) -> Result<Self> {
+        jsonschema::validate(&Self::schema(), &params)?;
+        let action = serde_json::from_value::<Self>(params)?;
+        WeightedIndex::new(action.branches.iter().map(|branch| branch.weight)).map_err(|e| ActflowError::Action(format!("invalid branch weights: {}", e)))?;
+        Ok(action)
+    }
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "branches": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string" },
+                            "weight": { "type": "number", "minimum": 0 }
+                        },
+                        "required": ["id", "weight"]
+                    }
+                },
+                "seed": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["branches"]
+        })
+    }
+
+    fn action_type(&self) -> ActionType {
+        ActionType::Weighted
+    }
+
+    async fn run(
+        &self,
+        _ctx: Arc<Context>,
+        _nid: NodeId,
+    ) -> Result<ActionOutput> {
+        let selected = match self.seed {
+            Some(seed) => self.select(&mut StdRng::seed_from_u64(seed)),
+            None => self.select(&mut rand::thread_rng()),
+        };
+        Ok(ActionOutput::success(Vars::new().with(WEIGHTED_SELECTED, selected)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Channel;
+
+    #[test]
+    fn test_zero_weight_is_never_selected() {
+        let action = WeightedAction::create(json!({
+            "branches": [{ "id": "a", "weight": 0 }, { "id": "b", "weight": 100 }]
+        }))
+        .unwrap();
+
+        let mut rng = rand::thread_rng();
+        assert!((0..100).all(|_| action.select(&mut rng) == "b"));
+    }
+
+    #[test]
+    fn test_seeded_selection_is_deterministic() {
+        let runtime = Arc::new(tokio::runtime::Runtime::new().unwrap());
+        let ctx = Arc::new(Context::new("pid".to_string(), Arc::new(Channel::new(runtime.clone()))));
+        let action = WeightedAction::create(json!({
+            "branches": [{ "id": "a", "weight": 1 }, { "id": "b", "weight": 1 }, { "id": "c", "weight": 1 }],
+            "seed": 42
+        }))
+        .unwrap();
+
+        let selected = |action: &WeightedAction| {
+            let output = runtime.block_on(action.run(ctx.clone(), "w".to_string())).unwrap();
+            output.outputs.get::<String>(WEIGHTED_SELECTED).unwrap()
+        };
+        let first = selected(&action);
+        assert!((0..20).all(|_| selected(&action) == first));
+    }
+
+    #[test]
+    fn test_create_rejects_all_zero_weights() {
+        let err = WeightedAction::create(json!({
+            "branches": [{ "id": "a", "weight": 0 }, { "id": "b", "weight": 0 }]
+        }))
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("invalid branch weights"), "{}", err);
+    }
+}
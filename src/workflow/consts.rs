@@ -3,3 +3,17 @@ pub const IF_ELSE_RESULT: &str = "result";
 pub const IF_ELSE_TRUE: &str = "true";
 pub const IF_ELSE_FALSE: &str = "false";
 pub const IF_ELSE_SELECTED: &str = "selected_case_id";
+
+// weighted constants
+pub const WEIGHTED_SELECTED: &str = "selected_branch";
+
+// error strategy constants
+pub const FAIL_BRANCH_ERROR: &str = "error";
+
+// fan-out constants
+pub const FAN_OUT_ITEM: &str = "item";
+pub const FAN_OUT_INDEX: &str = "index";
+pub const FAN_OUT_RESULTS: &str = "results";
+
+// join constants
+pub const INCOMING_NAMESPACE: &str = "__incoming__";
@@ -28,17 +28,20 @@ pub enum FixedHandle {
     False,
     /// Error/failure branch for error handling.
     FailBranch,
+    /// Catch-all branch of a conditional node, taken instead of `false`
+    /// when no case matched.
+    Default,
 }
 
 /// Source handle identifying which output port of a node an edge originates from.
 ///
 /// Edges can originate from:
-/// - Fixed handles (source, true, false, fail_branch)
+/// - Fixed handles (source, true, false, fail_branch, default)
 /// - Dynamic handles (case IDs from if_else nodes)
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum SourceHandle {
-    /// Fixed handle types: source, true, false, fail_branch.
+    /// Fixed handle types: source, true, false, fail_branch, default.
     Fixed(FixedHandle),
     /// Dynamic handle: node ID or case ID (e.g., from if-else conditions).
     Node(NodeId),
@@ -61,6 +64,9 @@ pub struct Edge {
     pub target: NodeId,
     /// Which output handle this edge connects from.
     pub source_handle: SourceHandle,
+    /// Template selecting an array to fan the target node out over.
+    #[serde(default)]
+    pub fan_out_selector: Option<String>,
     /// Current execution state of this edge.
     #[serde(default)]
     pub status: NodeState,
@@ -0,0 +1,87 @@
+//! Functions callable from templates.
+//!
+//! Templates can call registered functions with the `{{ name(arg1, arg2) }}`
+//! syntax. Arguments are split on commas and trimmed; surrounding quotes are
+//! removed. Other template variables inside the arguments are resolved first,
+//! e.g. `{{ upper({{#n1.name#}}) }}`; function calls cannot be nested.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+use crate::{ActflowError, Result, ShareLock, utils};
+
+/// A function callable from templates, taking the call's arguments.
+pub type TemplateFunction = Arc<dyn Fn(&[String]) -> Result<String> + Send + Sync>;
+
+/// Registry of template functions.
+///
+/// Comes with the built-ins `now()`, `uuid()`, `upper(s)`, `base64encode(s)`
+/// and `base64decode(s)`. Custom functions registered under an existing name
+/// replace it.
+#[derive(Clone)]
+pub struct TemplateFunctions {
+    functions: ShareLock<HashMap<String, TemplateFunction>>,
+}
+
+impl Default for TemplateFunctions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateFunctions {
+    /// Creates a registry containing the built-in functions.
+    pub fn new() -> Self {
+        let registry = Self {
+            functions: Arc::new(RwLock::new(HashMap::new())),
+        };
+
+        registry.register("now", |_| Ok(utils::time::time_millis().to_string()));
+        registry.register("uuid", |_| Ok(uuid::Uuid::new_v4().to_string()));
+        registry.register("upper", |args| Ok(single_arg("upper", args)?.to_uppercase()));
+        registry.register("base64encode", |args| Ok(STANDARD.encode(single_arg("base64encode", args)?)));
+        registry.register("base64decode", |args| {
+            let bytes = STANDARD.decode(single_arg("base64decode", args)?).map_err(|e| ActflowError::Runtime(format!("base64decode: {}", e)))?;
+            Ok(String::from_utf8(bytes)?)
+        });
+
+        registry
+    }
+
+    /// Registers a function under `name`.
+    pub fn register(
+        &self,
+        name: &str,
+        f: impl Fn(&[String]) -> Result<String> + Send + Sync + 'static,
+    ) {
+        self.functions.write().unwrap().insert(name.to_string(), Arc::new(f));
+    }
+
+    /// Calls the function registered under `name`.
+    pub fn call(
+        &self,
+        name: &str,
+        args: &[String],
+    ) -> Result<String> {
+        let f = self.functions.read().unwrap().get(name).cloned().ok_or(ActflowError::Runtime(format!("template function '{}' not found", name)))?;
+        f(args)
+    }
+}
+
+fn single_arg<'a>(
+    name: &str,
+    args: &'a [String],
+) -> Result<&'a str> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(ActflowError::Runtime(format!(
+            "template function '{}' expects 1 argument, got {}",
+            name,
+            args.len()
+        ))),
+    }
+}
@@ -0,0 +1,84 @@
+//! Interceptors run around every action.
+//!
+//! Interceptors handle cross-cutting concerns such as logging, metrics or
+//! auth injection without changing individual actions. They are registered on
+//! the engine with [`Engine::register_interceptor`](crate::Engine::register_interceptor)
+//! and called by the dispatcher around each [`Action::run`](super::actions::Action::run),
+//! including every retry attempt and every fan-out item.
+
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    ShareLock,
+    runtime::Context,
+    workflow::{actions::ActionOutput, node::Node},
+};
+
+/// Hooks called before and after a node's action runs.
+pub trait ActionInterceptor: Send + Sync {
+    /// Called before the action runs.
+    ///
+    /// Returning an output short-circuits the node: the action and the
+    /// remaining interceptors' `before_run` are skipped and the output is used
+    /// as the action's result.
+    fn before_run(
+        &self,
+        _ctx: &Context,
+        _node: &Node,
+    ) -> Option<ActionOutput> {
+        None
+    }
+
+    /// Called with the action's output, including short-circuited outputs.
+    fn after_run(
+        &self,
+        _ctx: &Context,
+        _node: &Node,
+        _output: &ActionOutput,
+    ) {
+    }
+}
+
+/// Ordered chain of action interceptors.
+#[derive(Clone, Default)]
+pub struct ActionInterceptors {
+    interceptors: ShareLock<Vec<Arc<dyn ActionInterceptor>>>,
+}
+
+impl ActionInterceptors {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self {
+            interceptors: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Appends `interceptor` to the chain.
+    pub fn register(
+        &self,
+        interceptor: Arc<dyn ActionInterceptor>,
+    ) {
+        self.interceptors.write().unwrap().push(interceptor);
+    }
+
+    /// Calls `before_run` in registration order, stopping at the first short-circuit.
+    pub fn before_run(
+        &self,
+        ctx: &Context,
+        node: &Node,
+    ) -> Option<ActionOutput> {
+        self.interceptors.read().unwrap().iter().find_map(|interceptor| interceptor.before_run(ctx, node))
+    }
+
+    /// Calls `after_run` on every interceptor in registration order.
+    pub fn after_run(
+        &self,
+        ctx: &Context,
+        node: &Node,
+        output: &ActionOutput,
+    ) {
+        for interceptor in self.interceptors.read().unwrap().iter() {
+            interceptor.after_run(ctx, node, output);
+        }
+    }
+}
@@ -1,8 +1,11 @@
 pub mod actions;
 pub mod consts;
 pub mod edge;
+pub mod functions;
+pub mod interceptor;
 pub mod node;
 pub mod template;
+#[allow(clippy::module_inception)]
 mod workflow;
 
 pub use workflow::Workflow;
@@ -3,14 +3,22 @@
 //! Nodes are the building blocks of a workflow, each representing an action
 //! to be executed (e.g., HTTP request, code execution, conditional branching).
 
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
+#[cfg(any(feature = "js", feature = "python"))]
+use crate::workflow::actions::CodeAction;
+#[cfg(feature = "nats")]
+use crate::workflow::actions::PublishAction;
 use crate::{
     ActflowError, Result,
     common::Vars,
-    workflow::actions::{Action, ActionOutput, ActionType, AgentAction, CodeAction, EndAction, HttpRequestAction, IfElseAction, StartAction},
+    runtime::Context,
+    workflow::{
+        actions::{Action, ActionOutput, ActionType, AgentAction, EndAction, HttpRequestAction, IfElseAction, PollAction, StartAction, WeightedAction},
+        template,
+    },
 };
 
 /// Unique identifier for a node within a workflow.
@@ -65,7 +73,8 @@ pub enum ErrorStrategy {
     /// Use a default value on error.
     DefaultValue,
     /// Take the fail branch on error.
-    FaileBranch,
+    #[serde(alias = "faile_branch")]
+    FailBranch,
 }
 
 #[derive(Deserialize)]
@@ -79,11 +88,25 @@ struct NodeMetadata {
     #[serde(default)]
     default_value: Option<Vars>,
     #[serde(default)]
+    continue_on_error: bool,
+    #[serde(default)]
     retry: Option<RetryConfig>,
     uses: ActionType,
     // timeout in milliseconds
     #[serde(default)]
     timeout: Option<u64>,
+    #[serde(default)]
+    inputs: Option<Vec<InputVariable>>,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(default)]
+    skip_if: Option<String>,
+    #[serde(default)]
+    skip_subtree: bool,
+    #[serde(default)]
+    output_transform: Option<serde_json::Value>,
+    #[serde(default)]
+    concurrency_key: Option<String>,
     action: serde_json::Value,
 }
 
@@ -99,6 +122,9 @@ pub struct Node {
     pub error_strategy: ErrorStrategy,
     /// default value when error occurs
     pub default_value: Option<Vars>,
+    /// skip instead of failing when no error strategy is set
+    #[serde(default)]
+    pub continue_on_error: bool,
     /// retry config
     pub retry: Option<RetryConfig>,
     /// uses which action
@@ -108,34 +134,64 @@ pub struct Node {
     pub status: NodeState,
     /// action execution timeout
     pub timeout: Option<Duration>,
+    /// input variable mapping
+    pub inputs: Vec<InputVariable>,
+    /// skipped without executing when set
+    #[serde(default)]
+    pub disabled: bool,
+    /// template skipping the node when it resolves truthy
+    #[serde(default)]
+    pub skip_if: Option<String>,
+    /// whether skipping by `skip_if` also skips the nodes reached only through this node
+    #[serde(default)]
+    pub skip_subtree: bool,
+    /// template reshaping the action's outputs
+    #[serde(default)]
+    pub output_transform: Option<serde_json::Value>,
+    /// lock shared with other nodes that must not run concurrently
+    #[serde(default)]
+    pub concurrency_key: Option<String>,
     /// action data
     pub action_data: serde_json::Value,
-    /// node action
-    pub action: Box<dyn Action>,
+    /// node action, shared between clones of the node
+    pub action: Arc<dyn Action>,
 }
 
 impl Clone for Node {
     fn clone(&self) -> Self {
-        let action = Self::create_action(self.uses, self.action_data.clone()).unwrap();
-
         Self {
             id: self.id.clone(),
             title: self.title.clone(),
             desc: self.desc.clone(),
-            error_strategy: self.error_strategy.clone(),
+            error_strategy: self.error_strategy,
             default_value: self.default_value.clone(),
+            continue_on_error: self.continue_on_error,
             retry: self.retry.clone(),
-            uses: self.uses.clone(),
-            status: self.status.clone(),
-            timeout: self.timeout.clone(),
+            uses: self.uses,
+            status: self.status,
+            timeout: self.timeout,
+            inputs: self.inputs.clone(),
+            disabled: self.disabled,
+            skip_if: self.skip_if.clone(),
+            skip_subtree: self.skip_subtree,
+            output_transform: self.output_transform.clone(),
+            concurrency_key: self.concurrency_key.clone(),
             action_data: self.action_data.clone(),
-            action: action,
+            action: self.action.clone(),
         }
     }
 }
 
 impl Node {
     pub fn new(input: Vars) -> Result<Self> {
+        if let Some(uses) = input.get::<String>("uses")
+            && uses.parse::<ActionType>().is_err()
+        {
+            return Err(ActflowError::Node(format!(
+                "unknown action type '{}' — is the feature enabled?",
+                uses
+            )));
+        }
         let node_input: NodeMetadata = serde_json::from_value(input.into()).map_err(|e| ActflowError::Node(format!("invalid node input: {}", e)))?;
 
         let action = Self::create_action(node_input.uses, node_input.action.clone())?;
@@ -146,10 +202,17 @@ impl Node {
             desc: node_input.desc,
             error_strategy: node_input.error_strategy,
             default_value: node_input.default_value,
+            continue_on_error: node_input.continue_on_error,
             retry: node_input.retry,
             uses: node_input.uses,
             status: NodeState::Unknown,
             timeout: node_input.timeout.map(Duration::from_millis),
+            inputs: node_input.inputs.unwrap_or_default(),
+            disabled: node_input.disabled,
+            skip_if: node_input.skip_if,
+            skip_subtree: node_input.skip_subtree,
+            output_transform: node_input.output_transform,
+            concurrency_key: node_input.concurrency_key,
             action_data: node_input.action,
             action,
         })
@@ -158,25 +221,94 @@ impl Node {
     fn create_action(
         uses: ActionType,
         action_params: serde_json::Value,
-    ) -> Result<Box<dyn Action>> {
+    ) -> Result<Arc<dyn Action>> {
         match uses {
-            ActionType::Agent => Ok(Box::new(AgentAction::create(action_params)?)),
-            ActionType::Code => Ok(Box::new(CodeAction::create(action_params)?)),
-            ActionType::End => Ok(Box::new(EndAction::create(action_params)?)),
-            ActionType::HttpRequest => Ok(Box::new(HttpRequestAction::create(action_params)?)),
-            ActionType::IfElse => Ok(Box::new(IfElseAction::create(action_params)?)),
-            ActionType::Start => Ok(Box::new(StartAction::create(action_params)?)),
-            _ => return Err(ActflowError::Node(format!("invalid 'uses': {:?}", uses))),
+            ActionType::Agent => Ok(Arc::new(AgentAction::create(action_params)?)),
+            #[cfg(any(feature = "js", feature = "python"))]
+            ActionType::Code => Ok(Arc::new(CodeAction::create(action_params)?)),
+            #[cfg(not(any(feature = "js", feature = "python")))]
+            ActionType::Code => Err(ActflowError::Node("code action support not compiled in".to_string())),
+            ActionType::End => Ok(Arc::new(EndAction::create(action_params)?)),
+            ActionType::HttpRequest => Ok(Arc::new(HttpRequestAction::create(action_params)?)),
+            ActionType::IfElse => Ok(Arc::new(IfElseAction::create(action_params)?)),
+            ActionType::Poll => Ok(Arc::new(PollAction::create(action_params)?)),
+            #[cfg(feature = "nats")]
+            ActionType::Publish => Ok(Arc::new(PublishAction::create(action_params)?)),
+            #[cfg(not(feature = "nats"))]
+            ActionType::Publish => Err(ActflowError::Node("publish action support not compiled in".to_string())),
+            ActionType::Start => Ok(Arc::new(StartAction::create(action_params)?)),
+            ActionType::Weighted => Ok(Arc::new(WeightedAction::create(action_params)?)),
+            _ => Err(ActflowError::Node(format!("invalid 'uses': {:?}", uses))),
         }
     }
 }
 
+/// Named node input resolved from a template before the action runs.
+///
+/// Resolved inputs are addressable by the node itself as `{{#nodeId.variable#}}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InputVariable {
+    /// input name
+    pub variable: String,
+    /// template to resolve the value, e.g. `{{#nodeId.key#}}`
+    pub value_selector: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RetryConfig {
     /// retry times
-    pub times: u64,
+    pub times: RetryValue,
     /// retry interval in milliseconds
-    pub interval: u64,
+    pub interval: RetryValue,
+    /// retry times used when `times` is a template that does not resolve
+    #[serde(default)]
+    pub default_times: u64,
+    /// retry interval used when `interval` is a template that does not resolve
+    #[serde(default)]
+    pub default_interval: u64,
+}
+
+impl RetryConfig {
+    /// Resolves the retry times and interval before the node runs, falling
+    /// back to `default_times` and `default_interval` for templates that do
+    /// not resolve to a non-negative integer.
+    pub fn resolve(
+        &self,
+        ctx: &Context,
+    ) -> (u64, u64) {
+        (
+            self.times.resolve(ctx).unwrap_or(self.default_times),
+            self.interval.resolve(ctx).unwrap_or(self.default_interval),
+        )
+    }
+}
+
+/// A retry setting: a number, or a template such as `{{#n1.retry_count#}}`
+/// resolved from upstream outputs or env when the node is about to run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RetryValue {
+    Fixed(u64),
+    Template(String),
+}
+
+impl RetryValue {
+    /// Returns the value, or `None` if the template does not resolve to a non-negative integer.
+    pub fn resolve(
+        &self,
+        ctx: &Context,
+    ) -> Option<u64> {
+        match self {
+            RetryValue::Fixed(value) => Some(*value),
+            RetryValue::Template(value) => template::resolve_template(ctx, value).ok()?.trim().parse().ok(),
+        }
+    }
+}
+
+impl From<u64> for RetryValue {
+    fn from(value: u64) -> Self {
+        RetryValue::Fixed(value)
+    }
 }
 
 /// Result of a node execution
@@ -190,6 +322,8 @@ pub struct NodeResult {
     pub error: Option<String>,
     /// action exception message
     pub exception: Option<String>,
+    /// suggested delay before retrying a failure
+    pub retry_after: Option<Duration>,
 }
 
 impl NodeResult {
@@ -200,23 +334,46 @@ impl NodeResult {
             outputs: output.outputs,
             error: output.error,
             exception: output.exception,
+            retry_after: output.retry_after,
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_clone_shares_action() {
+        let mut node = Node::new(Vars::from(json!({
+            "id": "n1",
+            "title": "n1",
+            "uses": "start",
+            "action": {}
+        })))
+        .unwrap();
+        // Cloning must not rebuild, and so not re-validate, the action
+        node.action_data = json!("not a valid start action");
+
+        let clones: Vec<Node> = (0..1000).map(|_| node.clone()).collect();
+        assert!(clones.iter().all(|c| Arc::ptr_eq(&c.action, &node.action)));
+        assert_eq!(Arc::strong_count(&node.action), 1001);
+    }
+
+    #[test]
+    fn test_unknown_action_type() {
+        let result = Node::new(Vars::from(json!({
+            "id": "n1",
+            "title": "n1",
+            "uses": "send_sms",
+            "action": {}
+        })));
 
-    pub fn from_result_output(output: Result<ActionOutput>) -> Self {
-        match output {
-            Ok(action_output) => Self {
-                status: action_output.status,
-                outputs: action_output.outputs,
-                error: action_output.error,
-                exception: action_output.exception,
-            },
-            Err(e) => Self {
-                status: NodeExecutionStatus::Exception,
-                outputs: Vars::new(),
-                error: None,
-                exception: Some(e.to_string()),
-            },
+        match result {
+            Err(ActflowError::Node(message)) => assert_eq!(message, "unknown action type 'send_sms' — is the feature enabled?"),
+            _ => panic!("expected a node error"),
         }
     }
 }
@@ -4,21 +4,128 @@ use serde_json::Value;
 use crate::{ActflowError, Result, runtime::Context};
 
 /// Regex pattern for output template variables
-/// Format: `{{#nodeId.key#}}` or `{{#nodeId.key.subkey#}}`
+/// Format: `{{#nodeId.key#}}` or `{{#nodeId.key.subkey#}}`, with a trailing `?`
+/// (`{{#nodeId.key?#}}`) marking the reference optional
 const OUTPUT_TEMPLATE_PATTERN: &str = r"\{\{#([^.#]+)\.([^#]+)#\}\}";
 /// Regex pattern for environment variables
 /// Format: `{{$VAR_NAME$}}`
 const ENV_TEMPLATE_PATTERN: &str = r"\{\{\$([^$]+)\$\}\}";
+/// Regex pattern for process input variables
+/// Format: `{{@input.key@}}` or `{{@input.key.subkey@}}`
+const INPUT_TEMPLATE_PATTERN: &str = r"\{\{@input\.([^@]+)@\}\}";
+/// Regex pattern for workflow variables
+/// Format: `{{~NAME~}}` or `{{~NAME.key~}}`
+const VAR_TEMPLATE_PATTERN: &str = r"\{\{~([^~]+)~\}\}";
+/// Regex pattern for the start of a template function call
+/// Format: `{{ name(arg1, arg2) }}`, where an argument is plain text, a quoted
+/// string or a template variable
+const FUNCTION_TEMPLATE_PATTERN: &str = r"\{\{\s*([a-zA-Z_][a-zA-Z0-9_]*)\(";
+
+/// Number of resolution passes made by default: resolved values are not re-resolved
+pub const DEFAULT_TEMPLATE_DEPTH: usize = 1;
+
+/// How numbers are rendered when substituted into a template string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// As serialized in JSON, e.g. `1.0` for a float one
+    #[default]
+    Json,
+    /// Floats without a fractional part as integers, e.g. `1.0` as `1`
+    Integer,
+    /// With a fixed number of decimals, e.g. `1` as `1.00` for `Fixed(2)`
+    Fixed(usize),
+}
 
-/// Resolve template variables in the format `{{#nodeId.key#}}` and `{{$VAR_NAME$}}`
-/// Returns error if any template variable cannot be resolved
+impl NumberFormat {
+    /// Render `n` in this format
+    fn format(
+        &self,
+        n: &serde_json::Number,
+    ) -> String {
+        match (self, n.as_f64()) {
+            (NumberFormat::Integer, Some(f)) if n.is_f64() && f.fract() == 0.0 && f.abs() < i64::MAX as f64 => (f as i64).to_string(),
+            (NumberFormat::Fixed(decimals), Some(f)) => format!("{:.*}", decimals, f),
+            _ => n.to_string(),
+        }
+    }
+}
+
+/// Resolve template variables in the format `{{#nodeId.key#}}`, `{{$VAR_NAME$}}`, `{{@input.key@}}` and `{{~NAME.key~}}`,
+/// and template function calls in the format `{{ name(args) }}`
+/// Resolved values that contain templates themselves are resolved again, up to the
+/// context's template depth in total, stopping early once the result is stable;
+/// function calls are only taken from `template` itself, never from resolved values
+/// Returns error if any template variable cannot be resolved or a function call is invalid
 pub fn resolve_template(
     ctx: &Context,
     template: &str,
 ) -> Result<String> {
-    let mut result = template.to_string();
+    let mut result = resolve_template_once(ctx, template, true)?;
+    for _ in 1..ctx.template_depth() {
+        if !result.contains("{{") {
+            break;
+        }
+        let next = resolve_template_once(ctx, &result, false)?;
+        if next == result {
+            break;
+        }
+        result = next;
+    }
+    Ok(result)
+}
+
+/// Single resolution pass of [`resolve_template`], calling template functions if `calls` is set
+fn resolve_template_once(
+    ctx: &Context,
+    template: &str,
+    calls: bool,
+) -> Result<String> {
+    let mut result = String::new();
     let mut errors: Vec<String> = Vec::new();
 
+    // Function calls are parsed from the template before any variable is substituted,
+    // so resolved values can neither change nor inject a call
+    let mut rest = template;
+    let fn_re = Regex::new(FUNCTION_TEMPLATE_PATTERN).unwrap();
+    while calls && let Some(caps) = fn_re.captures(rest) {
+        let call_start = caps.get(0).unwrap();
+        result.push_str(&resolve_variables(ctx, &rest[..call_start.start()], &mut errors));
+
+        match parse_call_args(&rest[call_start.end()..]) {
+            Some((args, len)) => {
+                // Each argument is resolved on its own and passed on as is
+                let args: Vec<String> = args.iter().map(|arg| resolve_variables(ctx, arg, &mut errors)).collect();
+                match ctx.functions().call(&caps[1], &args) {
+                    Ok(value) => result.push_str(&value),
+                    Err(e) => errors.push(e.to_string()),
+                }
+                rest = &rest[call_start.end() + len..];
+            }
+            None => {
+                let call = &rest[call_start.start()..];
+                let call = call.find("}}").map_or(call, |end| &call[..end + 2]);
+                errors.push(format!("invalid template function call '{}'", call));
+                rest = &rest[call_start.start() + call.len()..];
+            }
+        }
+    }
+    result.push_str(&resolve_variables(ctx, rest, &mut errors));
+
+    if !errors.is_empty() {
+        return Err(ActflowError::Runtime(errors.join(", ")));
+    }
+
+    Ok(result)
+}
+
+/// Resolve the template variables in `template`, recording the ones that cannot be resolved in `errors`
+fn resolve_variables(
+    ctx: &Context,
+    template: &str,
+    errors: &mut Vec<String>,
+) -> String {
+    let mut result = template.to_string();
+
     // First, resolve environment variables from context
     let env_re = Regex::new(ENV_TEMPLATE_PATTERN).unwrap();
     for caps in env_re.captures_iter(template) {
@@ -35,61 +142,167 @@ pub fn resolve_template(
         }
     }
 
+    // Then, resolve process input variables
+    let input_re = Regex::new(INPUT_TEMPLATE_PATTERN).unwrap();
+    for caps in input_re.captures_iter(template) {
+        let full_match = &caps[0];
+
+        let inputs: Value = ctx.process_inputs().clone().into();
+        match inputs.pointer(&to_json_pointer(&caps[1])).cloned().map(|v| value_to_string(v, ctx.number_format())) {
+            Some(value) => {
+                result = result.replace(full_match, &value);
+            }
+            None => {
+                errors.push(format!("input '{}' not found", &caps[1]));
+            }
+        }
+    }
+
+    // Then, resolve workflow variables
+    let var_re = Regex::new(VAR_TEMPLATE_PATTERN).unwrap();
+    for caps in var_re.captures_iter(template) {
+        let full_match = &caps[0];
+
+        let vars: Value = ctx.vars().clone().into();
+        match vars.pointer(&to_json_pointer(&caps[1])).cloned().map(|v| value_to_string(v, ctx.number_format())) {
+            Some(value) => {
+                result = result.replace(full_match, &value);
+            }
+            None => {
+                errors.push(format!("workflow variable '{}' not found", &caps[1]));
+            }
+        }
+    }
+
     // Then, resolve output template variables
     let re = Regex::new(OUTPUT_TEMPLATE_PATTERN).unwrap();
     for caps in re.captures_iter(template) {
         let full_match = &caps[0];
         let node_id = &caps[1];
-        let key_path = &caps[2];
+        let (key_path, optional) = split_optional(&caps[2]);
 
         // Get outputs for the node
-        let resolved_value = if let Some(node_outputs) = ctx.outputs().get(&node_id.to_string()) {
-            // Handle nested keys like "result.data.value"
-            let keys: Vec<&str> = key_path.split('.').collect();
-            let mut current_value: Option<Value> = None;
-
-            // Get the first key
-            if let Some(first_key) = keys.first() {
-                current_value = node_outputs.get::<Value>(first_key);
-
-                // Traverse nested keys
-                for key in keys.iter().skip(1) {
-                    if let Some(ref val) = current_value {
-                        current_value = val.get(key).cloned();
-                    } else {
-                        break;
-                    }
-                }
-            }
-
-            // Convert value to string
-            match current_value {
-                Some(Value::String(s)) => Some(s),
-                Some(Value::Number(n)) => Some(n.to_string()),
-                Some(Value::Bool(b)) => Some(b.to_string()),
-                Some(Value::Null) => Some("null".to_string()),
-                Some(v) => Some(v.to_string()), // For objects/arrays, use JSON string
-                None => None,
-            }
-        } else {
-            None
-        };
+        let resolved_value = ctx.resolve_path(node_id, &to_json_pointer(key_path)).map(|v| value_to_string(v, ctx.number_format()));
 
         match resolved_value {
             Some(value) => {
                 result = result.replace(full_match, &value);
             }
+            // Missing optional references resolve to an empty string
+            None if optional => {
+                result = result.replace(full_match, "");
+            }
             None => {
                 errors.push(format!("variable '{}' not found", full_match));
             }
         }
     }
 
-    if !errors.is_empty() {
-        return Err(ActflowError::Runtime(errors.join(", ")));
+    result
+}
+
+/// Parse the arguments of a template function call, starting right after its `(`
+/// Arguments are separated by `,` and may be quoted or contain template variables,
+/// but must not contain a bare `(`, `)`, `{` or `}`
+/// Returns the arguments with their quotes removed, and the length of the call up to
+/// and including its closing `}}`, or `None` if the call is invalid
+fn parse_call_args(s: &str) -> Option<(Vec<String>, usize)> {
+    let mut args: Vec<String> = Vec::new();
+    let mut arg = String::new();
+    let mut i = 0;
+    loop {
+        let rest = &s[i..];
+        let c = rest.chars().next()?;
+        match c {
+            '{' if rest.starts_with("{{") && rest[2..].starts_with(['#', '$', '@', '~']) => {
+                let len = rest.find("}}")? + 2;
+                arg.push_str(&rest[..len]);
+                i += len;
+            }
+            '"' | '\'' => {
+                let len = rest[1..].find(c)? + 2;
+                arg.push_str(&rest[..len]);
+                i += len;
+            }
+            ',' => {
+                args.push(unquote(&arg));
+                arg.clear();
+                i += 1;
+            }
+            ')' => {
+                let after = &rest[1..];
+                let trimmed = after.trim_start();
+                if !trimmed.starts_with("}}") {
+                    return None;
+                }
+                if !args.is_empty() || !arg.trim().is_empty() {
+                    args.push(unquote(&arg));
+                }
+                return Some((args, i + 1 + after.len() - trimmed.len() + 2));
+            }
+            '(' | '{' | '}' => return None,
+            _ => {
+                arg.push(c);
+                i += c.len_utf8();
+            }
+        }
     }
+}
 
-    Ok(result)
+/// Trim a function argument and remove its surrounding quotes, if any
+fn unquote(arg: &str) -> String {
+    let arg = arg.trim();
+    for quote in ['"', '\''] {
+        if arg.len() >= 2 && arg.starts_with(quote) && arg.ends_with(quote) {
+            return arg[1..arg.len() - 1].to_string();
+        }
+    }
+    arg.to_string()
+}
+
+/// Split a key path into the path and whether the reference is optional (`key?`)
+fn split_optional(key_path: &str) -> (&str, bool) {
+    match key_path.strip_suffix('?') {
+        Some(key_path) => (key_path, true),
+        None => (key_path, false),
+    }
+}
+
+/// Whether `template` contains an optional `{{#nodeId.key?#}}` reference
+pub fn has_optional_reference(template: &str) -> bool {
+    let re = Regex::new(OUTPUT_TEMPLATE_PATTERN).unwrap();
+    re.captures_iter(template).any(|caps| split_optional(&caps[2]).1)
+}
+
+/// Convert a dotted key path like "result.items.0" to a JSON pointer ("/result/items/0")
+fn to_json_pointer(key_path: &str) -> String {
+    key_path.split('.').map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1"))).collect()
+}
+
+/// Convert a resolved value to its template string form
+fn value_to_string(
+    value: Value,
+    number_format: NumberFormat,
+) -> String {
+    match value {
+        Value::String(s) => s,
+        Value::Number(n) => number_format.format(&n),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "null".to_string(),
+        v => v.to_string(), // For objects/arrays, use JSON string
+    }
+}
+
+/// Whether a resolved condition such as `skip_if` holds: anything but an
+/// empty string, `false`, `0` or `null`
+pub fn is_truthy(resolved: &str) -> bool {
+    !matches!(resolved.trim(), "" | "false" | "0" | "null")
+}
+
+/// Ids of the nodes referenced by `{{#nodeId.key#}}` variables in `template`
+pub fn referenced_nodes(template: &str) -> Vec<String> {
+    let re = Regex::new(OUTPUT_TEMPLATE_PATTERN).unwrap();
+    re.captures_iter(template).map(|caps| caps[1].to_string()).collect()
 }
 
 /// Resolve template variables and return all matched values as a Vec
@@ -106,30 +319,13 @@ pub fn resolve_template_to_values(
     for caps in re.captures_iter(template) {
         let full_match = &caps[0];
         let node_id = &caps[1];
-        let key_path = &caps[2];
+        let (key_path, optional) = split_optional(&caps[2]);
 
-        let resolved_value = if let Some(node_outputs) = ctx.outputs().get(&node_id.to_string()) {
-            let keys: Vec<&str> = key_path.split('.').collect();
-            let mut current_value: Option<Value> = None;
-
-            if let Some(first_key) = keys.first() {
-                current_value = node_outputs.get::<Value>(first_key);
-
-                for key in keys.iter().skip(1) {
-                    if let Some(ref val) = current_value {
-                        current_value = val.get(key).cloned();
-                    } else {
-                        break;
-                    }
-                }
-            }
-            current_value
-        } else {
-            None
-        };
+        let resolved_value = ctx.resolve_path(node_id, &to_json_pointer(key_path));
 
         match resolved_value {
             Some(value) => values.push(value),
+            None if optional => values.push(Value::Null),
             None => errors.push(format!("variable '{}' not found", full_match)),
         }
     }
@@ -146,6 +342,62 @@ pub fn resolve_template_to_values(
     Ok(values)
 }
 
+/// Attach the node and field that referenced a template to its resolution error
+pub fn field_error(
+    node_id: &str,
+    field: &str,
+    err: ActflowError,
+) -> ActflowError {
+    ActflowError::Runtime(format!("node '{}' field '{}': {}", node_id, field, err))
+}
+
+/// Resolve a template referenced by `field` of node `node_id`, naming both on failure
+pub fn resolve_template_in(
+    ctx: &Context,
+    template: &str,
+    node_id: &str,
+    field: &str,
+) -> Result<String> {
+    resolve_template(ctx, template).map_err(|e| field_error(node_id, field, e))
+}
+
+/// Resolve a JSON value referenced by `field` of node `node_id`, naming both on failure
+pub fn resolve_json_value_in(
+    ctx: &Context,
+    value: &Value,
+    node_id: &str,
+    field: &str,
+) -> Result<Value> {
+    resolve_json_value(ctx, value).map_err(|e| field_error(node_id, field, e))
+}
+
+/// Resolve template variables in a JSON Value recursively, keeping value types:
+/// a string consisting solely of one `{{#nodeId.key#}}` reference becomes the
+/// referenced value itself rather than its string form
+pub fn resolve_json_template(
+    ctx: &Context,
+    value: &Value,
+) -> Result<Value> {
+    match value {
+        Value::String(s) => {
+            let re = Regex::new(&format!("^{}$", OUTPUT_TEMPLATE_PATTERN)).unwrap();
+            if re.is_match(s.trim()) {
+                return resolve_template_to_values(ctx, s.trim()).map(|values| values.into_iter().next().unwrap_or(Value::Null));
+            }
+            resolve_json_value(ctx, value)
+        }
+        Value::Array(arr) => {
+            let resolved: Result<Vec<Value>> = arr.iter().map(|v| resolve_json_template(ctx, v)).collect();
+            Ok(Value::Array(resolved?))
+        }
+        Value::Object(obj) => {
+            let resolved: Result<serde_json::Map<String, Value>> = obj.iter().map(|(k, v)| resolve_json_template(ctx, v).map(|rv| (k.clone(), rv))).collect();
+            Ok(Value::Object(resolved?))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
 /// Resolve template variables in a JSON Value recursively
 pub fn resolve_json_value(
     ctx: &Context,
@@ -180,8 +432,7 @@ mod tests {
     use serde_json::json;
 
     use super::*;
-    use crate::common::Vars;
-    use crate::runtime::Channel;
+    use crate::{common::Vars, runtime::Channel};
 
     fn create_test_context() -> Context {
         let runtime = tokio::runtime::Runtime::new().unwrap();
@@ -231,6 +482,28 @@ mod tests {
         assert_eq!(result, "count: 42");
     }
 
+    #[test]
+    fn test_resolve_template_number_format() {
+        let ctx = create_test_context();
+        ctx.add_output(
+            "node1".to_string(),
+            Vars::from(json!({ "page": 1.0, "count": 3, "ratio": 0.5 })),
+        );
+        let template = "/items?page={{#node1.page#}}&count={{#node1.count#}}&ratio={{#node1.ratio#}}";
+
+        // Default rendering is unchanged
+        assert_eq!(resolve_template(&ctx, template).unwrap(), "/items?page=1.0&count=3&ratio=0.5");
+
+        let ctx = ctx.with_number_format(NumberFormat::Integer);
+        assert_eq!(resolve_template(&ctx, template).unwrap(), "/items?page=1&count=3&ratio=0.5");
+
+        let ctx = ctx.with_number_format(NumberFormat::Fixed(2));
+        assert_eq!(
+            resolve_template(&ctx, template).unwrap(),
+            "/items?page=1.00&count=3.00&ratio=0.50"
+        );
+    }
+
     #[test]
     fn test_resolve_template_bool_output() {
         let ctx = create_test_context();
@@ -326,6 +599,160 @@ mod tests {
         assert_eq!(result, "http://localhost:8080/api");
     }
 
+    #[test]
+    fn test_resolve_template_node_inputs() {
+        let ctx = create_test_context();
+        let mut inputs = Vars::new();
+        inputs.set("user_id", 7);
+        ctx.add_inputs("node1".to_string(), inputs);
+
+        let result = resolve_template(&ctx, "/users/{{#node1.user_id#}}").unwrap();
+        assert_eq!(result, "/users/7");
+    }
+
+    #[test]
+    fn test_resolve_template_array_index() {
+        let ctx = create_test_context();
+        let mut vars = Vars::new();
+        vars.set("items", json!([{ "name": "a" }, { "name": "b" }]));
+        ctx.add_output("node1".to_string(), vars);
+
+        let result = resolve_template(&ctx, "{{#node1.items.1.name#}}").unwrap();
+        assert_eq!(result, "b");
+    }
+
+    #[test]
+    fn test_resolve_template_process_inputs() {
+        let mut inputs = Vars::new();
+        inputs.set("user_id", 42);
+        inputs.set("user", json!({"name": "alice"}));
+        let ctx = create_test_context().with_process_inputs(inputs);
+
+        let result = resolve_template(&ctx, "id={{@input.user_id@}} name={{@input.user.name@}}").unwrap();
+        assert_eq!(result, "id=42 name=alice");
+
+        let result = resolve_template(&ctx, "{{@input.missing@}}");
+        assert!(result.unwrap_err().to_string().contains("input 'missing' not found"));
+    }
+
+    #[test]
+    fn test_resolve_template_workflow_vars() {
+        let vars = Vars::new().with("limits", json!({ "retries": 3, "regions": ["eu", "us"] })).with("name", "demo");
+        let ctx = create_test_context().with_vars(vars);
+
+        let result = resolve_template(&ctx, "{{~name~}}: {{~limits.retries~}} retries in {{~limits.regions.1~}}").unwrap();
+        assert_eq!(result, "demo: 3 retries in us");
+
+        let result = resolve_template(&ctx, "{{~limits.timeout~}}");
+        assert!(result.unwrap_err().to_string().contains("workflow variable 'limits.timeout' not found"));
+    }
+
+    #[test]
+    fn test_resolve_template_function_now() {
+        let ctx = create_test_context();
+        let result = resolve_template(&ctx, "{{ now() }}").unwrap();
+        assert!(result.parse::<i64>().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_resolve_nested_template() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let channel = Arc::new(Channel::new(Arc::new(runtime)));
+        let ctx = Context::new("test-pid".to_string(), channel).with_template_depth(3);
+        ctx.add_output("a".to_string(), Vars::from(json!({ "x": "{{#b.x#}}", "loop": "{{#a.loop#}}" })));
+        ctx.add_output("b".to_string(), Vars::from(json!({ "x": 42 })));
+        ctx.add_output("c".to_string(), Vars::from(json!({ "x": "{{#a.x#}}" })));
+
+        assert_eq!(resolve_template(&ctx, "value: {{#a.x#}}").unwrap(), "value: 42");
+        // A self-reference is stable after one pass
+        assert_eq!(resolve_template(&ctx, "{{#a.loop#}}").unwrap(), "{{#a.loop#}}");
+
+        // One pass by default
+        let ctx = ctx.with_template_depth(DEFAULT_TEMPLATE_DEPTH);
+        assert_eq!(resolve_template(&ctx, "{{#a.x#}}").unwrap(), "{{#b.x#}}");
+        // Depth bounds the number of passes
+        let ctx = ctx.with_template_depth(2);
+        assert_eq!(resolve_template(&ctx, "{{#c.x#}}").unwrap(), "{{#b.x#}}");
+    }
+
+    #[test]
+    fn test_resolve_optional_reference() {
+        let ctx = create_test_context();
+        let mut vars = Vars::new();
+        vars.set("name", "alice");
+        ctx.add_output("node1".to_string(), vars);
+
+        assert_eq!(resolve_template(&ctx, "{{#node1.name?#}}").unwrap(), "alice");
+        assert_eq!(resolve_template(&ctx, "[{{#node1.missing?#}}]").unwrap(), "[]");
+        assert_eq!(resolve_template(&ctx, "[{{#node2.name?#}}]").unwrap(), "[]");
+        assert!(resolve_template(&ctx, "{{#node1.missing#}}").is_err());
+
+        assert_eq!(
+            resolve_json_template(&ctx, &json!("{{#node1.missing?#}}")).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_resolve_template_function_with_variable() {
+        let ctx = create_test_context();
+        let mut vars = Vars::new();
+        vars.set("name", "alice");
+        ctx.add_output("node1".to_string(), vars);
+
+        let result = resolve_template(&ctx, "Hi {{ upper({{#node1.name#}}) }}").unwrap();
+        assert_eq!(result, "Hi ALICE");
+
+        let result = resolve_template(&ctx, "{{ base64encode(\"abc\") }}").unwrap();
+        assert_eq!(result, "YWJj");
+    }
+
+    #[test]
+    fn test_resolve_template_custom_function() {
+        let ctx = create_test_context();
+        ctx.functions().register("join", |args| Ok(args.join("-")));
+
+        let result = resolve_template(&ctx, "{{ join(a, b, c) }}").unwrap();
+        assert_eq!(result, "a-b-c");
+    }
+
+    #[test]
+    fn test_resolve_template_function_args_are_exact() {
+        let ctx = create_test_context();
+        ctx.functions().register("join", |args| Ok(args.join("|")));
+        ctx.add_output("node1".to_string(), Vars::from(json!({ "name": "a(b){c}", "list": "x, y" })));
+
+        // Resolved values are passed on whole, whatever they contain
+        assert_eq!(resolve_template(&ctx, "{{ upper({{#node1.name#}}) }}").unwrap(), "A(B){C}");
+        assert_eq!(resolve_template(&ctx, "{{ join({{#node1.list#}}, z) }}").unwrap(), "x, y|z");
+        assert_eq!(resolve_template(&ctx, "{{ join(\"a, b\", 'c)') }}").unwrap(), "a, b|c)");
+    }
+
+    #[test]
+    fn test_resolve_template_invalid_function_call() {
+        let ctx = create_test_context();
+        for template in ["{{ upper(a(b)) }}", "{{ upper(a{b) }}", "{{ upper(a) ", "x {{ upper(\"a) }}"] {
+            let err = resolve_template(&ctx, template).unwrap_err().to_string();
+            assert!(err.contains("invalid template function call"), "{}: {}", template, err);
+        }
+    }
+
+    #[test]
+    fn test_resolve_template_does_not_call_functions_in_values() {
+        let ctx = create_test_context().with_template_depth(3);
+        ctx.add_output("node1".to_string(), Vars::from(json!({ "text": "{{ uuid() }}" })));
+
+        assert_eq!(resolve_template(&ctx, "{{#node1.text#}}").unwrap(), "{{ uuid() }}");
+        assert_eq!(resolve_template(&ctx, "{{ upper({{#node1.text#}}) }}").unwrap(), "{{ UUID() }}");
+    }
+
+    #[test]
+    fn test_resolve_template_unknown_function() {
+        let ctx = create_test_context();
+        let result = resolve_template(&ctx, "{{ missing() }}");
+        assert!(result.unwrap_err().to_string().contains("template function 'missing' not found"));
+    }
+
     // ==================== resolve_template_to_values tests ====================
 
     #[test]
@@ -452,4 +879,22 @@ mod tests {
         // Should parse JSON string back to object
         assert_eq!(result, json!({"foo": "bar"}));
     }
+
+    #[test]
+    fn test_resolve_json_template_keeps_types() {
+        let ctx = create_test_context();
+        let mut vars = Vars::new();
+        vars.set("age", 25);
+        vars.set("tags", json!(["a", "b"]));
+        vars.set("name", "Alice");
+        ctx.add_output("node1".to_string(), vars);
+
+        let input = json!({
+            "years": "{{#node1.age#}}",
+            "tags": ["{{#node1.tags#}}"],
+            "greeting": "hi {{#node1.name#}}"
+        });
+        let result = resolve_json_template(&ctx, &input).unwrap();
+        assert_eq!(result, json!({"years": 25, "tags": [["a", "b"]], "greeting": "hi Alice"}));
+    }
 }
@@ -4,11 +4,19 @@
 //! It wraps the workflow model in a directed graph structure (using petgraph) for
 //! efficient traversal and state management.
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
 
 use petgraph::{
     Direction,
-    graph::{DiGraph, NodeIndex},
+    algo::{has_path_connecting, toposort},
+    graph::{DiGraph, EdgeIndex, NodeIndex},
     visit::EdgeRef,
 };
 
@@ -16,8 +24,11 @@ use crate::{
     ActflowError, Result, ShareLock, WorkflowModel,
     common::Vars,
     workflow::{
+        actions::ActionType,
+        consts::INCOMING_NAMESPACE,
         edge::{Edge, EdgeId, EdgeSelectOptions, SourceHandle},
-        node::{Node, NodeId, NodeState},
+        node::{ErrorStrategy, Node, NodeId, NodeState, RetryConfig},
+        template,
     },
 };
 
@@ -36,6 +47,12 @@ use crate::{
 pub struct Workflow {
     /// Thread-safe directed graph storing nodes and edges.
     graph: ShareLock<DiGraph<Node, Edge>>,
+    /// Number of executed or skipped nodes, only changed under the graph's write lock.
+    completed: Arc<AtomicUsize>,
+    /// Index of each node in the graph; nodes are never removed, so entries stay valid.
+    node_indices: ShareLock<HashMap<NodeId, NodeIndex>>,
+    /// Index of each edge in the graph; edges are never removed, so entries stay valid.
+    edge_indices: ShareLock<HashMap<EdgeId, EdgeIndex>>,
 }
 
 #[allow(unused)]
@@ -44,6 +61,9 @@ impl Workflow {
     pub fn new() -> Self {
         Self {
             graph: ShareLock::new(DiGraph::new().into()),
+            completed: Arc::new(AtomicUsize::new(0)),
+            node_indices: ShareLock::new(HashMap::new().into()),
+            edge_indices: ShareLock::new(HashMap::new().into()),
         }
     }
 
@@ -122,7 +142,14 @@ impl Workflow {
         node: Node,
     ) -> NodeIndex {
         let mut graph = self.graph.write().unwrap();
-        graph.add_node(node)
+        if is_completed(node.status) {
+            self.completed.fetch_add(1, Ordering::Relaxed);
+        }
+        let nid = node.id.clone();
+        let idx = graph.add_node(node);
+        // Lookups have always found the first node with an id
+        self.node_indices.write().unwrap().entry(nid).or_insert(idx);
+        idx
     }
 
     /// add edge between two nodes
@@ -133,16 +160,96 @@ impl Workflow {
         edge: Edge,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.add_edge(from, to, edge);
+        let eid = edge.id.clone();
+        let idx = graph.add_edge(from, to, edge);
+        // Lookups have always found the first edge with an id
+        self.edge_indices.write().unwrap().entry(eid).or_insert(idx);
     }
 
     /// get node by id
+    ///
+    /// The returned node is a clone sharing the constructed action, so this is
+    /// cheap enough for the dispatcher's hot paths.
     pub fn get_node(
         &self,
         id: &NodeId,
     ) -> Option<Node> {
         let graph = self.graph.read().unwrap();
-        graph.node_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].clone())
+        self.node_index(id).map(|idx| graph[idx].clone())
+    }
+
+    /// Looks up the graph index of a node.
+    fn node_index(
+        &self,
+        id: &NodeId,
+    ) -> Option<NodeIndex> {
+        self.node_indices.read().unwrap().get(id).copied()
+    }
+
+    /// Looks up the graph index of an edge.
+    fn edge_index(
+        &self,
+        id: &EdgeId,
+    ) -> Option<EdgeIndex> {
+        self.edge_indices.read().unwrap().get(id).copied()
+    }
+
+    /// Reads a node under the lock without cloning it.
+    fn with_node<R>(
+        &self,
+        id: &NodeId,
+        f: impl FnOnce(&Node) -> R,
+    ) -> Option<R> {
+        let graph = self.graph.read().unwrap();
+        self.node_index(id).map(|idx| f(&graph[idx]))
+    }
+
+    /// get the action type of a node
+    pub fn node_action_type(
+        &self,
+        id: &NodeId,
+    ) -> Option<ActionType> {
+        self.with_node(id, |n| n.uses)
+    }
+
+    /// get the retry config of a node
+    pub fn node_retry(
+        &self,
+        id: &NodeId,
+    ) -> Option<RetryConfig> {
+        self.with_node(id, |n| n.retry.clone()).flatten()
+    }
+
+    /// get the action timeout of a node
+    pub fn node_timeout(
+        &self,
+        id: &NodeId,
+    ) -> Option<Duration> {
+        self.with_node(id, |n| n.timeout).flatten()
+    }
+
+    /// get the error strategy of a node
+    pub fn node_error_strategy(
+        &self,
+        id: &NodeId,
+    ) -> Option<ErrorStrategy> {
+        self.with_node(id, |n| n.error_strategy)
+    }
+
+    /// whether a failure of the node is treated as a skip
+    pub fn node_continue_on_error(
+        &self,
+        id: &NodeId,
+    ) -> bool {
+        self.with_node(id, |n| n.continue_on_error).unwrap_or(false)
+    }
+
+    /// Whether skipping the node through `skip_if` also skips its exclusive subtree
+    pub fn node_skips_subtree(
+        &self,
+        id: &NodeId,
+    ) -> bool {
+        self.with_node(id, |n| !n.disabled && n.skip_if.is_some() && n.skip_subtree).unwrap_or(false)
     }
 
     /// get edge by id
@@ -151,7 +258,7 @@ impl Workflow {
         id: &EdgeId,
     ) -> Option<Edge> {
         let graph = self.graph.read().unwrap();
-        graph.edge_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].clone())
+        self.edge_index(id).map(|idx| graph[idx].clone())
     }
 
     /// get node state by id
@@ -159,7 +266,7 @@ impl Workflow {
         &self,
         id: &NodeId,
     ) -> Option<NodeState> {
-        self.get_node(id).map(|n| n.status)
+        self.with_node(id, |n| n.status)
     }
 
     /// get edge state by id
@@ -170,16 +277,224 @@ impl Workflow {
         self.get_edge(id).map(|e| e.status)
     }
 
+    /// get the fan-out selector of the first incoming edge of a node that has one
+    pub fn get_fan_out_selector(
+        &self,
+        nid: &NodeId,
+    ) -> Option<String> {
+        let graph = self.graph.read().unwrap();
+        self.node_index(nid).and_then(|idx| graph.edges_directed(idx, Direction::Incoming).find_map(|edge_ref| edge_ref.weight().fan_out_selector.clone()))
+    }
+
+    /// Get the sources of the edges into a node that were not skipped, in edge order
+    pub fn get_incoming_sources(
+        &self,
+        nid: &NodeId,
+    ) -> Vec<NodeId> {
+        let graph = self.graph.read().unwrap();
+        let Some(idx) = self.node_index(nid) else {
+            return Vec::new();
+        };
+        let mut edges: Vec<_> = graph.edges_directed(idx, Direction::Incoming).filter(|edge_ref| edge_ref.weight().status != NodeState::Skipped).collect();
+        edges.sort_by_key(|edge_ref| edge_ref.id());
+        edges.into_iter().map(|edge_ref| graph[edge_ref.source()].id.clone()).collect()
+    }
+
     /// get max parallelism
     pub fn get_max_parallelism(&self) -> usize {
         let graph = self.graph.read().unwrap();
         graph.node_indices().map(|idx| graph.neighbors_directed(idx, petgraph::Direction::Outgoing).count()).max().unwrap_or(0)
     }
 
+    /// Node ids in topological order.
+    ///
+    /// Returns an error if the graph contains a cycle.
+    pub fn topological_order(&self) -> Result<Vec<NodeId>> {
+        let graph = self.graph.read().unwrap();
+        let order = Self::toposort(&graph)?;
+        Ok(order.into_iter().map(|idx| graph[idx].id.clone()).collect())
+    }
+
+    /// Longest chain of nodes (by node count) from a root node to a leaf node.
+    ///
+    /// Returns an error if the graph contains a cycle.
+    pub fn critical_path(&self) -> Result<Vec<NodeId>> {
+        let graph = self.graph.read().unwrap();
+        let (depths, prev) = Self::longest_paths(&graph)?;
+
+        let mut path = Vec::new();
+        let mut current = depths.iter().max_by_key(|(_, depth)| **depth).map(|(idx, _)| *idx);
+        while let Some(idx) = current {
+            path.push(graph[idx].id.clone());
+            current = prev.get(&idx).copied();
+        }
+        path.reverse();
+
+        Ok(path)
+    }
+
+    /// Maximum number of nodes that can run concurrently.
+    ///
+    /// Nodes are grouped by their depth on the longest path from a root; nodes at
+    /// the same depth never depend on each other, so the largest group is the width.
+    /// Returns an error if the graph contains a cycle.
+    pub fn max_width(&self) -> Result<usize> {
+        let graph = self.graph.read().unwrap();
+        let (depths, _) = Self::longest_paths(&graph)?;
+
+        let mut widths: HashMap<usize, usize> = HashMap::new();
+        for depth in depths.values() {
+            *widths.entry(*depth).or_default() += 1;
+        }
+
+        Ok(widths.into_values().max().unwrap_or(0))
+    }
+
+    /// Checks every `{{#nodeId.key#}}` reference in the node and edge definitions.
+    ///
+    /// A referenced node must exist and have a path to the referencing node, so
+    /// its outputs are available when the reference is resolved. Nodes may
+    /// reference themselves (their own inputs), and nodes in `seeded` are always
+    /// available. All dangling references are reported at once.
+    pub fn check_references(
+        &self,
+        seeded: &HashSet<NodeId>,
+    ) -> Result<()> {
+        let graph = self.graph.read().unwrap();
+        let indices: HashMap<&NodeId, NodeIndex> = graph.node_indices().map(|idx| (&graph[idx].id, idx)).collect();
+
+        let mut errors = Vec::new();
+        let mut check = |owner: String, consumer: NodeIndex, field: &str, template: &str| {
+            for referenced in template::referenced_nodes(template) {
+                if referenced == INCOMING_NAMESPACE {
+                    continue;
+                }
+                let problem = match indices.get(&referenced) {
+                    None => format!("unknown node '{}'", referenced),
+                    Some(&idx) if idx != consumer && !seeded.contains(&referenced) && !has_path_connecting(&*graph, idx, consumer, None) => {
+                        format!("node '{}' does not run before it", referenced)
+                    }
+                    Some(_) => continue,
+                };
+                errors.push(format!("{} field '{}': {}", owner, field, problem));
+            }
+        };
+
+        for idx in graph.node_indices() {
+            let node = &graph[idx];
+            let owner = format!("node '{}'", node.id);
+            for (field, template) in template_strings(&node.action_data) {
+                check(owner.clone(), idx, &field, template);
+            }
+            for input in &node.inputs {
+                check(owner.clone(), idx, &format!("inputs.{}", input.variable), &input.value_selector);
+            }
+            if let Some(transform) = &node.output_transform {
+                for (field, template) in template_strings(transform) {
+                    check(owner.clone(), idx, &join_field("output_transform", &field), template);
+                }
+            }
+            if let Some(skip_if) = &node.skip_if {
+                check(owner.clone(), idx, "skip_if", skip_if);
+            }
+        }
+        for edge_idx in graph.edge_indices() {
+            let edge = &graph[edge_idx];
+            if let (Some(selector), Some((_, target))) = (&edge.fan_out_selector, graph.edge_endpoints(edge_idx)) {
+                check(format!("edge '{}'", edge.id), target, "fan_out_selector", selector);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ActflowError::Workflow(format!(
+                "invalid template references: {}",
+                errors.join("; ")
+            )))
+        }
+    }
+
+    /// Maps each node to the other nodes reading its outputs: its successors and
+    /// the nodes whose templates reference it, including through fan-out edges.
+    ///
+    /// Nodes nobody reads from are left out.
+    pub fn output_consumers(&self) -> HashMap<NodeId, HashSet<NodeId>> {
+        let graph = self.graph.read().unwrap();
+
+        let mut consumers: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
+        let mut add = |producer: String, consumer: &NodeId| {
+            if &producer != consumer {
+                consumers.entry(producer).or_default().insert(consumer.clone());
+            }
+        };
+
+        for idx in graph.node_indices() {
+            let node = &graph[idx];
+            for successor in graph.neighbors_directed(idx, Direction::Outgoing) {
+                add(node.id.clone(), &graph[successor].id);
+            }
+
+            let mut templates: Vec<&str> = template_strings(&node.action_data).into_iter().map(|(_, template)| template).collect();
+            templates.extend(node.inputs.iter().map(|input| input.value_selector.as_str()));
+            if let Some(transform) = &node.output_transform {
+                templates.extend(template_strings(transform).into_iter().map(|(_, template)| template));
+            }
+            templates.extend(node.skip_if.as_deref());
+            for referenced in templates.into_iter().flat_map(template::referenced_nodes) {
+                add(referenced, &node.id);
+            }
+        }
+        for edge_idx in graph.edge_indices() {
+            if let (Some(selector), Some((_, target))) = (&graph[edge_idx].fan_out_selector, graph.edge_endpoints(edge_idx)) {
+                for referenced in template::referenced_nodes(selector) {
+                    add(referenced, &graph[target].id);
+                }
+            }
+        }
+
+        consumers
+    }
+
+    /// Sorts the graph topologically, reporting a cycle as a workflow error.
+    fn toposort(graph: &DiGraph<Node, Edge>) -> Result<Vec<NodeIndex>> {
+        toposort(graph, None).map_err(|cycle| ActflowError::Workflow(format!("workflow contains a cycle at node {}", graph[cycle.node_id()].id)))
+    }
+
+    /// Computes each node's depth on the longest path from a root, along with the
+    /// predecessor on that path.
+    fn longest_paths(graph: &DiGraph<Node, Edge>) -> Result<(HashMap<NodeIndex, usize>, HashMap<NodeIndex, NodeIndex>)> {
+        let order = Self::toposort(graph)?;
+
+        let mut depths: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut prev: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        for idx in order {
+            let best = graph.neighbors_directed(idx, Direction::Incoming).max_by_key(|pred| depths[pred]);
+            let depth = best.map(|pred| depths[&pred] + 1).unwrap_or(1);
+            if let Some(pred) = best {
+                prev.insert(idx, pred);
+            }
+            depths.insert(idx, depth);
+        }
+
+        Ok((depths, prev))
+    }
+
     /// get root node
-    pub fn get_root_node(&self) -> Option<Node> {
+    ///
+    /// Prefers the node using the `start` action; falls back to the first node
+    /// without incoming edges. Returns an error if there are multiple start nodes.
+    pub fn get_root_node(&self) -> Result<Option<Node>> {
         let graph = self.graph.read().unwrap();
-        graph.node_indices().find(|idx| graph.neighbors_directed(*idx, petgraph::Direction::Incoming).count() == 0).map(|idx| graph[idx].clone())
+        let starts: Vec<NodeIndex> = graph.node_indices().filter(|idx| graph[*idx].uses == ActionType::Start).collect();
+        match starts.as_slice() {
+            [start] => Ok(Some(graph[*start].clone())),
+            [] => Ok(graph.node_indices().find(|idx| graph.neighbors_directed(*idx, petgraph::Direction::Incoming).count() == 0).map(|idx| graph[idx].clone())),
+            _ => Err(ActflowError::Workflow(format!(
+                "workflow has multiple start nodes: {}",
+                starts.iter().map(|idx| graph[*idx].id.as_str()).collect::<Vec<_>>().join(", ")
+            ))),
+        }
     }
 
     /// get all node ids
@@ -195,9 +510,7 @@ impl Workflow {
         edge_select: EdgeSelectOptions,
     ) -> Vec<String> {
         let graph = self.graph.read().unwrap();
-        graph
-            .node_indices()
-            .find(|idx| graph[*idx].id.eq(nid))
+        self.node_index(nid)
             .map(|src_idx| {
                 graph
                     .edges_directed(src_idx, Direction::Outgoing)
@@ -224,7 +537,9 @@ impl Workflow {
         id: &NodeId,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.node_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].status = NodeState::Taken);
+        if let Some(idx) = self.node_index(id) {
+            self.set_node_status(&mut graph, idx, NodeState::Taken);
+        }
     }
 
     /// mark node as skipped
@@ -233,7 +548,9 @@ impl Workflow {
         id: &NodeId,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.node_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].status = NodeState::Skipped);
+        if let Some(idx) = self.node_index(id) {
+            self.set_node_status(&mut graph, idx, NodeState::Skipped);
+        }
     }
 
     /// mark node as executed
@@ -242,7 +559,9 @@ impl Workflow {
         id: &NodeId,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.node_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].status = NodeState::Executed);
+        if let Some(idx) = self.node_index(id) {
+            self.set_node_status(&mut graph, idx, NodeState::Executed);
+        }
     }
 
     /// mark edge as taken
@@ -251,7 +570,9 @@ impl Workflow {
         id: &EdgeId,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.edge_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].status = NodeState::Taken);
+        if let Some(idx) = self.edge_index(id) {
+            graph[idx].status = NodeState::Taken;
+        }
     }
 
     /// mark edge as skipped
@@ -260,7 +581,9 @@ impl Workflow {
         id: &EdgeId,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.edge_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].status = NodeState::Skipped);
+        if let Some(idx) = self.edge_index(id) {
+            graph[idx].status = NodeState::Skipped;
+        }
     }
 
     /// mark edge as executed
@@ -269,7 +592,9 @@ impl Workflow {
         id: &EdgeId,
     ) {
         let mut graph = self.graph.write().unwrap();
-        graph.edge_indices().find(|idx| graph[*idx].id.eq(id)).map(|idx| graph[idx].status = NodeState::Executed);
+        if let Some(idx) = self.edge_index(id) {
+            graph[idx].status = NodeState::Executed;
+        }
     }
 
     /// check if node is ready
@@ -278,7 +603,7 @@ impl Workflow {
         nid: &NodeId,
     ) -> Result<bool> {
         let graph = self.graph.read().unwrap();
-        let node_idx = graph.node_indices().find(|idx| graph[*idx].id.eq(nid)).ok_or(ActflowError::Runtime(format!("node {} not found", nid)))?;
+        let node_idx = self.node_index(nid).ok_or(ActflowError::Runtime(format!("node {} not found", nid)))?;
 
         if graph.neighbors_directed(node_idx, Direction::Incoming).filter(|e| graph[*e].status == NodeState::Unknown).count() > 0 {
             return Ok(false);
@@ -293,7 +618,7 @@ impl Workflow {
         nid: &NodeId,
     ) -> Result<bool> {
         let graph = self.graph.read().unwrap();
-        let node_idx = graph.node_indices().find(|idx| graph[*idx].id.eq(nid)).ok_or(ActflowError::Runtime(format!("node {} not found", nid)))?;
+        let node_idx = self.node_index(nid).ok_or(ActflowError::Runtime(format!("node {} not found", nid)))?;
 
         if graph.neighbors_directed(node_idx, Direction::Outgoing).count() == 0 {
             Ok(true)
@@ -302,10 +627,68 @@ impl Workflow {
         }
     }
 
+    /// Get the nodes that have been taken but have not yet executed or been skipped
+    pub fn get_running_nodes(&self) -> Vec<NodeId> {
+        let graph = self.graph.read().unwrap();
+        graph.node_indices().filter(|idx| graph[*idx].status == NodeState::Taken).map(|idx| graph[idx].id.clone()).collect()
+    }
+
     /// check if all nodes are executed or skipped
+    ///
+    /// Uses the running count kept by the `mark_*` methods instead of scanning the nodes.
     pub fn is_all_node_executed(&self) -> bool {
         let graph = self.graph.read().unwrap();
-        graph.node_indices().all(|idx| graph[idx].status == NodeState::Executed || graph[idx].status == NodeState::Skipped)
+        self.completed.load(Ordering::Relaxed) == graph.node_count()
+    }
+
+    /// check if a node has been executed or skipped
+    pub fn is_node_completed(
+        &self,
+        id: &NodeId,
+    ) -> bool {
+        self.with_node(id, |n| is_completed(n.status)).unwrap_or(false)
+    }
+
+    /// check if any node has been executed or skipped
+    pub fn has_completed_nodes(&self) -> bool {
+        self.completed.load(Ordering::Relaxed) > 0
+    }
+
+    /// Get the nodes in the given state
+    pub fn get_nodes_in_state(
+        &self,
+        state: NodeState,
+    ) -> Vec<NodeId> {
+        let graph = self.graph.read().unwrap();
+        graph.node_weights().filter(|node| node.status == state).map(|node| node.id.clone()).collect()
+    }
+
+    /// Get the edges in the given state
+    pub fn get_edges_in_state(
+        &self,
+        state: NodeState,
+    ) -> Vec<EdgeId> {
+        let graph = self.graph.read().unwrap();
+        graph.edge_weights().filter(|edge| edge.status == state).map(|edge| edge.id.clone()).collect()
+    }
+
+    /// Sets a node's status, keeping the completed count in step.
+    fn set_node_status(
+        &self,
+        graph: &mut DiGraph<Node, Edge>,
+        idx: NodeIndex,
+        status: NodeState,
+    ) {
+        match (is_completed(graph[idx].status), is_completed(status)) {
+            (false, true) => {
+                self.completed.fetch_add(1, Ordering::Relaxed);
+            }
+            (true, false) => {
+                self.completed.fetch_sub(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+        graph[idx].status = status;
     }
 
     /// Get all outgoing edges from a node
@@ -314,9 +697,7 @@ impl Workflow {
         nid: &NodeId,
     ) -> Vec<Edge> {
         let graph = self.graph.read().unwrap();
-        graph
-            .node_indices()
-            .find(|idx| graph[*idx].id.eq(nid))
+        self.node_index(nid)
             .map(|src_idx| graph.edges_directed(src_idx, Direction::Outgoing).map(|edge_ref| edge_ref.weight().clone()).collect())
             .unwrap_or_default()
     }
@@ -334,8 +715,7 @@ impl Workflow {
             let mut graph = self.graph.write().unwrap();
 
             // Find and mark the edge as skipped
-            let edge_idx = graph.edge_indices().find(|idx| graph[*idx].id.eq(&current_edge_id));
-            let Some(edge_idx) = edge_idx else {
+            let Some(edge_idx) = self.edge_index(&current_edge_id) else {
                 continue;
             };
 
@@ -355,7 +735,7 @@ impl Workflow {
 
             if all_incoming_skipped && graph[target_idx].status == NodeState::Unknown {
                 // Mark node as skipped
-                graph[target_idx].status = NodeState::Skipped;
+                self.set_node_status(&mut graph, target_idx, NodeState::Skipped);
                 skipped.push((target_node_id.clone(), current_edge_id.clone()));
 
                 // Add all outgoing edges to process queue
@@ -369,6 +749,26 @@ impl Workflow {
         skipped
     }
 
+    /// Nodes that have not started but whose predecessors have all completed.
+    ///
+    /// Skipping a branch can unblock a join node whose other predecessors already
+    /// executed; nobody else would schedule it, so callers check this after skipping.
+    /// Root nodes and nodes reached only through skipped edges are excluded.
+    pub fn get_ready_nodes(&self) -> Vec<NodeId> {
+        let graph = self.graph.read().unwrap();
+        graph
+            .node_indices()
+            .filter(|idx| graph[*idx].status == NodeState::Unknown)
+            .filter(|idx| graph.edges_directed(*idx, Direction::Incoming).any(|e| e.weight().status != NodeState::Skipped))
+            .filter(|idx| {
+                graph
+                    .neighbors_directed(*idx, Direction::Incoming)
+                    .all(|pred_idx| graph[pred_idx].status == NodeState::Executed || graph[pred_idx].status == NodeState::Skipped)
+            })
+            .map(|idx| graph[idx].id.clone())
+            .collect()
+    }
+
     /// Skip all unselected branches from a node
     /// Returns a list of (node_id, edge_id) pairs that were skipped
     pub fn skip_unselected_branches(
@@ -394,34 +794,590 @@ impl Workflow {
     }
 }
 
+/// Whether a node in `status` no longer holds the workflow open.
+fn is_completed(status: NodeState) -> bool {
+    status == NodeState::Executed || status == NodeState::Skipped
+}
+
+/// Every string in `value` along with its dotted field path.
+fn template_strings(value: &serde_json::Value) -> Vec<(String, &str)> {
+    fn walk<'a>(
+        value: &'a serde_json::Value,
+        path: String,
+        out: &mut Vec<(String, &'a str)>,
+    ) {
+        match value {
+            serde_json::Value::String(s) => out.push((path, s)),
+            serde_json::Value::Array(items) => items.iter().enumerate().for_each(|(i, item)| walk(item, join_field(&path, &i.to_string()), out)),
+            serde_json::Value::Object(map) => map.iter().for_each(|(key, item)| walk(item, join_field(&path, key), out)),
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(value, String::new(), &mut out);
+    out
+}
+
+/// Appends `key` to a dotted field path.
+fn join_field(
+    path: &str,
+    key: &str,
+) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
 impl TryFrom<&WorkflowModel> for Workflow {
     type Error = ActflowError;
 
     fn try_from(model: &WorkflowModel) -> Result<Self> {
+        // Inline fragment instances before building the graph
+        let expanded;
+        let model = if model.nodes.iter().any(|node| node.is_fragment()) {
+            expanded = model.expand_fragments()?;
+            &expanded
+        } else {
+            model
+        };
+
         let mut graph: DiGraph<Node, Edge> = DiGraph::new();
 
         let mut nodes = HashMap::new();
+        let mut node_indices = HashMap::new();
+        let mut edge_indices = HashMap::new();
+        let annotations: HashSet<&String> = model.nodes.iter().filter(|node| node.is_annotation()).map(|node| &node.id).collect();
 
-        for node in model.nodes.iter() {
-            let node_value = serde_json::to_value(node).map_err(|e| ActflowError::from(e))?;
+        for (i, node) in model.nodes.iter().enumerate() {
+            if node.is_annotation() {
+                continue;
+            }
+            let node_value = serde_json::to_value(node).map_err(ActflowError::from)?;
             let input = Vars::from(node_value);
 
-            let node = Node::new(input)?;
+            // Locate action validation errors within the workflow model
+            let node = Node::new(input).map_err(|e| match e {
+                ActflowError::Validation {
+                    path,
+                    message,
+                } => ActflowError::Validation {
+                    path: format!("/nodes/{}/action{}", i, path),
+                    message,
+                },
+                e => e,
+            })?;
             let nid = node.id.clone();
             let node_idx = graph.add_node(node);
+            node_indices.entry(nid.clone()).or_insert(node_idx);
             nodes.insert(nid, node_idx);
         }
-        for edge in model.edges.iter() {
-            let edge_value = serde_json::to_value(edge).map_err(|e| ActflowError::from(e))?;
+        // Edges attached to annotations only carry editor layout
+        for edge in model.edges.iter().filter(|edge| !annotations.contains(&edge.source) && !annotations.contains(&edge.target)) {
+            let edge_value = serde_json::to_value(edge).map_err(ActflowError::from)?;
             let input = Vars::from(edge_value);
 
             let edge = Edge::new(input)?;
             let source = nodes.get(&edge.source).ok_or(ActflowError::Edge(format!("source node {} not found", edge.source)))?;
             let target = nodes.get(&edge.target).ok_or(ActflowError::Edge(format!("target node {} not found", edge.target)))?;
-            graph.add_edge(*source, *target, edge);
+            let eid = edge.id.clone();
+            let edge_idx = graph.add_edge(*source, *target, edge);
+            edge_indices.entry(eid).or_insert(edge_idx);
         }
+        let completed = graph.node_weights().filter(|node| is_completed(node.status)).count();
         Ok(Self {
             graph: ShareLock::new(graph.into()),
+            completed: Arc::new(AtomicUsize::new(completed)),
+            node_indices: ShareLock::new(node_indices.into()),
+            edge_indices: ShareLock::new(edge_indices.into()),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        NodeModel,
+        test_support::{branch_edge, edge, node},
+        workflow::edge::FixedHandle,
+    };
+
+    fn create_workflow(
+        nodes: &[&str],
+        edges: &[(&str, &str)],
+    ) -> Workflow {
+        let nodes: Vec<(&str, &str)> = nodes.iter().map(|id| (*id, "start")).collect();
+        create_workflow_with_uses(&nodes, edges)
+    }
+
+    fn create_workflow_with_uses(
+        nodes: &[(&str, &str)],
+        edges: &[(&str, &str)],
+    ) -> Workflow {
+        let model = WorkflowModel {
+            nodes: nodes.iter().map(|(id, uses)| node(id, uses, json!({}))).collect(),
+            edges: edges.iter().enumerate().map(|(i, (source, target))| edge(&format!("e{}", i), source, target)).collect(),
+            ..Default::default()
+        };
+        Workflow::try_from(&model).unwrap()
+    }
+
+    #[test]
+    fn test_critical_path_diamond() {
+        // a -> b -> c -> e, a -> d -> e
+        let workflow = create_workflow(
+            &["a", "b", "c", "d", "e"],
+            &[("a", "b"), ("b", "c"), ("c", "e"), ("a", "d"), ("d", "e")],
+        );
+
+        assert_eq!(workflow.critical_path().unwrap(), vec!["a", "b", "c", "e"]);
+        assert_eq!(workflow.max_width().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_critical_path_cycle() {
+        let workflow = create_workflow(&["a", "b"], &[("a", "b"), ("b", "a")]);
+
+        assert!(workflow.critical_path().is_err());
+        assert!(workflow.max_width().is_err());
+    }
+
+    #[test]
+    fn test_get_root_node_prefers_start() {
+        // "orphan" has no incoming edges but is not a start node
+        let workflow = create_workflow_with_uses(&[("orphan", "end"), ("s", "start"), ("e", "end")], &[("s", "e")]);
+        assert_eq!(workflow.get_root_node().unwrap().unwrap().id, "s");
+    }
+
+    #[test]
+    fn test_get_root_node_multiple_starts() {
+        let workflow = create_workflow(&["a", "b"], &[]);
+        assert!(matches!(workflow.get_root_node(), Err(ActflowError::Workflow(_))));
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let edges = [("a", "b"), ("b", "c"), ("c", "e"), ("a", "d"), ("d", "e")];
+        // insertion order deliberately differs from the dependency order
+        let workflow = create_workflow(&["e", "d", "c", "b", "a"], &edges);
+
+        let order = workflow.topological_order().unwrap();
+        assert_eq!(order.len(), 5);
+        let position = |id: &str| order.iter().position(|n| n == id).unwrap();
+        for (source, target) in edges {
+            assert!(position(source) < position(target), "{} should come before {}", source, target);
+        }
+
+        let cyclic = create_workflow(&["a", "b"], &[("a", "b"), ("b", "a")]);
+        assert!(matches!(cyclic.topological_order(), Err(ActflowError::Workflow(_))));
+    }
+
+    #[test]
+    fn test_annotation_nodes_are_excluded() {
+        let workflow = create_workflow_with_uses(
+            &[("s", "start"), ("note", "annotation"), ("e", "end")],
+            &[("s", "e"), ("s", "note"), ("note", "e")],
+        );
+
+        let mut ids = workflow.get_all_node_ids();
+        ids.sort();
+        assert_eq!(ids, vec!["e", "s"]);
+
+        // The edge from the annotation does not hold back its target
+        workflow.mark_node_executed(&"s".to_string());
+        assert_eq!(
+            workflow.get_next_ready_node(&"s".to_string(), EdgeSelectOptions::default()),
+            vec!["e"]
+        );
+
+        workflow.mark_node_executed(&"e".to_string());
+        assert!(workflow.is_all_node_executed());
+    }
+
+    #[test]
+    fn test_completion_count_follows_node_states() {
+        let workflow = create_workflow(&["a", "b", "c"], &[("a", "b"), ("a", "c")]);
+        assert!(!workflow.is_all_node_executed());
+
+        workflow.mark_node_taken(&"a".to_string());
+        workflow.mark_node_executed(&"a".to_string());
+        // Marking twice counts once
+        workflow.mark_node_executed(&"a".to_string());
+        let skipped = workflow.skip_branch(&"e1".to_string());
+        assert_eq!(skipped, vec![("c".to_string(), "e1".to_string())]);
+        assert!(!workflow.is_all_node_executed());
+
+        // Going back to taken reopens the workflow
+        workflow.mark_node_executed(&"b".to_string());
+        assert!(workflow.is_all_node_executed());
+        workflow.mark_node_taken(&"b".to_string());
+        assert!(!workflow.is_all_node_executed());
+        workflow.mark_node_skipped(&"b".to_string());
+        assert!(workflow.is_all_node_executed());
+    }
+
+    #[test]
+    fn test_completion_check_on_large_graph() {
+        // Checking after every completion used to scan the whole graph each time
+        let ids: Vec<String> = (0..5000).map(|i| format!("n{}", i)).collect();
+        let nodes: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let edges: Vec<(&str, &str)> = nodes.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let workflow = create_workflow(&nodes, &edges);
+
+        let started = std::time::Instant::now();
+        for (i, id) in ids.iter().enumerate() {
+            assert!(!workflow.is_all_node_executed(), "complete before {}", i);
+            workflow.mark_node_executed(id);
+        }
+        assert!(workflow.is_all_node_executed());
+        assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_node_lookups_use_index() {
+        let workflow = create_workflow(&["a", "b"], &[("a", "b")]);
+        assert_eq!(workflow.get_node(&"b".to_string()).unwrap().id, "b");
+        assert!(workflow.get_node(&"missing".to_string()).is_none());
+
+        // Nodes added later are indexed too
+        let node = Node::new(Vars::from(json!({ "id": "c", "title": "c", "uses": "start", "action": {} }))).unwrap();
+        let c = workflow.add_node(node);
+        let b = workflow.node_index(&"b".to_string()).unwrap();
+        workflow.add_edge(
+            b,
+            c,
+            Edge::new(Vars::from(
+                json!({ "id": "e1", "source": "b", "target": "c", "source_handle": "source" }),
+            ))
+            .unwrap(),
+        );
+        assert_eq!(workflow.node_index(&"c".to_string()), Some(c));
+        assert_eq!(workflow.get_node(&"c".to_string()).unwrap().id, "c");
+
+        workflow.mark_node_executed(&"b".to_string());
+        assert_eq!(workflow.get_node_state(&"b".to_string()), Some(NodeState::Executed));
+        assert_eq!(
+            workflow.get_next_ready_node(&"b".to_string(), EdgeSelectOptions::default()),
+            vec!["c"]
+        );
+        assert!(workflow.is_end_node(&"c".to_string()).unwrap());
+        assert!(workflow.is_node_ready(&"missing".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_node_lookups_on_large_graph() {
+        // Every lookup used to scan the node list
+        let ids: Vec<String> = (0..20_000).map(|i| format!("n{}", i)).collect();
+        let nodes: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let workflow = create_workflow(&nodes, &[]);
+
+        let started = std::time::Instant::now();
+        for id in &ids {
+            assert_eq!(workflow.node_action_type(id), Some(ActionType::Start));
+            workflow.mark_node_executed(id);
+        }
+        assert!(workflow.is_all_node_executed());
+        assert!(started.elapsed() < Duration::from_secs(1), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_edge_lookups_use_index() {
+        let workflow = create_workflow(&["a", "b", "c"], &[("a", "b"), ("b", "c")]);
+        let edge = workflow.get_edge(&"e1".to_string()).unwrap();
+        assert_eq!((edge.source.as_str(), edge.target.as_str()), ("b", "c"));
+        assert!(workflow.get_edge(&"missing".to_string()).is_none());
+
+        workflow.mark_edge_taken(&"e0".to_string());
+        assert_eq!(workflow.get_edge_state(&"e0".to_string()), Some(NodeState::Taken));
+        workflow.mark_edge_executed(&"e0".to_string());
+        assert_eq!(workflow.get_edge_state(&"e0".to_string()), Some(NodeState::Executed));
+        assert_eq!(workflow.get_edge_state(&"e1".to_string()), Some(NodeState::Unknown));
+    }
+
+    #[test]
+    fn test_skip_branch_on_deep_chain() {
+        // Skipping walks the chain edge by edge; each step used to scan all edges
+        let ids: Vec<String> = (0..20_000).map(|i| format!("n{}", i)).collect();
+        let nodes: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let edges: Vec<(&str, &str)> = nodes.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let workflow = create_workflow(&nodes, &edges);
+
+        workflow.mark_node_executed(&ids[0]);
+        let started = std::time::Instant::now();
+        let skipped = workflow.skip_branch(&"e0".to_string());
+        assert!(started.elapsed() < Duration::from_secs(1), "{:?}", started.elapsed());
+
+        assert_eq!(skipped.len(), ids.len() - 1);
+        assert_eq!(
+            skipped.last(),
+            Some(&(ids[ids.len() - 1].clone(), format!("e{}", ids.len() - 2)))
+        );
+        assert!(workflow.is_all_node_executed());
+    }
+
+    #[test]
+    fn test_try_from_expands_fragments() {
+        let model = WorkflowModel {
+            nodes: vec![
+                node("s", "start", json!({})),
+                node(
+                    "first",
+                    "fragment",
+                    json!({ "fragment": "finish", "params": { "label": "one" } }),
+                ),
+                node(
+                    "second",
+                    "fragment",
+                    json!({ "fragment": "finish", "params": { "label": "two" } }),
+                ),
+            ],
+            edges: vec![edge("e1", "s", "first"), edge("e2", "first", "second")],
+            fragments: HashMap::from([(
+                "finish".to_string(),
+                crate::FragmentModel {
+                    nodes: vec![node("done", "end", json!({ "outputs": { "label": "{{%label%}}" } }))],
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let workflow = Workflow::try_from(&model).unwrap();
+
+        assert_eq!(workflow.topological_order().unwrap(), ["s", "first/done", "second/done"]);
+        assert_eq!(
+            workflow.get_node(&"second/done".to_string()).unwrap().action_data,
+            json!({ "outputs": { "label": "two" } })
+        );
+        assert_eq!(
+            workflow.get_edge(&"e2".to_string()).map(|edge| (edge.source, edge.target)),
+            Some(("first/done".to_string(), "second/done".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_output_consumers() {
+        let model = WorkflowModel {
+            nodes: vec![
+                node("s", "start", json!({})),
+                node("a", "end", json!({ "outputs": { "own": "{{#a.value#}}" } })),
+                node("b", "end", json!({ "outputs": { "from_start": "{{#s.value#}}" } })),
+            ],
+            edges: vec![edge("e1", "s", "a"), edge("e2", "a", "b")],
+            ..Default::default()
+        };
+        let workflow = Workflow::try_from(&model).unwrap();
+
+        let consumers = workflow.output_consumers();
+        assert_eq!(consumers["s"], HashSet::from(["a".to_string(), "b".to_string()]));
+        assert_eq!(consumers["a"], HashSet::from(["b".to_string()]));
+        assert!(!consumers.contains_key("b"));
+    }
+
+    #[test]
+    fn test_check_references_reports_all() {
+        let model = WorkflowModel {
+            nodes: vec![
+                node("s", "start", json!({})),
+                node(
+                    "a",
+                    "end",
+                    json!({ "outputs": { "from_start": "{{#s.value#}}", "own": "{{#a.value#}}" } }),
+                ),
+                NodeModel {
+                    inputs: Some(json!([{ "variable": "x", "value_selector": "{{#nonexistent.x#}}" }])),
+                    ..node(
+                        "b",
+                        "end",
+                        json!({ "outputs": { "sibling": "{{#a.value#}}", "seeded": "{{#c.value#}}" } }),
+                    )
+                },
+                node("c", "end", json!({})),
+            ],
+            edges: vec![edge("e1", "s", "a"), edge("e2", "s", "b")],
+            ..Default::default()
+        };
+        let workflow = Workflow::try_from(&model).unwrap();
+
+        let err = workflow.check_references(&HashSet::from(["c".to_string()])).unwrap_err().to_string();
+        assert!(err.contains("node 'b' field 'inputs.x': unknown node 'nonexistent'"), "{}", err);
+        assert!(
+            err.contains("node 'b' field 'outputs.sibling': node 'a' does not run before it"),
+            "{}",
+            err
+        );
+        assert!(!err.contains("node 'a' field"), "{}", err);
+        assert!(!err.contains("seeded"), "{}", err);
+
+        let valid = create_workflow(&["a", "b"], &[("a", "b")]);
+        assert!(valid.check_references(&HashSet::new()).is_ok());
+    }
+
+    #[test]
+    fn test_get_node_reuses_action() {
+        let workflow = create_workflow(&["a", "b"], &[("a", "b")]);
+        let first = workflow.get_node(&"a".to_string()).unwrap();
+
+        for _ in 0..10_000 {
+            let node = workflow.get_node(&"a".to_string()).unwrap();
+            assert!(Arc::ptr_eq(&node.action, &first.action));
+        }
+        // Only the graph's node and `first` hold the action
+        assert_eq!(Arc::strong_count(&first.action), 2);
+    }
+
+    #[test]
+    fn test_node_accessors_match_clone() {
+        let model = WorkflowModel {
+            nodes: vec![NodeModel {
+                id: "a".to_string(),
+                uses: "start".to_string(),
+                action: json!({}),
+                timeout: Some(1500),
+                retry: Some(json!({ "times": 3, "interval": 100 })),
+                error_strategy: Some("default_value".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let workflow = Workflow::try_from(&model).unwrap();
+        let id = "a".to_string();
+        let node = workflow.get_node(&id).unwrap();
+
+        assert_eq!(workflow.node_action_type(&id), Some(node.uses));
+        assert_eq!(workflow.node_timeout(&id), node.timeout);
+        assert_eq!(
+            workflow.node_retry(&id).map(|r| (r.times, r.interval)),
+            Some((3.into(), 100.into()))
+        );
+        assert_eq!(workflow.node_error_strategy(&id), Some(ErrorStrategy::DefaultValue));
+        assert_eq!(workflow.get_node_state(&id), Some(node.status));
+
+        let missing = "missing".to_string();
+        assert_eq!(workflow.node_action_type(&missing), None);
+        assert!(workflow.node_retry(&missing).is_none());
+    }
+
+    #[test]
+    fn test_invalid_action_validation_path() {
+        let model = WorkflowModel {
+            nodes: vec![
+                NodeModel {
+                    id: "start".to_string(),
+                    uses: "start".to_string(),
+                    action: json!({}),
+                    ..Default::default()
+                },
+                NodeModel {
+                    id: "check".to_string(),
+                    uses: "if_else".to_string(),
+                    action: json!({ "cases": [{ "case_id": "true", "logical_operator": "xor", "conditions": [] }] }),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        match Workflow::try_from(&model) {
+            Err(ActflowError::Validation {
+                path,
+                ..
+            }) => assert_eq!(path, "/nodes/1/action/cases/0/logical_operator"),
+            _ => panic!("expected a validation error"),
+        }
+    }
+
+    #[cfg(not(all(feature = "js", feature = "python")))]
+    #[test]
+    fn test_code_action_not_compiled_in() {
+        let language = if cfg!(feature = "js") {
+            "python3"
+        } else {
+            "javascript"
+        };
+        let model = WorkflowModel {
+            nodes: vec![NodeModel {
+                id: "code".to_string(),
+                uses: "code".to_string(),
+                action: json!({ "variables": [], "code_language": language, "code": "" }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        match Workflow::try_from(&model) {
+            Err(ActflowError::Node(message)) => assert!(message.ends_with("not compiled in")),
+            _ => panic!("expected a node error"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_action_type_from_json() {
+        let model = WorkflowModel::from_json(
+            r#"{"id": "w", "name": "w", "desc": "", "env": {}, "nodes": [{"id": "n1", "title": "n1", "desc": "", "uses": "send_sms", "action": {}}], "edges": []}"#,
+        )
+        .unwrap();
+
+        match Workflow::try_from(&model) {
+            Err(ActflowError::Node(message)) => assert_eq!(message, "unknown action type 'send_sms' — is the feature enabled?"),
+            _ => panic!("expected a node error"),
+        }
+    }
+
+    /// s -> a -> j, s -> c, c -(false)-> b -> j
+    fn create_conditional_diamond() -> Workflow {
+        let model = WorkflowModel {
+            nodes: vec![
+                node("s", "start", json!({})),
+                node("a", "end", json!({})),
+                node("c", "if_else", json!({ "cases": [] })),
+                node("b", "end", json!({})),
+                node("j", "end", json!({})),
+            ],
+            edges: vec![edge("s-a", "s", "a"), edge("s-c", "s", "c"), branch_edge("c-b", "c", "b", "false"), edge("b-j", "b", "j"), edge("a-j", "a", "j")],
+            ..Default::default()
+        };
+        Workflow::try_from(&model).unwrap()
+    }
+
+    #[test]
+    fn test_skip_branch_unblocks_join() {
+        // the unconditional arm finishes before the condition skips the other arm
+        let workflow = create_conditional_diamond();
+        for nid in ["s", "a", "c"] {
+            workflow.mark_node_executed(&nid.to_string());
+        }
+        assert!(workflow.get_next_ready_node(&"a".to_string(), EdgeSelectOptions::default()).is_empty());
+
+        let skipped = workflow.skip_unselected_branches(&"c".to_string(), &SourceHandle::Fixed(FixedHandle::True));
+        assert_eq!(
+            skipped.into_iter().map(|(nid, _)| nid).collect::<Vec<_>>(),
+            vec!["b".to_string()]
+        );
+        assert_eq!(workflow.get_ready_nodes(), vec!["j".to_string()]);
+    }
+
+    #[test]
+    fn test_skip_branch_join_waits_for_pending_arm() {
+        // the unconditional arm is still running when the other arm is skipped
+        let workflow = create_conditional_diamond();
+        workflow.mark_node_executed(&"s".to_string());
+        workflow.mark_node_taken(&"a".to_string());
+        workflow.mark_node_executed(&"c".to_string());
+
+        workflow.skip_unselected_branches(&"c".to_string(), &SourceHandle::Fixed(FixedHandle::True));
+        assert!(workflow.get_ready_nodes().is_empty());
+
+        workflow.mark_node_executed(&"a".to_string());
+        assert_eq!(
+            workflow.get_next_ready_node(&"a".to_string(), EdgeSelectOptions::default()),
+            vec!["j".to_string()]
+        );
+    }
+}